@@ -0,0 +1,26 @@
+fn main() {
+    println!("cargo:rerun-if-changed=src/cpp/tetgen.cxx");
+    println!("cargo:rerun-if-changed=src/cpp/bindings.cpp");
+    println!("cargo:rerun-if-changed=src/cpp/tetgen.h");
+    println!("cargo:rerun-if-changed=src/cpp/predicates.cxx");
+
+    // TetGen is only linked into native builds; the wasm preview has no mesher
+    if std::env::var_os("CARGO_FEATURE_NATIVE").is_none() {
+        return;
+    }
+
+    let mut build = cc::Build::new();
+    build
+        .cpp(true) // Switch to C++ compiler
+        .file("src/cpp/tetgen.cxx")
+        .file("src/cpp/predicates.cxx")
+        .file("src/cpp/bindings.cpp")
+        .define("TETLIBRARY", None); // Required macro for TetGen
+    // MSVC and GCC/Clang spell the optimization flag differently; each rejects the other's
+    if std::env::var("CARGO_CFG_TARGET_ENV").as_deref() == Ok("msvc") {
+        build.flag("/O2");
+    } else {
+        build.flag("-O3");
+    }
+    build.compile("tetgen_lib");
+}
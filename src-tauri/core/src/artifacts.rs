@@ -0,0 +1,225 @@
+//! Index of every file the backend generates (exports, .geo, .msh, FEA results).
+//!
+//! Records are appended as JSON lines to `artifacts.jsonl` in the app data dir. The
+//! in-memory copy answers queries immediately; a writer thread persists new records
+//! so exports and meshing never wait on disk. Only the newest `MAX_RECORDS` are kept:
+//! once the file holds twice that, the writer rewrites it with just the newest ones.
+//!
+//! The index itself needs threads and uuids, so it only exists in `native` builds;
+//! the record types and `project_hash` are shared with the wasm preview.
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "native")]
+use std::{
+    collections::VecDeque,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    sync::mpsc::{self, Sender},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// One file produced by a command, with the inputs it came from.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ArtifactRecord {
+    /// Random UUID
+    pub id: String,
+    /// "export", "geo", "msh", "result", "optimization"
+    pub kind: String,
+    /// Where the file was written
+    pub path: String,
+    /// Hash of the project state that produced it
+    pub project_hash: String,
+    /// Command-specific inputs worth keeping with the file
+    pub params: serde_json::Value,
+    /// Unix seconds
+    pub created_at: u64,
+}
+
+/// Filter for `query_artifacts`. All fields are optional; results are newest first.
+#[derive(Debug, Deserialize, Default)]
+pub struct ArtifactQuery {
+    /// Exact `kind`
+    pub kind: Option<String>,
+    /// Exact `project_hash`
+    pub project_hash: Option<String>,
+    /// Substring of `path`
+    pub path_contains: Option<String>,
+    /// Created at or after (Unix seconds)
+    pub since: Option<u64>,
+    /// Created at or before (Unix seconds)
+    pub until: Option<u64>,
+    /// Maximum number of records returned
+    pub limit: Option<usize>,
+}
+
+/// Records kept in memory and on disk. Well beyond a few months of heavy use.
+#[cfg(feature = "native")]
+pub const MAX_RECORDS: usize = 10_000;
+
+/// In-memory list of artifact records, appended to a JSONL file in the background.
+#[cfg(feature = "native")]
+pub struct ArtifactIndex {
+    records: Mutex<VecDeque<ArtifactRecord>>,
+    writer: Option<Sender<ArtifactRecord>>,
+}
+
+#[cfg(feature = "native")]
+impl ArtifactIndex {
+    /// Loads existing records from `index_path` and starts the background writer.
+    /// Without a path the index only lives for the session.
+    pub fn open(index_path: Option<PathBuf>) -> Self {
+        let (records, lines) = index_path.as_deref().map(read_records).unwrap_or_default();
+        let records: VecDeque<ArtifactRecord> = newest(records, MAX_RECORDS).into();
+
+        let writer = index_path.map(|path| {
+            let (tx, rx) = mpsc::channel::<ArtifactRecord>();
+            // Drop stale or unreadable lines left by earlier sessions before appending
+            let mut on_disk = if lines > records.len() { compact(&path, MAX_RECORDS) } else { lines };
+            std::thread::spawn(move || {
+                for record in rx {
+                    match append_record(&path, &record) {
+                        Ok(()) => on_disk += 1,
+                        Err(e) => eprintln!("Failed to persist artifact record: {}", e),
+                    }
+                    if on_disk >= 2 * MAX_RECORDS {
+                        on_disk = compact(&path, MAX_RECORDS);
+                    }
+                }
+            });
+            tx
+        });
+
+        Self { records: Mutex::new(records), writer }
+    }
+
+    /// Adds a record for `path` and queues it for persisting.
+    pub fn record(&self, kind: &str, path: &Path, project_hash: &str, params: serde_json::Value) -> ArtifactRecord {
+        let record = ArtifactRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            path: path.to_string_lossy().into_owned(),
+            project_hash: project_hash.to_string(),
+            params,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+        };
+
+        let mut records = self.records.lock().unwrap();
+        if records.len() >= MAX_RECORDS {
+            records.pop_front();
+        }
+        records.push_back(record.clone());
+        drop(records);
+        if let Some(writer) = &self.writer {
+            let _ = writer.send(record.clone());
+        }
+        record
+    }
+
+    /// Records matching `q`, newest first.
+    pub fn query(&self, q: &ArtifactQuery) -> Vec<ArtifactRecord> {
+        let records = self.records.lock().unwrap();
+        records.iter()
+            .rev()
+            .filter(|r| q.kind.as_ref().is_none_or(|k| &r.kind == k))
+            .filter(|r| q.project_hash.as_ref().is_none_or(|h| &r.project_hash == h))
+            .filter(|r| q.path_contains.as_ref().is_none_or(|s| r.path.contains(s.as_str())))
+            .filter(|r| q.since.is_none_or(|t| r.created_at >= t))
+            .filter(|r| q.until.is_none_or(|t| r.created_at <= t))
+            .take(q.limit.unwrap_or(usize::MAX))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Parsed records of an index file, oldest first, and its number of lines.
+#[cfg(feature = "native")]
+fn read_records(path: &Path) -> (Vec<ArtifactRecord>, usize) {
+    let Ok(content) = std::fs::read_to_string(path) else { return (Vec::new(), 0) };
+    let records = content.lines().filter_map(|l| serde_json::from_str(l).ok()).collect();
+    (records, content.lines().count())
+}
+
+#[cfg(feature = "native")]
+fn newest(mut records: Vec<ArtifactRecord>, keep: usize) -> Vec<ArtifactRecord> {
+    records.drain(..records.len().saturating_sub(keep));
+    records
+}
+
+/// Rewrites the index with its newest `keep` readable records, through a temporary file so
+/// a crash never leaves it half written. Returns the number of lines now on disk.
+#[cfg(feature = "native")]
+fn compact(path: &Path, keep: usize) -> usize {
+    let (records, lines) = read_records(path);
+    let records = newest(records, keep);
+    let tmp = path.with_extension("jsonl.tmp");
+    let written = std::fs::File::create(&tmp).map_err(|e| e.to_string()).and_then(|mut file| {
+        for record in &records {
+            let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+            writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+        }
+        std::fs::rename(&tmp, path).map_err(|e| e.to_string())
+    });
+    match written {
+        Ok(()) => records.len(),
+        Err(e) => {
+            eprintln!("Failed to compact artifact index: {}", e);
+            lines
+        }
+    }
+}
+
+#[cfg(feature = "native")]
+fn append_record(path: &Path, record: &ArtifactRecord) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+    let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| e.to_string())?;
+    writeln!(file, "{}", line).map_err(|e| e.to_string())
+}
+
+/// Stable content hash (FNV-1a over the JSON encoding) used to group artifacts by geometry.
+pub fn project_hash<T: Serialize + ?Sized>(value: &T) -> String {
+    let json = serde_json::to_vec(value).unwrap_or_default();
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in json {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+#[cfg(all(test, feature = "native"))]
+mod tests {
+    use super::*;
+
+    fn record(i: u64) -> ArtifactRecord {
+        ArtifactRecord {
+            id: i.to_string(),
+            kind: "export".into(),
+            path: format!("/tmp/out_{}.svg", i),
+            project_hash: project_hash(&i),
+            params: serde_json::Value::Null,
+            created_at: i,
+        }
+    }
+
+    #[test]
+    fn test_compact_keeps_newest_readable_records() {
+        let path = std::env::temp_dir().join(format!("shortstack_artifacts_{}.jsonl", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        for i in 0..5 {
+            append_record(&path, &record(i)).unwrap();
+        }
+        std::fs::OpenOptions::new().append(true).open(&path).unwrap().write_all(b"{truncated\n").unwrap();
+
+        assert_eq!(compact(&path, 3), 3);
+        let (records, lines) = read_records(&path);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(lines, 3);
+        let ids: Vec<&str> = records.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, ["2", "3", "4"]);
+    }
+}
@@ -0,0 +1,277 @@
+//! Centre of mass of the assembled stack and its margin over the support points.
+use crate::export::{ExportRequest, discretize_path_closed, shape_to_polygon};
+use crate::messages::{Message, MessageCode};
+use csgrs::sketch::Sketch;
+use csgrs::traits::CSG;
+use geo::{Area, Centroid, ConvexHull, Contains, Distance, Euclidean, MultiPoint, Point, Polygon};
+use serde::{Deserialize, Serialize};
+
+/// One layer of the stack, bottom first. Layers sit directly on top of each other.
+#[derive(Debug, Deserialize)]
+pub struct BalanceLayer {
+    /// Layer geometry and thickness
+    pub request: ExportRequest,
+    /// Material density (g/cm^3)
+    pub density: f64,
+}
+
+/// A component not modelled as a layer, e.g. a battery or motor.
+#[derive(Debug, Deserialize, Clone)]
+pub struct PointMass {
+    /// Mass (g)
+    pub mass: f64,
+    /// Position (mm), z measured from the bottom of the stack
+    pub position: [f64; 3],
+}
+
+/// A variant of the assembly, e.g. "with battery" / "without battery".
+#[derive(Debug, Deserialize, Clone)]
+pub struct BalanceConfiguration {
+    /// Label shown in the report
+    pub name: String,
+    /// Extra masses present in this configuration
+    #[serde(default)]
+    pub point_masses: Vec<PointMass>,
+}
+
+/// Input of `balance_report`.
+#[derive(Debug, Deserialize)]
+pub struct BalanceRequest {
+    /// The stack, bottom layer first
+    pub layers: Vec<BalanceLayer>,
+    /// Configurations to evaluate; each gets one `BalanceResult`
+    pub configurations: Vec<BalanceConfiguration>,
+    /// Contact points on the ground; defaults to the bottom layer's outline
+    #[serde(default)]
+    pub supports: Vec<[f64; 2]>,
+}
+
+/// Balance of one configuration.
+#[derive(Debug, Serialize, Clone)]
+pub struct BalanceResult {
+    /// `BalanceConfiguration::name`
+    pub name: String,
+    /// Total mass (g)
+    pub mass: f64,
+    /// Centre of mass (mm)
+    pub center_of_mass: [f64; 3],
+    /// Centre of mass projects inside the support polygon
+    pub stable: bool,
+    /// Distance to the nearest support edge; negative when outside
+    pub tip_margin: f64,
+    /// Degrees of tilt before it falls over, when stable
+    pub tip_angle: Option<f64>,
+}
+
+/// Mass-weighted sum of positions, so pieces can be added before dividing.
+#[derive(Default, Clone, Copy)]
+struct MassSum {
+    mass: f64,
+    moment: [f64; 3],
+}
+
+impl MassSum {
+    fn add(&mut self, mass: f64, at: [f64; 3]) {
+        self.mass += mass;
+        for (m, x) in self.moment.iter_mut().zip(at) {
+            *m += mass * x;
+        }
+    }
+
+    fn merge(&mut self, other: MassSum) {
+        self.mass += other.mass;
+        for (m, o) in self.moment.iter_mut().zip(other.moment) {
+            *m += o;
+        }
+    }
+}
+
+fn sketch_polygons(sketch: &Sketch<()>) -> Vec<Polygon<f64>> {
+    let mut polys = Vec::new();
+    for geom in &sketch.geometry {
+        match geom {
+            geo::Geometry::Polygon(p) => polys.push(p.clone()),
+            geo::Geometry::MultiPolygon(mp) => polys.extend(mp.0.iter().cloned()),
+            _ => {}
+        }
+    }
+    polys
+}
+
+/// Mass and moment of one layer. The layer is sliced at every distinct pocket depth;
+/// each slab is the outline minus the cuts reaching through it, so stepped pockets are
+/// weighed correctly without meshing.
+fn layer_mass(layer: &BalanceLayer, z0: f64) -> MassSum {
+    let req = &layer.request;
+    let t = req.layer_thickness;
+    let to_sketch = |p: Polygon<f64>| Sketch::<()>::from_geo(geo::Geometry::Polygon(p).into(), None);
+    let through_all = req.machining_type == "Cut";
+
+    let cuts: Vec<(f64, Polygon<f64>)> = req.shapes.iter()
+        .filter(|s| s.depth > 1e-6)
+        .filter_map(|s| shape_to_polygon(s).map(|p| (if through_all { t } else { s.depth.min(t) }, p)))
+        .collect();
+
+    let mut levels: Vec<f64> = cuts.iter().map(|(d, _)| *d).chain([t]).collect();
+    levels.sort_by(f64::total_cmp);
+    levels.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+
+    let outline = to_sketch(Polygon::new(discretize_path_closed(&req.outline), vec![]));
+    let mut sum = MassSum::default();
+    let mut top = 0.0;
+    for &bottom in &levels {
+        // Slab between depths `top` and `bottom`, measured from the cut face
+        let mut material = outline.clone();
+        for (depth, poly) in &cuts {
+            if *depth >= bottom - 1e-6 {
+                material = material.difference(&to_sketch(poly.clone()));
+            }
+        }
+        let polys = geo::MultiPolygon::new(sketch_polygons(&material));
+        let area = polys.unsigned_area();
+        if let Some(c) = polys.centroid() && area > 0.0 {
+            let mid = (top + bottom) / 2.0;
+            let z = if req.cut_direction == "Bottom" { z0 + mid } else { z0 + t - mid };
+            // mm^3 -> cm^3
+            sum.add(area * (bottom - top) * layer.density / 1000.0, [c.x(), c.y(), z]);
+        }
+        top = bottom;
+    }
+    sum
+}
+
+/// Centre of mass for every configuration and how far it sits inside the support polygon
+/// (the convex hull of `supports`). `tip_angle` is the tilt about the nearest support
+/// edge at which the centre of mass passes over it.
+pub fn balance_report(request: &BalanceRequest) -> Result<Vec<BalanceResult>, Message> {
+    if request.layers.is_empty() {
+        return Err(Message::new(MessageCode::BalanceNoLayers, "No layers to weigh"));
+    }
+    if let Some(i) = request.layers.iter().position(|l| l.request.outline.is_empty()) {
+        return Err(Message::new(MessageCode::OutlineMissing, format!("Layer {} has no outline", i)).with("layer", i));
+    }
+
+    let mut stack = MassSum::default();
+    let mut z = 0.0;
+    for layer in &request.layers {
+        stack.merge(layer_mass(layer, z));
+        z += layer.request.layer_thickness;
+    }
+
+    let support = if request.supports.is_empty() {
+        Polygon::new(discretize_path_closed(&request.layers[0].request.outline), vec![]).convex_hull()
+    } else {
+        MultiPoint::from(request.supports.iter().map(|p| Point::new(p[0], p[1])).collect::<Vec<_>>()).convex_hull()
+    };
+
+    let mut results = Vec::new();
+    for config in &request.configurations {
+        let mut total = stack;
+        for pm in &config.point_masses {
+            total.add(pm.mass, pm.position);
+        }
+        if total.mass <= 0.0 {
+            return Err(Message::new(MessageCode::BalanceNoMass, format!("Configuration '{}' has no mass", config.name))
+                .with("configuration", config.name.as_str()));
+        }
+        let com = total.moment.map(|m| m / total.mass);
+
+        let ground = Point::new(com[0], com[1]);
+        let edge_distance = Euclidean::distance(&ground, support.exterior());
+        let stable = support.unsigned_area() > 0.0 && support.contains(&ground);
+        let tip_margin = if stable { edge_distance } else { -edge_distance };
+        let tip_angle = (stable && com[2] > 0.0).then(|| tip_margin.atan2(com[2]).to_degrees());
+
+        results.push(BalanceResult {
+            name: config.name.clone(),
+            mass: total.mass,
+            center_of_mass: com,
+            stable,
+            tip_margin,
+            tip_angle,
+        });
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use serde_json::json;
+
+    /// 100 x 40 x 10 mm plate of density 1, so 40 g with its centre at (50, 20, 5).
+    fn plate(machining_type: &str, shapes: serde_json::Value) -> BalanceLayer {
+        let corner = |x: f64, y: f64| json!({ "x": x, "y": y, "handle_in": null, "handle_out": null });
+        serde_json::from_value(json!({
+            "density": 1.0,
+            "request": {
+                "filepath": "", "file_type": "SVG", "machining_type": machining_type, "cut_direction": "Top",
+                "outline": [corner(0.0, 0.0), corner(100.0, 0.0), corner(100.0, 40.0), corner(0.0, 40.0)],
+                "shapes": shapes, "layer_thickness": 10.0, "stl_content": null,
+            }
+        })).unwrap()
+    }
+
+    fn config(name: &str, point_masses: Vec<PointMass>) -> BalanceConfiguration {
+        BalanceConfiguration { name: name.into(), point_masses }
+    }
+
+    #[test]
+    fn test_plate_center_and_tip_angle() {
+        let request = BalanceRequest {
+            layers: vec![plate("Cut", json!([]))],
+            configurations: vec![
+                config("bare", vec![]),
+                config("loaded", vec![PointMass { mass: 40.0, position: [100.0, 20.0, 10.0] }]),
+                config("overhang", vec![PointMass { mass: 200.0, position: [150.0, 20.0, 10.0] }]),
+            ],
+            supports: vec![],
+        };
+        let results = balance_report(&request).unwrap();
+
+        let bare = &results[0];
+        assert_relative_eq!(bare.mass, 40.0, epsilon = 1e-9);
+        for (c, e) in bare.center_of_mass.iter().zip([50.0, 20.0, 5.0]) {
+            assert_relative_eq!(*c, e, epsilon = 1e-9);
+        }
+        // Nearest edge is a long side, 20 mm away, 5 mm below the centre of mass
+        assert!(bare.stable);
+        assert_relative_eq!(bare.tip_margin, 20.0, epsilon = 1e-9);
+        assert_relative_eq!(bare.tip_angle.unwrap(), 20f64.atan2(5.0).to_degrees(), epsilon = 1e-9);
+
+        // Equal mass on the right edge, on top: centre moves to (75, 20, 7.5)
+        let loaded = &results[1];
+        assert_relative_eq!(loaded.mass, 80.0, epsilon = 1e-9);
+        for (c, e) in loaded.center_of_mass.iter().zip([75.0, 20.0, 7.5]) {
+            assert_relative_eq!(*c, e, epsilon = 1e-9);
+        }
+        assert_relative_eq!(loaded.tip_angle.unwrap(), 20f64.atan2(7.5).to_degrees(), epsilon = 1e-9);
+
+        // (40 * 50 + 200 * 150) / 240 = 133.3, past the x = 100 edge
+        let overhang = &results[2];
+        assert!(!overhang.stable);
+        assert_relative_eq!(overhang.tip_margin, -(32000.0 / 240.0 - 100.0), epsilon = 1e-9);
+        assert_eq!(overhang.tip_angle, None);
+    }
+
+    #[test]
+    fn test_pocket_lowers_mass_and_centre() {
+        // 20 x 20 pocket, 5 mm deep from the top face: 2 g removed from z = 7.5
+        let pocket = json!([{
+            "shape_type": "rect", "x": 50.0, "y": 20.0, "width": 20.0, "height": 20.0, "diameter": null,
+            "angle": null, "corner_radius": null, "thickness": null, "points": null, "depth": 5.0,
+            "endmill_radius": null, "feed": null, "speed": null, "power": null,
+        }]);
+        let request = BalanceRequest {
+            layers: vec![plate("Carved/Printed", pocket)],
+            configurations: vec![config("bare", vec![])],
+            supports: vec![],
+        };
+        let result = &balance_report(&request).unwrap()[0];
+
+        assert_relative_eq!(result.mass, 38.0, epsilon = 1e-6);
+        assert_relative_eq!(result.center_of_mass[0], 50.0, epsilon = 1e-6);
+        assert_relative_eq!(result.center_of_mass[2], (40.0 * 5.0 - 2.0 * 7.5) / 38.0, epsilon = 1e-6);
+    }
+}
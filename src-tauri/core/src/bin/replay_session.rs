@@ -0,0 +1,54 @@
+//! Replays a recorded session outside the app:
+//!
+//!     cargo run -p shortstack-core --release --bin replay-session -- session.jsonl [work_dir]
+//!
+//! `$DIR` paths map to `work_dir` (default: `replay_<session>` next to the session file);
+//! copy any files the session read (traced images, depth maps) there first. Meshing commands
+//! run when GMSH_PATH points at a Gmsh executable. The full report is written to
+//! `work_dir/replay_report.json`; the exit code is 1 when any command failed.
+use shortstack_core::session::{self, ReplayOptions, ReplayStatus};
+use std::path::PathBuf;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let Some(session_path) = args.next().map(PathBuf::from) else {
+        eprintln!("Usage: replay-session <session.jsonl> [work_dir]");
+        std::process::exit(2);
+    };
+    let work_dir = args.next().map(PathBuf::from).unwrap_or_else(|| {
+        let stem = session_path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        session_path.with_file_name(format!("replay_{}", stem))
+    });
+    let options = ReplayOptions { gmsh: std::env::var_os("GMSH_PATH").map(PathBuf::from) };
+
+    let report = session::load_session(&session_path)
+        .and_then(|entries| session::replay(&entries, &work_dir, &options))
+        .unwrap_or_else(|e| {
+            eprintln!("Replay failed: {}", e);
+            std::process::exit(2);
+        });
+
+    for o in &report.outcomes {
+        let status = match o.status {
+            ReplayStatus::Ok => "ok",
+            ReplayStatus::Error => "ERROR",
+            ReplayStatus::Skipped => "skipped",
+        };
+        println!("{:>4} {:<28} {:<8} {:>7} ms", o.seq, o.command, status, o.millis);
+        if let Some(e) = &o.error {
+            println!("       {}", e);
+        }
+    }
+    println!("{} commands, {} errors, {} skipped", report.outcomes.len(), report.errors, report.skipped);
+
+    let report_path = work_dir.join("replay_report.json");
+    match serde_json::to_string_pretty(&report) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&report_path, json) {
+                eprintln!("Failed to write {}: {}", report_path.display(), e);
+            }
+        }
+        Err(e) => eprintln!("Failed to serialize report: {}", e),
+    }
+    std::process::exit(if report.errors > 0 { 1 } else { 0 });
+}
@@ -0,0 +1,257 @@
+//! Calibration grid matched to the board outline, for aligning laser-camera overlays.
+use crate::export::{ExportPoint, discretize_path_closed, polygon_to_path_data, write_dxf_circle, write_dxf_file, write_dxf_line, write_dxf_polygon};
+use crate::messages::{Message, MessageCode};
+use geo::{BoundingRect, Contains, Coord, LineString, Polygon};
+use serde::{Deserialize, Serialize};
+use svg::Document;
+use svg::node::element::{Circle, Line, Path};
+
+/// A camera/laser calibration grid to export over a board outline.
+#[derive(Debug, Deserialize)]
+pub struct CalibrationRequest {
+    /// Destination file
+    pub filepath: String,
+    /// "SVG" or "DXF"
+    pub file_type: String,
+    /// Board outline the grid is clipped to
+    pub outline: Vec<ExportPoint>,
+    /// "dots" or "checker"
+    #[serde(default = "default_pattern")]
+    pub pattern: String,
+    /// mm between grid features
+    pub spacing: f64,
+    /// Defaults to a quarter of the spacing
+    #[serde(default)]
+    pub dot_diameter: Option<f64>,
+    /// Scope for sandbox approvals
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+fn default_pattern() -> String { "dots".into() }
+
+/// Where the features ended up, so overlay software can match them to what the camera sees.
+#[derive(Debug, Serialize, Clone)]
+pub struct CalibrationSummary {
+    /// Dot centres, or inner corners of the checkerboard
+    pub features: Vec<[f64; 2]>,
+    /// Crosshair centres: min/min, max/min, min/max corners
+    pub fiducials: Vec<[f64; 2]>,
+    /// Grid spacing used (mm)
+    pub spacing: f64,
+}
+
+struct Grid {
+    board: Polygon<f64>,
+    dots: Vec<(Coord<f64>, f64)>,
+    squares: Vec<Polygon<f64>>,
+    fiducials: Vec<Coord<f64>>,
+    fiducial_size: f64,
+    summary: CalibrationSummary,
+}
+
+fn square(x: f64, y: f64, size: f64) -> Polygon<f64> {
+    Polygon::new(LineString::from(vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size), (x, y)]), vec![])
+}
+
+/// Lays the grid out on multiples of `spacing` from the outline's lower-left corner and
+/// keeps only features fully inside the board. Fiducials sit on three corners of the
+/// bounding box (the missing fourth fixes orientation) and are kept clear of the grid.
+fn build_grid(request: &CalibrationRequest) -> Result<Grid, Message> {
+    if request.outline.len() < 3 {
+        return Err(Message::new(MessageCode::OutlineTooFewPoints, "Board outline needs at least 3 points").with("min_points", 3));
+    }
+    if request.spacing <= 0.0 {
+        return Err(Message::not_positive("spacing", "Grid spacing must be positive"));
+    }
+    let s = request.spacing;
+    let board = Polygon::new(discretize_path_closed(&request.outline), vec![]);
+    let rect = board.bounding_rect().ok_or_else(Message::outline_degenerate)?;
+    let (min, max) = (rect.min(), rect.max());
+
+    let fiducial_size = s;
+    let fiducials = vec![
+        Coord { x: min.x + s, y: min.y + s },
+        Coord { x: max.x - s, y: min.y + s },
+        Coord { x: min.x + s, y: max.y - s },
+    ];
+    let clear_of_fiducials = |c: Coord<f64>| fiducials.iter().all(|f| (c.x - f.x).abs() > 1.5 * s || (c.y - f.y).abs() > 1.5 * s);
+
+    let cols = (rect.width() / s).floor() as i64;
+    let rows = (rect.height() / s).floor() as i64;
+    let mut dots = Vec::new();
+    let mut squares = Vec::new();
+    let mut features = Vec::new();
+
+    if request.pattern == "checker" {
+        for j in 0..rows {
+            for i in 0..cols {
+                let sq = square(min.x + i as f64 * s, min.y + j as f64 * s, s);
+                let centre = Coord { x: min.x + (i as f64 + 0.5) * s, y: min.y + (j as f64 + 0.5) * s };
+                if !board.contains(&sq) || !clear_of_fiducials(centre) { continue; }
+                if (i + j) % 2 == 0 {
+                    squares.push(sq);
+                }
+                // The corner a square shares with its diagonal neighbour is the detectable feature
+                features.push([min.x + (i + 1) as f64 * s, min.y + (j + 1) as f64 * s]);
+            }
+        }
+        // A corner only exists where all four squares around it were kept
+        let kept: std::collections::HashSet<(i64, i64)> = features.iter()
+            .map(|f| (((f[0] - min.x) / s).round() as i64, ((f[1] - min.y) / s).round() as i64))
+            .collect();
+        features.retain(|f| {
+            let (i, j) = (((f[0] - min.x) / s).round() as i64, ((f[1] - min.y) / s).round() as i64);
+            kept.contains(&(i + 1, j)) && kept.contains(&(i, j + 1)) && kept.contains(&(i + 1, j + 1))
+        });
+    } else {
+        let r = request.dot_diameter.unwrap_or(s / 4.0) / 2.0;
+        for j in 0..=rows {
+            for i in 0..=cols {
+                let c = Coord { x: min.x + i as f64 * s, y: min.y + j as f64 * s };
+                if !board.contains(&square(c.x - r, c.y - r, 2.0 * r)) || !clear_of_fiducials(c) { continue; }
+                dots.push((c, r));
+                features.push([c.x, c.y]);
+            }
+        }
+    }
+
+    let summary = CalibrationSummary {
+        features,
+        fiducials: fiducials.iter().map(|f| [f.x, f.y]).collect(),
+        spacing: s,
+    };
+    Ok(Grid { board, dots, squares, fiducials, fiducial_size, summary })
+}
+
+fn write_svg(path: &str, grid: &Grid) -> Result<(), Box<dyn std::error::Error>> {
+    // Same Y-down flip as the profile export so the two overlay exactly
+    let flip = |c: Coord<f64>| Coord { x: c.x, y: -c.y };
+    let board = geo::MapCoords::map_coords(&grid.board, flip);
+    let rect = board.bounding_rect().ok_or("Board outline is degenerate")?;
+
+    let mut document = Document::new()
+        .set("viewBox", format!("{} {} {} {}", rect.min().x, rect.min().y, rect.width(), rect.height()))
+        .set("width", format!("{}mm", rect.width()))
+        .set("height", format!("{}mm", rect.height()))
+        .set("xmlns", "http://www.w3.org/2000/svg");
+
+    document = document.add(Path::new()
+        .set("fill", "none")
+        .set("stroke", "black")
+        .set("stroke-width", "0.1mm")
+        .set("d", polygon_to_path_data(&board)));
+
+    for (c, r) in &grid.dots {
+        document = document.add(Circle::new().set("cx", c.x).set("cy", -c.y).set("r", *r).set("fill", "black"));
+    }
+    for sq in &grid.squares {
+        let sq = geo::MapCoords::map_coords(sq, flip);
+        document = document.add(Path::new().set("fill", "black").set("stroke", "none").set("d", polygon_to_path_data(&sq)));
+    }
+
+    let h = grid.fiducial_size / 2.0;
+    for f in &grid.fiducials {
+        document = document
+            .add(Line::new().set("x1", f.x - h).set("y1", -f.y).set("x2", f.x + h).set("y2", -f.y).set("stroke", "blue").set("stroke-width", "0.1mm"))
+            .add(Line::new().set("x1", f.x).set("y1", -f.y - h).set("x2", f.x).set("y2", -f.y + h).set("stroke", "blue").set("stroke-width", "0.1mm"))
+            .add(Circle::new().set("cx", f.x).set("cy", -f.y).set("r", h / 2.0).set("fill", "none").set("stroke", "blue").set("stroke-width", "0.1mm"));
+    }
+
+    svg::save(path, &document)?;
+    Ok(())
+}
+
+fn write_dxf(path: &str, grid: &Grid) -> Result<(), Box<dyn std::error::Error>> {
+    write_dxf_file(path, None, |file, owner, next_handle| {
+        write_dxf_polygon(file, &grid.board, "OUTLINE", 7, owner, next_handle)?;
+        for (c, r) in &grid.dots {
+            write_dxf_circle(file, c.x, c.y, *r, "GRID", 7, owner, next_handle)?;
+        }
+        for sq in &grid.squares {
+            write_dxf_polygon(file, sq, "GRID", 7, owner, next_handle)?;
+        }
+        let h = grid.fiducial_size / 2.0;
+        for f in &grid.fiducials {
+            write_dxf_line(file, Coord { x: f.x - h, y: f.y }, Coord { x: f.x + h, y: f.y }, "FIDUCIALS", 5, owner, next_handle)?;
+            write_dxf_line(file, Coord { x: f.x, y: f.y - h }, Coord { x: f.x, y: f.y + h }, "FIDUCIALS", 5, owner, next_handle)?;
+            write_dxf_circle(file, f.x, f.y, h / 2.0, "FIDUCIALS", 5, owner, next_handle)?;
+        }
+        Ok(())
+    })
+}
+
+/// Writes the grid in `request.file_type` and returns the feature positions (mm, board
+/// coordinates, y up).
+pub fn export_calibration_grid(request: &CalibrationRequest) -> Result<CalibrationSummary, Message> {
+    let grid = build_grid(request)?;
+    match request.file_type.as_str() {
+        "SVG" => write_svg(&request.filepath, &grid),
+        "DXF" => write_dxf(&request.filepath, &grid),
+        other => {
+            return Err(Message::new(MessageCode::UnsupportedFormat, format!("Unsupported calibration format: {}", other))
+                .with("format", other));
+        }
+    }.map_err(|e| Message::write_failed(&request.filepath, e))?;
+    Ok(grid.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(pattern: &str, file_type: &str, filepath: &str) -> CalibrationRequest {
+        let outline = [[0.0, 0.0], [100.0, 0.0], [100.0, 60.0], [0.0, 60.0]].iter()
+            .map(|p| ExportPoint { x: p[0], y: p[1], handle_in: None, handle_out: None })
+            .collect();
+        CalibrationRequest {
+            filepath: filepath.into(),
+            file_type: file_type.into(),
+            outline,
+            pattern: pattern.into(),
+            spacing: 10.0,
+            dot_diameter: None,
+            project_id: None,
+        }
+    }
+
+    #[test]
+    fn test_dot_grid_skips_edges_and_fiducials() {
+        let grid = build_grid(&request("dots", "SVG", "")).unwrap();
+        assert_eq!(grid.summary.fiducials, vec![[10.0, 10.0], [90.0, 10.0], [10.0, 50.0]]);
+        // 9 x 5 interior dots, less the 2 x 2 block around each fiducial
+        assert_eq!(grid.summary.features.len(), 45 - 12);
+        assert!(!grid.summary.features.contains(&[20.0, 20.0]));
+        assert!(grid.summary.features.contains(&[30.0, 30.0]));
+        assert!(grid.summary.features.contains(&[90.0, 50.0]));
+    }
+
+    #[test]
+    fn test_checker_corners_sit_between_filled_diagonals() {
+        let grid = build_grid(&request("checker", "SVG", "")).unwrap();
+        assert!(!grid.summary.features.is_empty());
+        for f in &grid.summary.features {
+            // Of the four squares meeting at an inner corner, the two diagonal ones are filled
+            let filled = [[-10.0, -10.0], [0.0, -10.0], [-10.0, 0.0], [0.0, 0.0]].iter()
+                .filter(|d| grid.squares.iter().any(|sq| {
+                    let c = sq.exterior().0[0];
+                    (c.x - (f[0] + d[0])).abs() < 1e-9 && (c.y - (f[1] + d[1])).abs() < 1e-9
+                }))
+                .count();
+            assert_eq!(filled, 2, "corner {:?}", f);
+        }
+    }
+
+    #[test]
+    fn test_svg_export_and_unsupported_format() {
+        let path = std::env::temp_dir().join(format!("shortstack_calibration_{}.svg", std::process::id()));
+        let summary = export_calibration_grid(&request("dots", "SVG", path.to_str().unwrap())).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        // One circle per dot plus the ring of each fiducial
+        assert_eq!(svg.matches("<circle").count(), summary.features.len() + 3);
+
+        let err = export_calibration_grid(&request("dots", "PNG", "")).unwrap_err();
+        assert_eq!(err.code, MessageCode::UnsupportedFormat);
+    }
+}
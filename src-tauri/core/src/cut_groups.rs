@@ -0,0 +1,71 @@
+//! Groups profile-cut shapes by their per-shape feed/speed/power overrides, so each group
+//! can be written on its own layer / colour and run at its own settings.
+use crate::export::ExportShape;
+
+/// A shape's machine setting overrides; all None means the layer's settings.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CutSettings {
+    /// See `ExportShape::feed`
+    pub feed: Option<f64>,
+    /// See `ExportShape::speed`
+    pub speed: Option<f64>,
+    /// See `ExportShape::power`
+    pub power: Option<f64>,
+}
+
+/// Stroke colours for override groups; laser software maps each colour to its own settings.
+/// Shapes without overrides keep the layer's usual red.
+const GROUP_COLORS: [&str; 6] = ["blue", "green", "magenta", "orange", "cyan", "purple"];
+
+impl CutSettings {
+    /// The overrides set on `shape`.
+    pub fn of(shape: &ExportShape) -> Self {
+        CutSettings { feed: shape.feed, speed: shape.speed, power: shape.power }
+    }
+
+    /// True when nothing is overridden.
+    pub fn is_default(&self) -> bool {
+        *self == CutSettings::default()
+    }
+
+    /// "F300_P40" style suffix for DXF layer names.
+    pub fn label(&self) -> String {
+        [("F", self.feed), ("S", self.speed), ("P", self.power)].iter()
+            .filter_map(|(k, v)| v.map(|v| format!("{}{}", k, v)))
+            .collect::<Vec<_>>()
+            .join("_")
+    }
+
+    /// DXF layer holding this group's cuts.
+    pub fn dxf_layer(&self) -> String {
+        if self.is_default() { "CUTS".into() } else { format!("CUTS_{}", self.label()) }
+    }
+
+    /// SVG stroke colour for this group; `index` is its position among the override groups.
+    pub fn svg_color(&self, index: usize) -> &'static str {
+        if self.is_default() { "red" } else { GROUP_COLORS[index % GROUP_COLORS.len()] }
+    }
+
+    /// `data-*` attributes recording the overrides on exported SVG elements.
+    pub fn svg_attributes(&self) -> Vec<(&'static str, f64)> {
+        [("data-feed", self.feed), ("data-speed", self.speed), ("data-power", self.power)].iter()
+            .filter_map(|(k, v)| v.map(|v| (*k, v)))
+            .collect()
+    }
+}
+
+/// Splits `shapes` into groups with identical settings, the no-override group first and the
+/// rest in order of first appearance. Empty groups are dropped. Overlapping shapes in
+/// different groups are not merged, so each is cut at its own settings.
+pub fn group_by_settings(shapes: &[ExportShape]) -> Vec<(CutSettings, Vec<ExportShape>)> {
+    let mut groups: Vec<(CutSettings, Vec<ExportShape>)> = vec![(CutSettings::default(), Vec::new())];
+    for shape in shapes {
+        let settings = CutSettings::of(shape);
+        match groups.iter_mut().find(|(g, _)| *g == settings) {
+            Some((_, members)) => members.push(shape.clone()),
+            None => groups.push((settings, vec![shape.clone()])),
+        }
+    }
+    groups.retain(|(_, members)| !members.is_empty());
+    groups
+}
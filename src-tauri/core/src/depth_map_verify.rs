@@ -0,0 +1,336 @@
+//! Reads a depth-map SVG/PNG back into depths and compares it with the shapes it was made from.
+use crate::export::{ExportRequest, get_board_and_shapes_expanded};
+use crate::export_verify;
+use crate::messages::{Message, MessageCode};
+use geo::{BoundingRect, Contains, Coord, LineString, MapCoords, Point, Polygon, Rect};
+use serde::Serialize;
+use svg::parser::Event;
+
+/// Grid samples along the longer board side when no resolution is given.
+const DEFAULT_SAMPLES_PER_SIDE: f64 = 400.0;
+
+/// Connected patch of samples whose read-back depth is off by more than the tolerance.
+#[derive(Debug, Serialize, Clone)]
+pub struct DepthMismatch {
+    /// Bounding box, board coordinates (mm)
+    pub min: [f64; 2],
+    /// See `min`
+    pub max: [f64; 2],
+    /// Samples in the patch
+    pub samples: usize,
+    /// Mean source depth over the patch
+    pub expected: f64,
+    /// Mean depth read back from the file
+    pub actual: f64,
+    /// Largest error of any sample in the patch (mm)
+    pub max_error: f64,
+}
+
+/// Result of comparing a depth map file with its source shapes.
+#[derive(Debug, Serialize, Clone)]
+pub struct DepthMapReport {
+    /// True when there are no mismatches
+    pub ok: bool,
+    /// Samples compared (edges of regions are skipped)
+    pub samples: usize,
+    /// Allowed depth error (mm)
+    pub tolerance: f64,
+    /// Depth per grey level (layer thickness / 255)
+    pub quantization: f64,
+    /// Largest error over all samples (mm)
+    pub max_error: f64,
+    /// Patches off by more than `tolerance`
+    pub mismatches: Vec<DepthMismatch>,
+}
+
+/// One filled element of the SVG, in file order.
+struct FillLayer {
+    rings: Vec<Polygon<f64>>, // Each ring on its own; containment uses the even-odd rule
+    bounds: Option<Rect<f64>>,
+    gray: u8,
+}
+
+/// The file as a lookup from board coordinates to grey level (None where nothing is painted).
+enum Source {
+    Svg(Vec<FillLayer>),
+    Png(image::GrayImage),
+}
+
+fn parse_gray(fill: &str) -> Option<u8> {
+    match fill.trim() {
+        "white" => Some(255),
+        "black" => Some(0),
+        f => {
+            let inner = f.strip_prefix("rgb(")?.strip_suffix(')')?;
+            let values: Vec<u8> = inner.split(',').filter_map(|v| v.trim().parse().ok()).collect();
+            // The exporter always writes equal channels; average anything else
+            (values.len() == 3).then(|| ((values[0] as u16 + values[1] as u16 + values[2] as u16) / 3) as u8)
+        }
+    }
+}
+
+fn load_svg(path: &str) -> Result<Vec<FillLayer>, Message> {
+    let mut content = String::new();
+    let mut layers = Vec::new();
+    for event in svg::open(path, &mut content).map_err(|e| Message::read_failed(path, e))? {
+        let Event::Tag(tag, _, attrs) = event else { continue };
+        let Some(gray) = attrs.get("fill").and_then(|f| parse_gray(f)) else { continue };
+        let num = |k: &str| attrs.get(k).and_then(|v| v.parse::<f64>().ok());
+
+        let rings: Vec<Polygon<f64>> = match tag {
+            "path" => {
+                let Some(d) = attrs.get("d") else { continue };
+                export_verify::path_rings(d).map_err(|e| Message::read_failed(path, e))?.0.into_iter()
+                    .filter(|r| r.len() >= 3)
+                    .map(|r| Polygon::new(LineString::from(r), vec![]))
+                    .collect()
+            }
+            "rect" => {
+                let (Some(x), Some(y), Some(w), Some(h)) = (num("x"), num("y"), num("width"), num("height")) else { continue };
+                vec![Rect::new(Coord { x, y }, Coord { x: x + w, y: y + h }).to_polygon()]
+            }
+            _ => continue,
+        };
+        let bounds = rings.iter().filter_map(|r| r.bounding_rect()).reduce(|a, b| {
+            Rect::new(
+                Coord { x: a.min().x.min(b.min().x), y: a.min().y.min(b.min().y) },
+                Coord { x: a.max().x.max(b.max().x), y: a.max().y.max(b.max().y) },
+            )
+        });
+        layers.push(FillLayer { rings, bounds, gray });
+    }
+    Ok(layers)
+}
+
+impl Source {
+    /// Grey level at `p` (SVG user units), painting later elements over earlier ones.
+    fn gray_at(&self, p: Coord<f64>, view: &Rect<f64>) -> Option<u8> {
+        match self {
+            Source::Svg(layers) => layers.iter().rev().find(|l| {
+                l.bounds.is_some_and(|b| b.contains(&p))
+                    && l.rings.iter().filter(|r| r.contains(&Point::from(p))).count() % 2 == 1
+            }).map(|l| l.gray),
+            Source::Png(img) => {
+                // The PNG is taken to be a render of the full viewBox
+                let u = (p.x - view.min().x) / view.width() * img.width() as f64;
+                let v = (p.y - view.min().y) / view.height() * img.height() as f64;
+                if u < 0.0 || v < 0.0 || u >= img.width() as f64 || v >= img.height() as f64 {
+                    return None;
+                }
+                Some(img.get_pixel(u as u32, v as u32).0[0])
+            }
+        }
+    }
+}
+
+/// Depth the map should show at `p`: the last (topmost) shape slice containing it, as the
+/// exporter layers them, or 0 on bare board. Computed straight from the shapes so mistakes
+/// in the exporter's boolean occlusion logic show up as mismatches.
+fn expected_depth(shapes: &[(Polygon<f64>, Rect<f64>, f64)], p: Point<f64>, thickness: f64) -> f64 {
+    shapes.iter().rev()
+        .find(|(poly, bounds, _)| bounds.contains(&p.0) && poly.contains(&p))
+        .map_or(0.0, |(_, _, d)| d.clamp(0.0, thickness))
+}
+
+/// Samples the board on a grid (`resolution` mm, default 1/400 of the longer side), reads
+/// the depth the file encodes at each point and compares it with the source shapes.
+/// Samples next to a depth change are skipped, as rasterised edges are ambiguous there.
+/// `tolerance` defaults to one grey level.
+pub fn verify_depth_map(path: &str, request: &ExportRequest, tolerance: Option<f64>, resolution: Option<f64>) -> Result<DepthMapReport, Message> {
+    let thickness = request.layer_thickness;
+    if thickness <= 0.0 {
+        return Err(Message::not_positive("layer_thickness", "Layer thickness must be positive"));
+    }
+    let (board, shapes) = get_board_and_shapes_expanded(request).ok_or_else(Message::outline_missing)?;
+    let board_bounds = board.bounding_rect().ok_or_else(Message::outline_degenerate)?;
+
+    // Same transform and viewBox as generate_depth_map_svg
+    let mirror_x = request.cut_direction == "Bottom";
+    let to_file = move |c: Coord<f64>| Coord { x: if mirror_x { -c.x } else { c.x }, y: -c.y };
+    let view = board.map_coords(to_file).bounding_rect().ok_or_else(Message::outline_degenerate)?;
+
+    let is_png = std::path::Path::new(path).extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("png"));
+    let source = if is_png {
+        let img = image::open(path).map_err(|e| Message::read_failed(path, e))?.to_luma8();
+        let (aspect_img, aspect_view) = (img.width() as f64 / img.height() as f64, view.width() / view.height());
+        if (aspect_img / aspect_view - 1.0).abs() > 0.02 {
+            return Err(Message::new(
+                MessageCode::DepthMapAspectMismatch,
+                format!("Image aspect ratio {:.3} does not match the board's {:.3}", aspect_img, aspect_view),
+            ).with("image_aspect", aspect_img).with("board_aspect", aspect_view));
+        }
+        Source::Png(img)
+    } else {
+        Source::Svg(load_svg(path)?)
+    };
+
+    let quantization = thickness / 255.0;
+    let tolerance = tolerance.unwrap_or(quantization);
+    let mut step = resolution.unwrap_or(board_bounds.width().max(board_bounds.height()) / DEFAULT_SAMPLES_PER_SIDE);
+    if let Source::Png(img) = &source {
+        step = step.max(view.width() / img.width() as f64); // No point sampling finer than a pixel
+    }
+    if step <= 0.0 {
+        return Err(Message::not_positive("resolution", "Sampling resolution must be positive"));
+    }
+
+    let shapes: Vec<(Polygon<f64>, Rect<f64>, f64)> = shapes.into_iter()
+        .filter_map(|(poly, d)| poly.bounding_rect().map(|b| (poly, b, d)))
+        .collect();
+
+    let cols = (board_bounds.width() / step).ceil() as usize + 1;
+    let rows = (board_bounds.height() / step).ceil() as usize + 1;
+    let at = |i: usize, j: usize| Point::new(board_bounds.min().x + i as f64 * step, board_bounds.min().y + j as f64 * step);
+
+    // Expected depth on the grid (None outside the board)
+    let expected: Vec<Option<f64>> = (0..rows).flat_map(|j| (0..cols).map(move |i| (i, j)))
+        .map(|(i, j)| {
+            let p = at(i, j);
+            board.contains(&p).then(|| expected_depth(&shapes, p, thickness))
+        })
+        .collect();
+
+    let mut samples = 0;
+    let mut max_error: f64 = 0.0;
+    // (expected, actual) for samples beyond tolerance
+    let mut bad: Vec<Option<(f64, f64)>> = vec![None; rows * cols];
+
+    for j in 0..rows {
+        for i in 0..cols {
+            let Some(want) = expected[j * cols + i] else { continue };
+            // Skip samples whose neighbours expect a different depth (or lie off the board)
+            let neighbours = [(i.wrapping_sub(1), j), (i + 1, j), (i, j.wrapping_sub(1)), (i, j + 1)];
+            let interior = neighbours.iter().all(|&(ni, nj)| {
+                ni < cols && nj < rows && expected[nj * cols + ni].is_some_and(|d| (d - want).abs() < 1e-9)
+            });
+            if !interior { continue; }
+
+            let Some(gray) = source.gray_at(to_file(at(i, j).0), &view) else { continue };
+            let got = (1.0 - gray as f64 / 255.0) * thickness;
+            samples += 1;
+            let err = (got - want).abs();
+            max_error = max_error.max(err);
+            if err > tolerance {
+                bad[j * cols + i] = Some((want, got));
+            }
+        }
+    }
+
+    // Group bad samples into 8-connected patches
+    let mut mismatches = Vec::new();
+    let mut seen = vec![false; rows * cols];
+    for start in 0..rows * cols {
+        if bad[start].is_none() || seen[start] { continue; }
+        seen[start] = true;
+        let mut stack = vec![start];
+        let (mut min, mut max) = ([f64::MAX; 2], [f64::MIN; 2]);
+        let (mut count, mut sum_want, mut sum_got, mut worst) = (0, 0.0, 0.0, 0.0f64);
+
+        while let Some(k) = stack.pop() {
+            let (i, j) = (k % cols, k / cols);
+            let (want, got) = bad[k].unwrap_or_default();
+            let p = at(i, j);
+            min = [min[0].min(p.x()), min[1].min(p.y())];
+            max = [max[0].max(p.x()), max[1].max(p.y())];
+            count += 1;
+            sum_want += want;
+            sum_got += got;
+            worst = worst.max((got - want).abs());
+
+            for dj in -1i64..=1 {
+                for di in -1i64..=1 {
+                    let (ni, nj) = (i as i64 + di, j as i64 + dj);
+                    if ni < 0 || nj < 0 || ni >= cols as i64 || nj >= rows as i64 { continue; }
+                    let nk = nj as usize * cols + ni as usize;
+                    if bad[nk].is_some() && !seen[nk] {
+                        seen[nk] = true;
+                        stack.push(nk);
+                    }
+                }
+            }
+        }
+        mismatches.push(DepthMismatch {
+            min,
+            max,
+            samples: count,
+            expected: sum_want / count as f64,
+            actual: sum_got / count as f64,
+            max_error: worst,
+        });
+    }
+    mismatches.sort_by_key(|m| std::cmp::Reverse(m.samples));
+
+    Ok(DepthMapReport {
+        ok: mismatches.is_empty(),
+        samples,
+        tolerance,
+        quantization,
+        max_error,
+        mismatches,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::generate_depth_map_svg;
+
+    fn request(filepath: &str, depth: f64) -> ExportRequest {
+        let corner = |x: f64, y: f64| serde_json::json!({ "x": x, "y": y, "handle_in": null, "handle_out": null });
+        let rect = serde_json::json!({
+            "shape_type": "rect", "x": 50.0, "y": 50.0, "width": 20.0, "height": 20.0, "diameter": null,
+            "angle": null, "corner_radius": null, "thickness": null, "points": null, "depth": depth,
+            "endmill_radius": null, "feed": null, "speed": null, "power": null,
+        });
+        serde_json::from_value(serde_json::json!({
+            "filepath": filepath, "file_type": "SVG", "machining_type": "Carved/Printed", "cut_direction": "Top",
+            "outline": [corner(0.0, 0.0), corner(100.0, 0.0), corner(100.0, 100.0), corner(0.0, 100.0)],
+            "shapes": [rect], "layer_thickness": 2.0, "stl_content": null,
+        })).unwrap()
+    }
+
+    fn temp_path(name: &str) -> String {
+        std::env::temp_dir().join(format!("shortstack_depth_{}_{}", std::process::id(), name)).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_exported_map_reads_back_clean() {
+        let path = temp_path("clean.svg");
+        let req = request(&path, 1.0);
+        generate_depth_map_svg(&req, None).unwrap();
+        let report = verify_depth_map(&path, &req, None, Some(1.0)).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(report.ok, "{:?}", report.mismatches);
+        assert!(report.samples > 9000);
+        assert!(report.max_error <= report.quantization);
+    }
+
+    #[test]
+    fn test_wrong_depth_is_one_patch_over_the_shape() {
+        let path = temp_path("wrong.svg");
+        generate_depth_map_svg(&request(&path, 1.0), None).unwrap();
+        let report = verify_depth_map(&path, &request(&path, 1.5), None, Some(1.0)).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert!(!report.ok);
+        assert_eq!(report.mismatches.len(), 1);
+        let patch = &report.mismatches[0];
+        // The rect is centred at (50, 50); edge samples are skipped
+        assert!(patch.min[0] > 39.0 && patch.max[0] < 61.0);
+        assert!(patch.min[1] > 39.0 && patch.max[1] < 61.0);
+        assert!((patch.expected - 1.5).abs() < 1e-9);
+        assert!((patch.actual - 1.0).abs() <= report.quantization);
+    }
+
+    #[test]
+    fn test_png_with_wrong_aspect_is_rejected() {
+        let path = temp_path("aspect.png");
+        image::GrayImage::from_pixel(100, 50, image::Luma([255])).save(&path).unwrap();
+        let err = verify_depth_map(&path, &request(&path, 1.0), None, None).unwrap_err();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(err.code, MessageCode::DepthMapAspectMismatch);
+    }
+}
@@ -0,0 +1,1423 @@
+//! Export requests (one board layer with its cut/carve shapes) and the SVG/DXF writers.
+//!
+//! Shapes are resolved to polygons here (bezier outlines, stroked lines, ball-nose gradients
+//! as depth slices); the analysis modules work from the same helpers so they see exactly the
+//! geometry that gets exported.
+use crate::export_stream::{self, ProgressSink};
+use crate::{cut_groups, export_verify, fem, overlap_groups};
+use crate::messages::{Message, MessageCode};
+use std::f64::consts::PI;
+use geo::{Coord, LineString, MultiPolygon, Polygon, Intersects, Contains};
+use geo::bounding_rect::BoundingRect;
+use geo::MapCoords;
+use svg::node::element::{Path, Rectangle, Circle};
+use svg::node::element::path::Data;
+use std::io::Write;
+use csgrs::sketch::Sketch;
+use csgrs::traits::CSG;
+
+/// Bezier handle offset, relative to its point (mm).
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct ExportVec2 {
+    /// X offset (mm)
+    pub x: f64,
+    /// Y offset (mm)
+    pub y: f64,
+}
+
+/// Path vertex with optional cubic Bezier handles.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct ExportPoint {
+    /// X position (mm)
+    pub x: f64,
+    /// Y position (mm)
+    pub y: f64,
+    /// Handle controlling the curve arriving at this point
+    pub handle_in: Option<ExportVec2>,
+    /// Handle controlling the curve leaving this point
+    pub handle_out: Option<ExportVec2>,
+}
+
+/// A resolved footprint shape as sent by the frontend for export.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Clone)]
+pub struct ExportShape {
+    /// "circle", "rect", "line" or "polygon"
+    pub shape_type: String,
+    /// Center x (mm)
+    pub x: f64,
+    /// Center y (mm)
+    pub y: f64,
+    /// Rect width (mm)
+    pub width: Option<f64>,
+    /// Rect height (mm)
+    pub height: Option<f64>,
+    /// Circle diameter (mm)
+    pub diameter: Option<f64>,
+    /// Rect rotation (degrees)
+    pub angle: Option<f64>,
+    /// Rect corner radius (mm)
+    pub corner_radius: Option<f64>,
+    /// Line stroke width (mm)
+    pub thickness: Option<f64>,
+    /// Line centerline or polygon vertices
+    pub points: Option<Vec<ExportPoint>>,
+    /// Cut depth from the machined face (mm)
+    pub depth: f64,
+    /// Radius of the ball-nose endmill for gradient generation
+    pub endmill_radius: Option<f64>,
+    // Per-shape machine settings; None runs at the layer's settings
+    /// Feed rate (mm/min)
+    pub feed: Option<f64>,
+    /// Spindle RPM
+    pub speed: Option<f64>,
+    /// Laser power, percent
+    pub power: Option<f64>,
+}
+
+/// One layer's export job: board outline, shapes and output format.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct ExportRequest {
+    /// Destination file
+    pub filepath: String,
+    /// "SVG", "DXF", "STEP", "STL"
+    pub file_type: String,
+    /// "Cut" or "Carved/Printed"
+    pub machining_type: String,
+    /// "Top" or "Bottom"
+    pub cut_direction: String,
+    /// Board outline as a closed path
+    pub outline: Vec<ExportPoint>,
+    /// Shapes on this layer
+    pub shapes: Vec<ExportShape>,
+    /// Layer thickness (mm)
+    pub layer_thickness: f64,
+    /// Binary STL data for STL exports
+    pub stl_content: Option<Vec<u8>>,
+    /// Scope for sandbox approvals
+    #[serde(default)]
+    pub project_id: Option<String>,
+    /// Carve-mode DXF: also fill each depth region with a solid HATCH
+    #[serde(default)]
+    pub dxf_hatch: bool,
+}
+
+/// Outcome of `export_layer_files`.
+#[derive(Debug, serde::Serialize)]
+pub struct ExportResult {
+    /// None when the format is not re-read (STL, depth maps)
+    pub verified: Option<bool>,
+    /// Differences between the written file and the source geometry
+    pub issues: Vec<String>,
+}
+
+/// Writes the file, streaming it to disk with progress reports when `progress` is given; returns
+/// what was written for the formats that can be verified (None for STL and depth-map SVG).
+pub fn write_layer_file(request: &ExportRequest, progress: Option<&ProgressSink>) -> Result<Option<export_verify::WrittenGeometry>, Message> {
+    let failed = |e: Box<dyn std::error::Error>| Message::write_failed(&request.filepath, e);
+    let carve = request.machining_type == "Carved/Printed";
+    match request.file_type.as_str() {
+        "STL" => {
+            // Write the pre-computed STL data from Typescript directly to file
+            let content = request.stl_content.as_ref()
+                .ok_or_else(|| Message::new(MessageCode::StlContentMissing, "STL export requested but no mesh content provided"))?;
+            write_file(&request.filepath, progress, |out| Ok(content.chunks(1 << 16).try_for_each(|c| out.write_all(c))?))
+                .map_err(failed)?;
+            Ok(None)
+        }
+        "SVG" if carve => generate_depth_map_svg(request, progress).map(|_| None).map_err(failed),
+        "SVG" => generate_profile_svg(request, progress).map(Some).map_err(failed),
+        "DXF" if carve => generate_depth_map_dxf(request, progress).map(Some).map_err(failed),
+        "DXF" => generate_dxf(request, progress).map(Some).map_err(failed),
+        other => Err(Message::new(MessageCode::UnsupportedFormat, format!("Unsupported export format: {}", other)).with("format", other)),
+    }
+}
+
+/// The SVG or DXF `write_layer_file` would produce for `request`, as bytes rather than a
+/// file (`filepath` is ignored). STL content comes from the frontend and is not generated here.
+pub fn export_bytes(request: &ExportRequest) -> Result<Vec<u8>, Message> {
+    if request.outline.is_empty() {
+        return Err(Message::outline_missing());
+    }
+    let mut out = Vec::new();
+    let carve = request.machining_type == "Carved/Printed";
+    let result = match request.file_type.as_str() {
+        "SVG" if carve => write_depth_map_svg(request, &mut out),
+        "SVG" => write_profile_svg(request, &mut out).map(|_| ()),
+        "DXF" if carve => write_depth_map_dxf(request, &mut out).map(|_| ()),
+        "DXF" => write_profile_dxf(request, &mut out).map(|_| ()),
+        other => return Err(Message::new(
+            MessageCode::UnsupportedFormat,
+            format!("{} files are not generated by the core", other),
+        ).with("format", other)),
+    };
+    result.map_err(|e| Message::write_failed(&request.file_type, e))?;
+    Ok(out)
+}
+
+// Evaluate cubic bezier at t
+fn eval_bezier(p0: Coord<f64>, p1: Coord<f64>, p2: Coord<f64>, p3: Coord<f64>, t: f64) -> Coord<f64> {
+    let mt = 1.0 - t;
+    let mt2 = mt * mt;
+    let mt3 = mt2 * mt;
+    let t2 = t * t;
+    let t3 = t2 * t;
+    
+    Coord {
+        x: mt3 * p0.x + 3.0 * mt2 * t * p1.x + 3.0 * mt * t2 * p2.x + t3 * p3.x,
+        y: mt3 * p0.y + 3.0 * mt2 * t * p1.y + 3.0 * mt * t2 * p2.y + t3 * p3.y,
+    }
+}
+
+// Discretize open or generic path
+fn discretize_path(points: &[ExportPoint]) -> LineString<f64> {
+    let mut coords = Vec::new();
+    if points.is_empty() { return LineString::new(vec![]); }
+
+    coords.push(Coord { x: points[0].x, y: points[0].y });
+
+    for i in 0..points.len() {
+        if i >= points.len() - 1 { break; }
+
+        let p0 = &points[i];
+        let p3 = &points[i+1];
+        
+        // Check for handles
+        let has_curve = p0.handle_out.is_some() || p3.handle_in.is_some();
+        
+        if has_curve {
+            let cp1 = if let Some(h) = &p0.handle_out {
+                Coord { x: p0.x + h.x, y: p0.y + h.y }
+            } else {
+                Coord { x: p0.x, y: p0.y }
+            };
+            
+            let cp2 = if let Some(h) = &p3.handle_in {
+                Coord { x: p3.x + h.x, y: p3.y + h.y }
+            } else {
+                Coord { x: p3.x, y: p3.y }
+            };
+
+            // Sample
+            let steps = 16;
+            for s in 1..=steps {
+                let t = s as f64 / steps as f64;
+                coords.push(eval_bezier(
+                    Coord { x: p0.x, y: p0.y }, 
+                    cp1, cp2, 
+                    Coord { x: p3.x, y: p3.y }, 
+                    t
+                ));
+            }
+        } else {
+            coords.push(Coord { x: p3.x, y: p3.y });
+        }
+    }
+    
+    LineString::new(coords)
+}
+
+/// Discretizes a board outline (bezier handles included) as a closed ring.
+pub fn discretize_path_closed(points: &[ExportPoint]) -> LineString<f64> {
+    if points.is_empty() { return LineString::new(vec![]); }
+    let mut ls = discretize_path(points);
+    
+    // Add closing segment
+    let last = &points[points.len() - 1];
+    let first = &points[0];
+    
+    // Check if we need to discretize the closing segment
+    let has_curve = last.handle_out.is_some() || first.handle_in.is_some();
+    
+    if has_curve {
+        let cp1 = if let Some(h) = &last.handle_out {
+            Coord { x: last.x + h.x, y: last.y + h.y }
+        } else {
+            Coord { x: last.x, y: last.y }
+        };
+        let cp2 = if let Some(h) = &first.handle_in {
+            Coord { x: first.x + h.x, y: first.y + h.y }
+        } else {
+            Coord { x: first.x, y: first.y }
+        };
+        
+        let steps = 16;
+        for s in 1..=steps {
+             let t = s as f64 / steps as f64;
+             ls.0.push(eval_bezier(
+                 Coord { x: last.x, y: last.y },
+                 cp1, cp2,
+                 Coord { x: first.x, y: first.y },
+                 t
+             ));
+        }
+    } else {
+        ls.0.push(Coord { x: first.x, y: first.y });
+    }
+    ls
+}
+
+fn stroke_linestring(ls: &LineString<f64>, thickness: f64) -> Polygon<f64> {
+    if ls.0.len() < 2 { return Polygon::new(LineString::new(vec![]), vec![]); }
+    
+    let half_t = thickness / 2.0;
+    let mut left_pts = Vec::new();
+    let mut right_pts = Vec::new();
+
+    for i in 0..ls.0.len() {
+        let p = ls.0[i];
+        let tangent;
+        
+        if i == 0 {
+            let next = ls.0[i+1];
+            let dx = next.x - p.x;
+            let dy = next.y - p.y;
+            let len = (dx*dx + dy*dy).sqrt();
+            tangent = Coord { x: dx/len, y: dy/len };
+        } else if i == ls.0.len() - 1 {
+            let prev = ls.0[i-1];
+            let dx = p.x - prev.x;
+            let dy = p.y - prev.y;
+            let len = (dx*dx + dy*dy).sqrt();
+            tangent = Coord { x: dx/len, y: dy/len };
+        } else {
+            let prev = ls.0[i-1];
+            let next = ls.0[i+1];
+            // Average tangent
+            let dx1 = p.x - prev.x; let dy1 = p.y - prev.y;
+            let dx2 = next.x - p.x; let dy2 = next.y - p.y;
+            // normalize both
+            let l1 = (dx1*dx1 + dy1*dy1).sqrt();
+            let l2 = (dx2*dx2 + dy2*dy2).sqrt();
+            let tx = dx1/l1 + dx2/l2;
+            let ty = dy1/l1 + dy2/l2;
+            let tl = (tx*tx + ty*ty).sqrt();
+            tangent = Coord { x: tx/tl, y: ty/tl };
+        }
+
+        let normal = Coord { x: -tangent.y, y: tangent.x };
+        
+        left_pts.push(Coord { x: p.x + normal.x * half_t, y: p.y + normal.y * half_t });
+        right_pts.push(Coord { x: p.x - normal.x * half_t, y: p.y - normal.y * half_t });
+    }
+
+    // Construct loop with Rounded Ends (Semicircles)
+    // Left forward
+    let mut loop_coords = left_pts.clone();
+    
+    // Tip Cap (Rounded): Rotate the normal vector CW 180 degrees at the end
+    let p_last = ls.0[ls.0.len() - 1];
+    let v_start = Coord { 
+        x: left_pts.last().unwrap().x - p_last.x, 
+        y: left_pts.last().unwrap().y - p_last.y 
+    };
+    
+    let steps = 16;
+    for i in 1..steps { 
+        let theta = -(i as f64 / steps as f64) * PI; // CW rotation
+        let cos_t = theta.cos();
+        let sin_t = theta.sin();
+        let vx = v_start.x * cos_t - v_start.y * sin_t;
+        let vy = v_start.x * sin_t + v_start.y * cos_t;
+        loop_coords.push(Coord { x: p_last.x + vx, y: p_last.y + vy });
+    }
+
+    // Right backward
+    loop_coords.extend(right_pts.iter().rev().cloned());
+    
+    // Start Cap (Rounded): Rotate the normal vector CW 180 degrees at the start
+    let p_first = ls.0[0];
+    let v_start_cap = Coord {
+        x: right_pts[0].x - p_first.x,
+        y: right_pts[0].y - p_first.y
+    };
+    
+    for i in 1..steps {
+        let theta = -(i as f64 / steps as f64) * PI;
+        let cos_t = theta.cos();
+        let sin_t = theta.sin();
+        let vx = v_start_cap.x * cos_t - v_start_cap.y * sin_t;
+        let vy = v_start_cap.x * sin_t + v_start_cap.y * cos_t;
+        loop_coords.push(Coord { x: p_first.x + vx, y: p_first.y + vy });
+    }
+
+    // Close
+    if let Some(first) = loop_coords.first() {
+        loop_coords.push(*first);
+    }
+
+    Polygon::new(LineString::new(loop_coords), vec![])
+}
+
+// -----------------------------------------------------------
+//  EXPANSION LOGIC FOR GRADIENTS
+// -----------------------------------------------------------
+
+/// The shape's polygon shrunk inwards by `offset` (used for ball-nose gradient slices).
+pub fn shape_to_polygon_offset(shape: &ExportShape, offset: f64) -> Option<Polygon<f64>> {
+    // Modify a clone of the shape params
+    let mut temp = shape.clone();
+    
+    match temp.shape_type.as_str() {
+        "circle" => {
+            if let Some(d) = temp.diameter {
+                temp.diameter = Some(d - 2.0 * offset);
+                if temp.diameter.unwrap() <= 1e-4 { return None; }
+            }
+        },
+        "rect" => {
+            if let Some(w) = temp.width { temp.width = Some(w - 2.0 * offset); }
+            if let Some(h) = temp.height { temp.height = Some(h - 2.0 * offset); }
+            if temp.width.unwrap_or(0.0) <= 1e-4 || temp.height.unwrap_or(0.0) <= 1e-4 { return None; }
+            
+            if let Some(cr) = temp.corner_radius {
+                temp.corner_radius = Some((cr - offset).max(0.0));
+            }
+        },
+        "line" => {
+            if let Some(t) = temp.thickness {
+                temp.thickness = Some(t - 2.0 * offset);
+                if temp.thickness.unwrap() <= 1e-4 { return None; }
+            }
+        },
+        _ => return None
+    }
+    
+    shape_to_polygon(&temp)
+}
+
+/// Steps used to approximate a ball-nose fillet as depth slices (gradient fidelity).
+pub const BALL_NOSE_STEPS: usize = 12;
+
+/// Fillet radius a shape's ball-nose gradient is actually generated with: the endmill
+/// radius, clamped to the shape's depth and half its narrowest dimension. 0 for flat cuts.
+pub fn ball_nose_fillet_radius(shape: &ExportShape) -> f64 {
+    let radius = shape.endmill_radius.unwrap_or(0.0);
+    if radius <= 1e-4 {
+        return 0.0;
+    }
+
+    // Safety: ensure radius isn't larger than the shape itself
+    let min_dim = match shape.shape_type.as_str() {
+        "circle" => shape.diameter.unwrap_or(0.0),
+        "rect" => shape.width.unwrap_or(0.0).min(shape.height.unwrap_or(0.0)),
+        "line" => shape.thickness.unwrap_or(0.0),
+        _ => 0.0,
+    };
+
+    // Clamp radius
+    let safe_radius = radius.min(shape.depth).min(min_dim / 2.0 - 0.001).max(0.0);
+    if safe_radius <= 1e-4 { 0.0 } else { safe_radius }
+}
+
+/// Expands a shape into (polygon, depth) slices approximating its ball-nose gradient;
+/// a single slice for flat cuts.
+pub fn expand_ball_nose_shape(shape: &ExportShape) -> Vec<(Polygon<f64>, f64)> {
+    let safe_radius = ball_nose_fillet_radius(shape);
+
+    // Standard flat cut (no radius, or effectively zero after the safety clamp)
+    if safe_radius <= 0.0 {
+        if let Some(poly) = shape_to_polygon(shape) {
+            return vec![(poly, shape.depth)];
+        }
+        return vec![];
+    }
+
+    let mut slices = Vec::new();
+    let steps = BALL_NOSE_STEPS;
+
+    // 1. Base Vertical Hole (Top of Fillet)
+    // Depth: Total - Radius
+    // Offset: 0 (Full width)
+    let base_depth = shape.depth - safe_radius;
+    if base_depth > 1e-4
+        && let Some(poly) = shape_to_polygon_offset(shape, 0.0) {
+        slices.push((poly, base_depth));
+    }
+
+    // 2. Fillet Slices (Curving inwards to bottom)
+    for i in 1..=steps {
+        let ratio = i as f64 / steps as f64;
+        let theta = ratio * std::f64::consts::FRAC_PI_2; // 0..90 deg
+        
+        // Z Depth increases from base_depth to shape.depth
+        let z = base_depth + theta.sin() * safe_radius;
+        
+        // Offset increases from 0 to radius
+        // Circular profile: offset = R - R*cos(theta)
+        let offset = safe_radius * (1.0 - theta.cos());
+        
+        if let Some(poly) = shape_to_polygon_offset(shape, offset) {
+            slices.push((poly, z));
+        }
+    }
+
+    slices
+}
+
+/// (polygon, depth) slices of shapes, before any union.
+pub type ShapeSlices = Vec<(Polygon<f64>, f64)>;
+
+/// Board polygon and the raw (polygon, depth) slices of every shape, in drawing order and
+/// not unioned; None when there is no outline.
+pub fn get_board_and_shapes_expanded(request: &ExportRequest) -> Option<(Polygon<f64>, ShapeSlices)> {
+    if request.outline.is_empty() { return None; }
+
+    let board_ls = discretize_path_closed(&request.outline);
+    let board_poly = Polygon::new(board_ls, vec![]);
+
+    // Convert Shapes to List of (Polygon, Depth)
+    let mut shape_list = Vec::new();
+
+    for shape in &request.shapes {
+        // Here we expand the shape into potential multiple slices
+        let slices = expand_ball_nose_shape(shape);
+        shape_list.extend(slices);
+    }
+
+    Some((board_poly, shape_list))
+}
+
+// Helper to partition semantic circles from those needing CSG unioning
+fn partition_isolated_circles(request: &ExportRequest) -> (Polygon<f64>, Vec<ExportShape>, Vec<ExportShape>) {
+    let board_ls = discretize_path_closed(&request.outline);
+    let board_poly = Polygon::new(board_ls, vec![]);
+
+    let mut isolated = Vec::new();
+    let mut csg_pool = Vec::new();
+
+    let shape_polys: Vec<(usize, Polygon<f64>)> = request.shapes.iter().enumerate()
+        .filter_map(|(i, s)| shape_to_polygon(s).map(|p| (i, p)))
+        .collect();
+
+    for (i, shape) in request.shapes.iter().enumerate() {
+        let mut is_isolated = false;
+        if shape.shape_type == "circle"
+            && let Some(poly) = shape_to_polygon(shape) {
+            let mut overlaps = false;
+            for (other_idx, other_poly) in &shape_polys {
+                if i == *other_idx { continue; }
+                if poly.intersects(other_poly) { overlaps = true; break; }
+            }
+            if !overlaps && board_poly.contains(&poly) { is_isolated = true; }
+        }
+
+        if is_isolated { isolated.push(shape.clone()); }
+        else { csg_pool.push(shape.clone()); }
+    }
+
+    (board_poly, isolated, csg_pool)
+}
+
+/// Unioned geometry for profile cuts from a specific pool.
+/// Shapes are first split into non-interacting groups (see overlap_groups) and each group
+/// is unioned on its own, so a failing boolean names the pair that caused it.
+pub fn get_geometry_unioned_from_pool(board_poly: &Polygon<f64>, pool: &[ExportShape]) -> Result<MultiPolygon<f64>, String> {
+    let board_sketch = Sketch::from_geo(geo::Geometry::Polygon(board_poly.clone()).into(), None);
+    let shapes: Vec<(&ExportShape, Polygon<f64>)> = pool.iter()
+        .filter_map(|s| shape_to_polygon(s).map(|p| (s, p)))
+        .collect();
+    let polys: Vec<Polygon<f64>> = shapes.iter().map(|(_, p)| p.clone()).collect();
+    let describe = |i: usize| format!("{} at ({:.3}, {:.3})", shapes[i].0.shape_type, shapes[i].0.x, shapes[i].0.y);
+
+    let mut polys_out = Vec::new();
+    for group in overlap_groups::group_overlapping(&polys) {
+        let mut united_sketch: Sketch<()> = Sketch::from_geo(geo::Geometry::Polygon(polys[group[0]].clone()).into(), None);
+        for &i in &group[1..] {
+            let shape_sketch = Sketch::from_geo(geo::Geometry::Polygon(polys[i].clone()).into(), None);
+            let current = united_sketch;
+            united_sketch = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| current.union(&shape_sketch)))
+                .map_err(|_| format!(
+                    "Boolean union failed adding {} to a group of {} shape(s) starting with {}",
+                    describe(i), group.len(), describe(group[0])
+                ))?;
+        }
+
+        let clipped_sketch = united_sketch.intersection(&board_sketch);
+        for geom in clipped_sketch.geometry {
+            match geom {
+                geo::Geometry::Polygon(p) => polys_out.push(p),
+                geo::Geometry::MultiPolygon(mp) => polys_out.extend(mp.0),
+                _ => {}
+            }
+        }
+    }
+    Ok(MultiPolygon::new(polys_out))
+}
+
+/// Streams `write` into the file at `path`, reporting progress when a sink is given.
+fn write_file<T>(
+    path: &str,
+    progress: Option<&ProgressSink>,
+    write: impl FnOnce(&mut dyn Write) -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let mut out = export_stream::ProgressWriter::create(path, progress)?;
+    let result = write(&mut out)?;
+    out.finish()?;
+    Ok(result)
+}
+
+/// Cut-mode SVG written to `request.filepath`; see `write_profile_svg`.
+pub fn generate_profile_svg(request: &ExportRequest, progress: Option<&ProgressSink>) -> Result<export_verify::WrittenGeometry, Box<dyn std::error::Error>> {
+    write_file(&request.filepath, progress, |out| write_profile_svg(request, out))
+}
+
+/// Cut-mode SVG: board outline plus the unioned shapes, one group per machine-settings set.
+pub fn write_profile_svg(request: &ExportRequest, out: &mut dyn Write) -> Result<export_verify::WrittenGeometry, Box<dyn std::error::Error>> {
+    let (board_poly_raw, isolated_circles, pool) = partition_isolated_circles(request);
+    // Shapes with feed/speed/power overrides are unioned and drawn per settings group
+    let mut united_groups = Vec::new();
+    for (settings, group) in cut_groups::group_by_settings(&pool) {
+        united_groups.push((settings, get_geometry_unioned_from_pool(&board_poly_raw, &group)?));
+    }
+
+    // Transform logic (Standard SVG Y-Down flip)
+    let transform = |c: Coord<f64>| Coord { x: c.x, y: -c.y };
+
+    let board_poly = board_poly_raw.map_coords(transform);
+
+    // SVG bounds
+    let bounds = board_poly.bounding_rect().unwrap_or_else(|| {
+        geo::Rect::new(Coord { x: 0.0, y: 0.0 }, Coord { x: 100.0, y: 100.0 })
+    });
+
+    let min_x = bounds.min().x;
+    let min_y = bounds.min().y;
+    let width = bounds.width();
+    let height = bounds.height();
+
+    // Elements are streamed out as they are built
+    export_stream::write_svg_start(out, min_x, min_y, width, height, None)?;
+
+    // Record what goes into the file, in board coordinates, for verification
+    let mut written = export_verify::WrittenGeometry::default();
+    written.add_polygon(&board_poly_raw);
+    for (_, united) in &united_groups {
+        for poly in &united.0 {
+            written.add_polygon(poly);
+        }
+    }
+
+    // Board Outline Path (Black)
+    let outline_data = polygon_to_path_data(&board_poly);
+    let outline_path = Path::new()
+        .set("fill", "none")
+        .set("stroke", "black")
+        .set("stroke-width", "0.1mm")
+        .set("d", outline_data);
+    export_stream::write_svg_node(out, &outline_path)?;
+
+    // United Shapes Paths (Red, override groups in their own colours)
+    let mut override_colors: Vec<cut_groups::CutSettings> = Vec::new();
+    let mut color_of = |settings: cut_groups::CutSettings| {
+        if settings.is_default() { return settings.svg_color(0); }
+        let index = override_colors.iter().position(|s| *s == settings).unwrap_or_else(|| {
+            override_colors.push(settings);
+            override_colors.len() - 1
+        });
+        settings.svg_color(index)
+    };
+
+    for (settings, united_raw) in &united_groups {
+        if united_raw.0.is_empty() { continue; }
+        let united_shapes = united_raw.map_coords(transform);
+        let mut shapes_data = Data::new();
+        for poly in &united_shapes.0 {
+            shapes_data = append_polygon_to_data(shapes_data, poly);
+        }
+
+        let mut shapes_path = Path::new()
+            .set("fill", "none")
+            .set("stroke", color_of(*settings))
+            .set("stroke-width", "0.1mm")
+            .set("d", shapes_data);
+        for (k, v) in settings.svg_attributes() {
+            shapes_path = shapes_path.set(k, v);
+        }
+        export_stream::write_svg_node(out, &shapes_path)?;
+    }
+
+    // Isolated Circles (Parametric)
+    for circle in &isolated_circles {
+        let r = circle.diameter.unwrap_or(0.0) / 2.0;
+        written.circles.push((circle.x, circle.y, r));
+        let settings = cut_groups::CutSettings::of(circle);
+        let mut c_node = Circle::new()
+            .set("cx", circle.x)
+            .set("cy", -circle.y)
+            .set("r", r)
+            .set("fill", "none")
+            .set("stroke", color_of(settings))
+            .set("stroke-width", "0.1mm");
+        for (k, v) in settings.svg_attributes() {
+            c_node = c_node.set(k, v);
+        }
+        export_stream::write_svg_node(out, &c_node)?;
+    }
+
+    export_stream::write_svg_end(out)?;
+
+    Ok(written)
+}
+
+/// Carve-mode SVG depth map written to `request.filepath`; see `write_depth_map_svg`.
+/// Nothing is written when there is no outline.
+pub fn generate_depth_map_svg(request: &ExportRequest, progress: Option<&ProgressSink>) -> Result<(), Box<dyn std::error::Error>> {
+    if request.outline.is_empty() {
+        return Ok(());
+    }
+    write_file(&request.filepath, progress, |out| write_depth_map_svg(request, out))
+}
+
+/// Carve-mode SVG depth map: each visible depth region filled with its grey level.
+pub fn write_depth_map_svg(request: &ExportRequest, out: &mut dyn Write) -> Result<(), Box<dyn std::error::Error>> {
+    let (board_poly_raw, regions) = get_depth_regions(request).ok_or("Board outline is missing")?;
+
+    // Check conditions for flipping X:
+    // We flip along the Y-axis (negate X) if we are Carving/Printing from the "Bottom".
+    let mirror_x = request.cut_direction == "Bottom";
+
+    // Transform logic:
+    // 1. SVG coordinate system has Y pointing DOWN. Our CAD uses Y pointing UP. We negate Y (-c.y).
+    // 2. If mirror_x is true, we negate X (-c.x) to flip horizontally.
+    let transform = |c: Coord<f64>| Coord { 
+        x: if mirror_x { -c.x } else { c.x }, 
+        y: -c.y 
+    };
+
+    let board_poly = board_poly_raw.map_coords(transform);
+    
+    // Bounds calculation based on board
+    let bounds = board_poly.bounding_rect().unwrap_or_else(|| {
+        geo::Rect::new(Coord { x: 0.0, y: 0.0 }, Coord { x: 100.0, y: 100.0 })
+    });
+    
+    let min_x = bounds.min().x;
+    let min_y = bounds.min().y;
+    let width = bounds.width();
+    let height = bounds.height();
+
+    export_stream::write_svg_start(out, min_x, min_y, width, height, Some("background-color: black"))?;
+
+    // 1. Background Black Rectangle (100% Cut / Empty Space)
+    let bg_rect = Rectangle::new()
+        .set("x", min_x)
+        .set("y", min_y)
+        .set("width", width)
+        .set("height", height)
+        .set("fill", "black");
+    export_stream::write_svg_node(out, &bg_rect)?;
+
+    // 2. Board Solid White (0% Cut / Material Surface)
+    let board_data = polygon_to_path_data(&board_poly);
+    let board_path = Path::new()
+        .set("fill", "white")
+        .set("stroke", "none") 
+        .set("d", board_data);
+    export_stream::write_svg_node(out, &board_path)?;
+
+    // 3. Depth regions, shallowest first so deep cuts are drawn last
+    for (depth, final_multipoly_raw) in regions {
+        let mut shapes_data = Data::new();
+        // Transform the geometry to SVG space here
+        let final_multipoly = final_multipoly_raw.map_coords(transform);
+        for poly in &final_multipoly.0 {
+            shapes_data = append_polygon_to_data(shapes_data, poly);
+        }
+
+        let ratio = (depth / request.layer_thickness).clamp(0.0, 1.0);
+
+        let val = (255.0 * (1.0 - ratio)).round() as u8;
+        let color = format!("rgb({},{},{})", val, val, val);
+
+        let shape_path = Path::new()
+            .set("fill", color)
+            .set("stroke", "none")
+            .set("d", shapes_data);
+        export_stream::write_svg_node(out, &shape_path)?;
+    }
+
+    export_stream::write_svg_end(out)?;
+
+    Ok(())
+}
+
+/// (depth, region) pairs produced by `get_depth_regions`.
+pub type DepthRegions = Vec<(f64, MultiPolygon<f64>)>;
+
+/// Splits the carved shapes into the regions visible from the cut face, one non-empty
+/// MultiPolygon per distinct depth (clipped to the board), sorted shallowest first.
+/// Returns the board polygon alongside; None when there is no outline.
+pub fn get_depth_regions(request: &ExportRequest) -> Option<(Polygon<f64>, DepthRegions)> {
+    // UPDATED: Use expanded shape generator which handles ball-nose gradients
+    let (board_poly_raw, shapes_raw) = get_board_and_shapes_expanded(request)?;
+
+    // Prepare board sketch for math clipping
+    let board_sketch = Sketch::from_geo(geo::Geometry::Polygon(board_poly_raw.clone()).into(), None);
+
+    // `shapes_raw` is ordered Bottom -> Top.
+    
+    struct Layer {
+        sketch: Sketch<()>,
+        depth: f64,
+    }
+
+    // A. Merge adjacent shapes with same depth AND clip them to board
+    let mut layers: Vec<Layer> = Vec::new();
+    for (poly_raw, depth) in shapes_raw {
+        let geom = geo::Geometry::Polygon(poly_raw);
+        // CLIP: Intersect each shape slice with the board outline before it enters the list
+        let sketch = Sketch::from_geo(geom.into(), None).intersection(&board_sketch);
+
+        if let Some(last) = layers.last_mut()
+            && (last.depth - depth).abs() < 1e-6 {
+            last.sketch = last.sketch.union(&sketch);
+            continue;
+        }
+        layers.push(Layer { sketch, depth });
+    }
+
+    // B. Compute Visible Regions
+    // We iterate from Top (end) to Bottom (start).
+    // A layer is visible except where it is obscured by *higher* layers.
+    // Optimization: Only subtract higher layers if they have a *different* depth.
+    // If they have the same depth, they merge naturally in the final step.
+    
+    let mut visible_parts: Vec<(f64, Sketch<()>)> = Vec::new();
+    
+    // Store union of shapes for each depth encountered so far (from Top)
+    // Used to subtract only shapes of *different* depth.
+    let mut processed_masks_by_depth: Vec<(f64, Sketch<()>)> = Vec::new();
+
+    for layer in layers.iter().rev() {
+        let mut visible = layer.sketch.clone();
+
+        // Subtract overlapping shapes from higher layers (processed_masks)
+        // BUT only if depths differ.
+        let mut subtraction_mask: Option<Sketch<()>> = None;
+        
+        for (d, mask_sketch) in &processed_masks_by_depth {
+            if (d - layer.depth).abs() > 1e-6 {
+                if let Some(curr) = subtraction_mask {
+                    subtraction_mask = Some(curr.union(mask_sketch));
+                } else {
+                    subtraction_mask = Some(mask_sketch.clone());
+                }
+            }
+        }
+
+        if let Some(mask) = subtraction_mask {
+            visible = visible.difference(&mask);
+        }
+
+        if !visible.geometry.is_empty() {
+             visible_parts.push((layer.depth, visible));
+        }
+
+        // Add CURRENT layer (full shape) to the masks for future (lower) layers
+        let mut found = false;
+        for (d, mask_sketch) in &mut processed_masks_by_depth {
+            if (*d - layer.depth).abs() < 1e-6 {
+                *mask_sketch = mask_sketch.union(&layer.sketch);
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            processed_masks_by_depth.push((layer.depth, layer.sketch.clone()));
+        }
+    }
+
+    // C. Group visible parts by Depth and Union them
+    // This merges split parts back together if they have the same depth
+    let mut final_depth_groups: Vec<(f64, Sketch<()>)> = Vec::new();
+
+    for (depth, sketch) in visible_parts {
+        let mut found = false;
+        for (d, group_sketch) in &mut final_depth_groups {
+            if (*d - depth).abs() < 1e-6 {
+                *group_sketch = group_sketch.union(&sketch);
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            final_depth_groups.push((depth, sketch));
+        }
+    }
+    
+    // Sort by depth so deep cuts are drawn last (optional if they don't overlap, but good for safety)
+    final_depth_groups.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let regions = final_depth_groups.into_iter()
+        .map(|(depth, sketch)| {
+            let mut p_list = Vec::new();
+            for geom in sketch.geometry {
+                match geom {
+                    geo::Geometry::Polygon(p) => p_list.push(p),
+                    geo::Geometry::MultiPolygon(mp) => p_list.extend(mp.0),
+                    _ => {}
+                }
+            }
+            (depth, MultiPolygon::new(p_list))
+        })
+        .filter(|(_, mp)| !mp.0.is_empty())
+        .collect();
+
+    Some((board_poly_raw, regions))
+}
+
+/// Cut-mode DXF written to `request.filepath`; see `write_profile_dxf`.
+pub fn generate_dxf(request: &ExportRequest, progress: Option<&ProgressSink>) -> Result<export_verify::WrittenGeometry, Box<dyn std::error::Error>> {
+    write_file(&request.filepath, progress, |out| write_profile_dxf(request, out))
+}
+
+/// Cut-mode DXF: board outline plus every shape as polylines/circles.
+pub fn write_profile_dxf(request: &ExportRequest, out: &mut dyn Write) -> Result<export_verify::WrittenGeometry, Box<dyn std::error::Error>> {
+    let (board_poly, isolated_circles, pool) = partition_isolated_circles(request);
+    let mut united_groups = Vec::new();
+    for (settings, group) in cut_groups::group_by_settings(&pool) {
+        united_groups.push((settings, get_geometry_unioned_from_pool(&board_poly, &group)?));
+    }
+
+    let mut written = export_verify::WrittenGeometry::default();
+    written.add_polygon(&board_poly);
+    for (_, united) in &united_groups {
+        for poly in &united.0 {
+            written.add_polygon(poly);
+        }
+    }
+    for circle in &isolated_circles {
+        written.circles.push((circle.x, circle.y, circle.diameter.unwrap_or(0.0) / 2.0));
+    }
+
+    write_dxf_document(out, |file, h_ms_br, next_handle| {
+        // Note: All entities in AC1015 should point to h_ms_br (Model Space) as owner
+        write_dxf_polygon(file, &board_poly, "OUTLINE", 7, h_ms_br, next_handle)?;
+
+        // Override groups go on their own "CUTS_F300_P40" style layers
+        let mut override_layers: Vec<cut_groups::CutSettings> = Vec::new();
+        let mut color_of = |settings: cut_groups::CutSettings| {
+            if settings.is_default() { return 1; }
+            let index = override_layers.iter().position(|s| *s == settings).unwrap_or_else(|| {
+                override_layers.push(settings);
+                override_layers.len() - 1
+            });
+            (index % 5) as i32 + 2
+        };
+
+        for (settings, united) in &united_groups {
+            let color = color_of(*settings);
+            for poly in &united.0 {
+                write_dxf_polygon(file, poly, &settings.dxf_layer(), color, h_ms_br, next_handle)?;
+            }
+        }
+
+        for circle in isolated_circles {
+            let r = circle.diameter.unwrap_or(0.0) / 2.0;
+            let settings = cut_groups::CutSettings::of(&circle);
+            write_dxf_circle(file, circle.x, circle.y, r, &settings.dxf_layer(), color_of(settings), h_ms_br, next_handle)?;
+        }
+        Ok(())
+    })?;
+    Ok(written)
+}
+
+/// Carve-mode DXF written to `request.filepath`; see `write_depth_map_dxf`.
+pub fn generate_depth_map_dxf(request: &ExportRequest, progress: Option<&ProgressSink>) -> Result<export_verify::WrittenGeometry, Box<dyn std::error::Error>> {
+    write_file(&request.filepath, progress, |out| write_depth_map_dxf(request, out))
+}
+
+/// Carve-mode DXF: the board outline plus the boundary of every depth region, each depth on
+/// its own layer ("DEPTH_1.500" for 1.5 mm). With `dxf_hatch` set, every region is also
+/// written as a solid HATCH at elevation -depth, for CAM packages that read filled regions.
+/// Not mirrored for bottom carving, so it lines up with the profile DXF.
+pub fn write_depth_map_dxf(request: &ExportRequest, out: &mut dyn Write) -> Result<export_verify::WrittenGeometry, Box<dyn std::error::Error>> {
+    let (board_poly, regions) = get_depth_regions(request).ok_or("Board outline is missing")?;
+
+    let mut written = export_verify::WrittenGeometry::default();
+    written.add_polygon(&board_poly);
+    for (_, region) in &regions {
+        for poly in &region.0 {
+            written.add_polygon(poly);
+        }
+    }
+
+    write_dxf_document(out, |file, h_ms_br, next_handle| {
+        write_dxf_polygon(file, &board_poly, "OUTLINE", 7, h_ms_br, next_handle)?;
+
+        for (i, (depth, region)) in regions.iter().enumerate() {
+            let layer = format!("DEPTH_{:.3}", depth);
+            let color = (i % 6) as i32 + 1; // Cycle the basic ACI colours so adjacent depths differ
+            for poly in &region.0 {
+                write_dxf_polygon(file, poly, &layer, color, h_ms_br, next_handle)?;
+            }
+            if request.dxf_hatch {
+                write_dxf_hatch(file, region, -depth, &layer, color, h_ms_br, next_handle)?;
+            }
+        }
+        Ok(())
+    })?;
+    Ok(written)
+}
+
+/// Writes a complete AC1015 DXF to `path`, streaming with progress reports when `progress` is
+/// given; see `write_dxf_document`.
+pub(crate) fn write_dxf_file(
+    path: &str,
+    progress: Option<&ProgressSink>,
+    write_entities: impl FnOnce(&mut dyn Write, &str, &mut dyn FnMut() -> String) -> std::io::Result<()>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    write_file(path, progress, |out| write_dxf_document(out, write_entities))
+}
+
+/// Writes a complete AC1015 DXF; `write_entities` fills the ENTITIES section. It receives
+/// the output, the model space owner handle and a handle allocator.
+fn write_dxf_document(
+    file: &mut dyn Write,
+    write_entities: impl FnOnce(&mut dyn Write, &str, &mut dyn FnMut() -> String) -> std::io::Result<()>,
+) -> Result<(), Box<dyn std::error::Error>> {
+
+    // Handle Management
+    // AC1015 requires a logical hierarchy. We'll reserve low handles for system objects.
+    let mut handle_counter = 0x30; // Start entity handles after system objects
+    let mut next_handle = || {
+        handle_counter += 1;
+        format!("{:X}", handle_counter)
+    };
+
+    // Constant handles for the mandatory structural objects
+    let h_root_dict = "10";
+    let h_layout_dict = "11";
+    let h_ms_br = "12"; // Model Space Block Record
+    let h_ps_br = "13"; // Paper Space Block Record
+    let h_ms_layout = "14"; // Model Space Layout Object
+    let h_ps_layout = "15"; // Paper Space Layout Object
+
+    // 1. HEADER SECTION
+    writeln!(file, "  0\nSECTION\n  2\nHEADER")?;
+    writeln!(file, "  9\n$ACADVER\n  1\nAC1015")?;    // Target DXF 2000
+    writeln!(file, "  9\n$DWGCODEPAGE\n  3\nANSI_1252")?; // Essential for AC1015
+    writeln!(file, "  9\n$INSUNITS\n 70\n4")?;       // Millimeters
+    writeln!(file, "  9\n$MEASUREMENT\n 70\n1")?;    // Metric
+    // $HANDSEED must be higher than the last handle used in the file
+    writeln!(file, "  9\n$HANDSEED\n  5\nFFFF")?; 
+    writeln!(file, "  0\nENDSEC")?;
+
+    // 2. TABLES SECTION
+    writeln!(file, "  0\nSECTION\n  2\nTABLES")?;
+    
+    // Block Record Table (Mandatory for AC1015)
+    writeln!(file, "  0\nTABLE\n  2\nBLOCK_RECORD\n  5\n1\n100\nAcDbSymbolTable\n 70\n2")?;
+    
+    // Model Space Record
+    writeln!(file, "  0\nBLOCK_RECORD\n  5\n{}\n100\nAcDbSymbolTableRecord\n100\nAcDbBlockTableRecord\n  2\n*MODEL_SPACE", h_ms_br)?;
+    writeln!(file, "340\n{}", h_ms_layout)?; // Pointer to Layout Object
+    
+    // Paper Space Record
+    writeln!(file, "  0\nBLOCK_RECORD\n  5\n{}\n100\nAcDbSymbolTableRecord\n100\nAcDbBlockTableRecord\n  2\n*PAPER_SPACE", h_ps_br)?;
+    writeln!(file, "340\n{}", h_ps_layout)?; // Pointer to Layout Object
+    
+    writeln!(file, "  0\nENDTAB")?;
+    
+    // Minimal Layer Table
+    writeln!(file, "  0\nTABLE\n  2\nLAYER\n  5\n2\n100\nAcDbSymbolTable\n 70\n2")?;
+    writeln!(file, "  0\nLAYER\n  5\n16\n100\nAcDbSymbolTableRecord\n100\nAcDbLayerTableRecord\n  2\n0\n 70\n0\n 62\n7\n  6\nContinuous")?;
+    writeln!(file, "  0\nENDTAB")?;
+    
+    writeln!(file, "  0\nENDSEC")?;
+
+    // 3. BLOCKS SECTION
+    // Definitions for the Model and Paper space containers
+    writeln!(file, "  0\nSECTION\n  2\nBLOCKS")?;
+    
+    // Model Space Block Definition
+    writeln!(file, "  0\nBLOCK\n  5\n17\n330\n{}\n100\nAcDbEntity\n  8\n0\n100\nAcDbBlockBegin\n  2\n*MODEL_SPACE\n 70\n0\n 10\n0\n 20\n0\n 30\n0\n  3\n*MODEL_SPACE", h_ms_br)?;
+    writeln!(file, "  0\nENDBLK\n  5\n18\n330\n{}\n100\nAcDbEntity\n  8\n0\n100\nAcDbBlockEnd", h_ms_br)?;
+    
+    // Paper Space Block Definition
+    writeln!(file, "  0\nBLOCK\n  5\n19\n330\n{}\n100\nAcDbEntity\n  8\n0\n100\nAcDbBlockBegin\n  2\n*PAPER_SPACE\n 70\n0\n 10\n0\n 20\n0\n 30\n0\n  3\n*PAPER_SPACE", h_ps_br)?;
+    writeln!(file, "  0\nENDBLK\n  5\n1A\n330\n{}\n100\nAcDbEntity\n  8\n0\n100\nAcDbBlockEnd", h_ps_br)?;
+    
+    writeln!(file, "  0\nENDSEC")?;
+
+    // 4. ENTITIES SECTION
+    writeln!(file, "  0\nSECTION\n  2\nENTITIES")?;
+
+    write_entities(file, h_ms_br, &mut next_handle)?;
+
+    writeln!(file, "  0\nENDSEC")?;
+
+    // 5. OBJECTS SECTION (The critical addition for AC1015 compatibility)
+    writeln!(file, "  0\nSECTION\n  2\nOBJECTS")?;
+    
+    // Root Dictionary
+    writeln!(file, "  0\nDICTIONARY\n  5\n{}\n100\nAcDbDictionary\n  3\nACAD_LAYOUT", h_root_dict)?;
+    writeln!(file, "350\n{}", h_layout_dict)?;
+    
+    // Layout Dictionary
+    writeln!(file, "  0\nDICTIONARY\n  5\n{}\n330\n{}\n100\nAcDbDictionary", h_layout_dict, h_root_dict)?;
+    writeln!(file, "  3\nModel\n350\n{}", h_ms_layout)?;
+    writeln!(file, "  3\nLayout1\n350\n{}", h_ps_layout)?;
+    
+    // Model Space Layout Object
+    writeln!(file, "  0\nLAYOUT\n  5\n{}\n330\n{}\n100\nAcDbPlotSettings\n100\nAcDbLayout", h_ms_layout, h_layout_dict)?;
+    writeln!(file, "  1\nModel\n 70\n1\n 71\n0")?; // Layout name and flags
+    writeln!(file, "330\n{}", h_ms_br)?; // Pointer back to Block Record (Bidirectional)
+
+    // Paper Space Layout Object
+    writeln!(file, "  0\nLAYOUT\n  5\n{}\n330\n{}\n100\nAcDbPlotSettings\n100\nAcDbLayout", h_ps_layout, h_layout_dict)?;
+    writeln!(file, "  1\nLayout1\n 70\n1\n 71\n1")?;
+    writeln!(file, "330\n{}", h_ps_br)?; // Pointer back to Block Record (Bidirectional)
+    
+    writeln!(file, "  0\nENDSEC")?;
+
+    writeln!(file, "  0\nEOF")?;
+
+    Ok(())
+}
+
+pub(crate) fn write_dxf_polygon(
+    file: &mut dyn Write, 
+    poly: &Polygon<f64>, 
+    layer: &str, 
+    color: i32, 
+    owner: &str,
+    next_handle: &mut dyn FnMut() -> String
+) -> std::io::Result<()> {
+    write_dxf_polyline(file, poly.exterior(), layer, color, owner, next_handle)?;
+    for interior in poly.interiors() {
+        write_dxf_polyline(file, interior, layer, color, owner, next_handle)?;
+    }
+    Ok(())
+}
+
+/// Solid-filled HATCH covering `region`; every exterior and interior ring becomes a
+/// polyline boundary path and the default odd-parity style leaves the holes empty.
+#[allow(clippy::too_many_arguments)]
+fn write_dxf_hatch(
+    file: &mut dyn Write,
+    region: &MultiPolygon<f64>,
+    elevation: f64,
+    layer: &str,
+    color: i32,
+    owner: &str,
+    next_handle: &mut dyn FnMut() -> String
+) -> std::io::Result<()> {
+    let rings: Vec<(&LineString<f64>, bool)> = region.0.iter()
+        .flat_map(|p| std::iter::once((p.exterior(), true)).chain(p.interiors().iter().map(|r| (r, false))))
+        .collect();
+
+    writeln!(file, "  0\nHATCH")?;
+    writeln!(file, "  5\n{}", next_handle())?;
+    writeln!(file, "330\n{}", owner)?;
+    writeln!(file, "100\nAcDbEntity\n  8\n{}\n 62\n{}\n100\nAcDbHatch", layer, color)?;
+    writeln!(file, " 10\n0.0\n 20\n0.0\n 30\n{:.4}", elevation)?; // Elevation point
+    writeln!(file, "210\n0.0\n220\n0.0\n230\n1.0")?;          // Extrusion: +Z
+    writeln!(file, "  2\nSOLID\n 70\n1\n 71\n0")?;              // Solid fill, not associative
+    writeln!(file, " 91\n{}", rings.len())?;
+
+    for (ring, exterior) in rings {
+        let mut coords = &ring.0[..];
+        if coords.len() > 1 && coords.first() == coords.last() {
+            coords = &coords[..coords.len() - 1];
+        }
+        // Flag 2 = polyline path, 1 = external boundary
+        writeln!(file, " 92\n{}", if exterior { 3 } else { 2 })?;
+        writeln!(file, " 72\n0\n 73\n1\n 93\n{}", coords.len())?; // No bulges, closed
+        for coord in coords {
+            writeln!(file, " 10\n{:.4}", coord.x)?;
+            writeln!(file, " 20\n{:.4}", coord.y)?;
+        }
+        writeln!(file, " 97\n0")?; // No source boundary objects
+    }
+
+    writeln!(file, " 75\n0\n 76\n1")?; // Odd-parity style, predefined pattern
+    writeln!(file, " 98\n0")?;         // No seed points
+    Ok(())
+}
+
+fn write_dxf_polyline(
+    file: &mut dyn Write, 
+    ls: &LineString<f64>, 
+    layer: &str, 
+    color: i32, 
+    owner: &str,
+    next_handle: &mut dyn FnMut() -> String
+) -> std::io::Result<()> {
+    let mut coords = &ls.0[..];
+    if coords.is_empty() { return Ok(()); }
+    
+    if coords.len() > 1 && coords.first() == coords.last() {
+        coords = &coords[..coords.len() - 1];
+    }
+
+    writeln!(file, "  0\nLWPOLYLINE")?;
+    writeln!(file, "  5\n{}", next_handle())?;       // Unique Handle
+    writeln!(file, "330\n{}", owner)?;               // Ownership link
+    writeln!(file, "100\nAcDbEntity")?;             // Subclass marker
+    writeln!(file, "  8\n{}", layer)?;
+    writeln!(file, " 62\n{}", color)?;
+    writeln!(file, "100\nAcDbPolyline")?;           // Class-specific marker
+    writeln!(file, " 90\n{}", coords.len())?;
+    writeln!(file, " 70\n1")?;                      // Flag 1 = Closed loop
+    
+    for coord in coords {
+        writeln!(file, " 10\n{:.4}", coord.x)?;
+        writeln!(file, " 20\n{:.4}", coord.y)?;
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_dxf_circle(
+    file: &mut dyn Write,
+    cx: f64,
+    cy: f64,
+    r: f64,
+    layer: &str,
+    color: i32,
+    owner: &str,
+    next_handle: &mut dyn FnMut() -> String
+) -> std::io::Result<()> {
+    writeln!(file, "  0\nCIRCLE")?;
+    writeln!(file, "  5\n{}", next_handle())?;
+    writeln!(file, "330\n{}", owner)?;
+    writeln!(file, "100\nAcDbEntity\n  8\n{}\n 62\n{}\n100\nAcDbCircle", layer, color)?;
+    writeln!(file, " 10\n{:.4}\n 20\n{:.4}\n 30\n0.0", cx, cy)?;
+    writeln!(file, " 40\n{:.4}", r)?;
+    Ok(())
+}
+
+pub(crate) fn write_dxf_line(
+    file: &mut dyn Write,
+    from: Coord<f64>,
+    to: Coord<f64>,
+    layer: &str,
+    color: i32,
+    owner: &str,
+    next_handle: &mut dyn FnMut() -> String
+) -> std::io::Result<()> {
+    writeln!(file, "  0\nLINE")?;
+    writeln!(file, "  5\n{}", next_handle())?;
+    writeln!(file, "330\n{}", owner)?;
+    writeln!(file, "100\nAcDbEntity\n  8\n{}\n 62\n{}\n100\nAcDbLine", layer, color)?;
+    writeln!(file, " 10\n{:.4}\n 20\n{:.4}\n 30\n0.0", from.x, from.y)?;
+    writeln!(file, " 11\n{:.4}\n 21\n{:.4}\n 31\n0.0", to.x, to.y)?;
+    Ok(())
+}
+
+/// Outline polygon of one shape (circle, rect, stroked line, or bezier path); None when the
+/// shape is degenerate or of an unknown type.
+pub fn shape_to_polygon(shape: &ExportShape) -> Option<Polygon<f64>> {
+    match shape.shape_type.as_str() {
+        "rect" => {
+            let w = shape.width.unwrap_or(0.0);
+            let h = shape.height.unwrap_or(0.0);
+            let cx = shape.x;
+            let cy = shape.y;
+            let angle_deg = shape.angle.unwrap_or(0.0);
+            let r = shape.corner_radius.unwrap_or(0.0);
+
+            // If radius is effectively 0, standard rect
+            if r < 0.001 {
+                let half_w = w / 2.0;
+                let half_h = h / 2.0;
+                let corners = [
+                    (-half_w, -half_h),
+                    (half_w, -half_h),
+                    (half_w, half_h),
+                    (-half_w, half_h),
+                ];
+                let rad = angle_deg.to_radians();
+                let cos_a = rad.cos();
+                let sin_a = rad.sin();
+                let rotated_coords: Vec<Coord<f64>> = corners.iter().map(|(x, y)| {
+                    Coord {
+                        x: cx + (x * cos_a - y * sin_a),
+                        y: cy + (x * sin_a + y * cos_a),
+                    }
+                }).collect();
+                return Some(Polygon::new(LineString::new(rotated_coords), vec![]));
+            }
+
+            // Rounded Rect
+            let steps_per_corner = 12;
+            let mut coords = Vec::new();
+            let half_w = w / 2.0;
+            let half_h = h / 2.0;
+            // Clamp radius
+            let safe_r = r.min(half_w).min(half_h);
+
+            // 4 quadrants
+            let quadrants = vec![
+                (half_w - safe_r, -half_h + safe_r, -std::f64::consts::FRAC_PI_2), // Bottom Right
+                (half_w - safe_r, half_h - safe_r, 0.0), // Top Right
+                (-half_w + safe_r, half_h - safe_r, std::f64::consts::FRAC_PI_2), // Top Left
+                (-half_w + safe_r, -half_h + safe_r, PI), // Bottom Left
+            ];
+
+            for (qx, qy, start_angle) in quadrants {
+                for i in 0..=steps_per_corner {
+                     let theta = start_angle + (i as f64 / steps_per_corner as f64) * std::f64::consts::FRAC_PI_2;
+                     coords.push((qx + safe_r * theta.cos(), qy + safe_r * theta.sin()));
+                }
+            }
+            
+            // Rotate and Translate
+            let rad = angle_deg.to_radians();
+            let cos_a = rad.cos();
+            let sin_a = rad.sin();
+
+            let final_coords: Vec<Coord<f64>> = coords.iter().map(|(x, y)| {
+                Coord {
+                    x: cx + (x * cos_a - y * sin_a),
+                    y: cy + (x * sin_a + y * cos_a),
+                }
+            }).collect();
+
+            Some(Polygon::new(LineString::new(final_coords), vec![]))
+        },
+        "circle" => {
+            let d = shape.diameter.unwrap_or(0.0);
+            let r = d / 2.0;
+            let cx = shape.x;
+            let cy = shape.y;
+            let steps = 64;
+            let mut coords = Vec::with_capacity(steps);
+            for i in 0..steps {
+                let theta = (i as f64 / steps as f64) * 2.0 * PI;
+                coords.push(Coord {
+                    x: cx + r * theta.cos(),
+                    y: cy + r * theta.sin(),
+                });
+            }
+            Some(Polygon::new(LineString::new(coords), vec![]))
+        },
+        "line" => {
+            if let Some(pts) = &shape.points {
+                 if pts.len() < 2 { return None; }
+                 let thickness = shape.thickness.unwrap_or(1.0).max(0.001);
+                 
+                 // Discretize centerline
+                 let center_ls = discretize_path(pts);
+                 // Stroke
+                 Some(stroke_linestring(&center_ls, thickness))
+            } else {
+                None
+            }
+        },
+        "polygon" => {
+            if let Some(pts) = &shape.points {
+                 if pts.len() < 3 { return None; }
+                 // Use discretize_path_closed to handle potential handles, 
+                 // though dense polygons from JS usually have none.
+                 let ls = discretize_path_closed(pts);
+                 Some(Polygon::new(ls, vec![]))
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
+
+pub(crate) fn polygon_to_path_data(poly: &Polygon<f64>) -> Data {
+    let mut data = Data::new();
+    data = append_linestring_to_data(data, poly.exterior());
+    for interior in poly.interiors() {
+        data = append_linestring_to_data(data, interior);
+    }
+    data
+}
+
+fn append_polygon_to_data(data: Data, poly: &Polygon<f64>) -> Data {
+    let mut d = append_linestring_to_data(data, poly.exterior());
+    for interior in poly.interiors() {
+        d = append_linestring_to_data(d, interior);
+    }
+    d
+}
+
+fn append_linestring_to_data(data: Data, ls: &LineString<f64>) -> Data {
+    let mut d = data;
+    let coords = ls.0.as_slice();
+    if coords.is_empty() {
+        return d;
+    }
+    d = d.move_to((coords[0].x, coords[0].y));
+    for coord in &coords[1..] {
+        d = d.line_to((coord.x, coord.y));
+    }
+    d = d.close();
+    d
+}
+
+fn ring_points(ls: &LineString<f64>) -> Vec<[f64; 2]> {
+    ls.0.iter().map(|c| [c.x, c.y]).collect()
+}
+
+/// The single layer an export request describes, in board coordinates at z = 0. "Cut"
+/// layers lose the union of their shapes as through cuts; carved layers get one cut per
+/// visible depth region, from the bottom face when `cut_direction` is "Bottom".
+pub fn export_request_layer(request: &ExportRequest) -> Result<fem::geo_builder::GeoLayer, Message> {
+    if request.outline.is_empty() {
+        return Err(Message::outline_missing());
+    }
+    if request.layer_thickness <= 0.0 {
+        return Err(Message::not_positive("layer_thickness", "Layer thickness must be positive"));
+    }
+    let board = Polygon::new(discretize_path_closed(&request.outline), vec![]);
+    let regions: DepthRegions = if request.machining_type == "Carved/Printed" {
+        get_depth_regions(request).map(|(_, regions)| regions).unwrap_or_default()
+    } else {
+        let cuts = get_geometry_unioned_from_pool(&board, &request.shapes)
+            .map_err(|e| Message::new(MessageCode::BooleanUnionFailed, format!("Failed to union shapes: {}", e)).with("error", e))?;
+        vec![(request.layer_thickness, cuts)]
+    };
+
+    let mut cuts = Vec::new();
+    for (depth, region) in regions {
+        for (n, poly) in region.0.iter().enumerate() {
+            cuts.push(fem::geo_builder::GeoCut {
+                id: format!("depth_{}_{}", depth, n),
+                exterior: ring_points(poly.exterior()),
+                interiors: poly.interiors().iter().map(ring_points).collect(),
+                depth,
+                from_bottom: request.cut_direction == "Bottom",
+            });
+        }
+    }
+
+    Ok(fem::geo_builder::GeoLayer {
+        id: "export".into(),
+        z: 0.0,
+        thickness: request.layer_thickness,
+        outline: ring_points(board.exterior()),
+        cuts,
+    })
+}
+
+/// One stackup layer's export request, for `stackup_layers`.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct StackupLayerRequest {
+    /// Stackup layer id, kept as the GeoLayer id
+    pub id: String,
+    /// The layer's export request
+    pub request: ExportRequest,
+}
+
+/// Meshable layers for a whole stackup from one export request per layer, listed in
+/// stackup order (top layer first). Layers are stacked bottom-up from z = 0 like the 3D view.
+pub fn stackup_layers(layers: &[StackupLayerRequest]) -> Result<Vec<fem::geo_builder::GeoLayer>, Message> {
+    let mut z = 0.0;
+    let mut out = Vec::with_capacity(layers.len());
+    for layer in layers.iter().rev() {
+        let mut geo = export_request_layer(&layer.request)?;
+        geo.id = layer.id.clone();
+        geo.z = z;
+        z += geo.thickness;
+        out.push(geo);
+    }
+    Ok(out)
+}
+
@@ -0,0 +1,154 @@
+//! Streams export files to disk as they are generated, reporting bytes-written progress
+//! through a callback (the app forwards it to the frontend as "export-progress" events).
+//!
+//! Rust-side exports (SVG, DXF) write each element through a `ProgressWriter` as soon as its
+//! geometry is ready rather than building the whole document first. Files whose content is
+//! produced by the frontend (STL meshes, G-code) arrive in chunks through the
+//! begin/append/finish stream commands, so neither side holds a second full copy.
+use crate::messages::{Message, MessageCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Receives progress reports; shared between the writer and whoever started the export.
+pub type ProgressSink = Arc<dyn Fn(&ExportProgress) + Send + Sync>;
+
+/// Bytes between progress events.
+const PROGRESS_INTERVAL: u64 = 1 << 20;
+
+/// Payload of an "export-progress" event.
+#[derive(Debug, Serialize, Clone)]
+pub struct ExportProgress {
+    /// File being written
+    pub path: String,
+    /// Bytes written so far
+    pub bytes_written: u64,
+    /// Set on the last report, once the file is flushed
+    pub done: bool,
+}
+
+/// Buffered file writer that counts bytes and reports progress every MiB.
+/// Without a sink (calibration grids, tests) it only counts.
+pub struct ProgressWriter {
+    inner: BufWriter<File>,
+    path: String,
+    written: u64,
+    reported: u64,
+    progress: Option<ProgressSink>,
+}
+
+impl ProgressWriter {
+    /// Creates (truncates) the file at `path`.
+    pub fn create(path: &str, progress: Option<&ProgressSink>) -> io::Result<Self> {
+        Ok(Self {
+            inner: BufWriter::new(File::create(path)?),
+            path: path.to_string(),
+            written: 0,
+            reported: 0,
+            progress: progress.cloned(),
+        })
+    }
+
+    /// Bytes written so far.
+    pub fn bytes_written(&self) -> u64 {
+        self.written
+    }
+
+    fn report(&mut self, done: bool) {
+        self.reported = self.written;
+        if let Some(progress) = &self.progress {
+            progress(&ExportProgress { path: self.path.clone(), bytes_written: self.written, done });
+        }
+    }
+
+    /// Flushes to disk and sends the final report; returns the file size.
+    pub fn finish(mut self) -> io::Result<u64> {
+        self.inner.flush()?;
+        self.report(true);
+        Ok(self.written)
+    }
+}
+
+impl Write for ProgressWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        if self.written - self.reported >= PROGRESS_INTERVAL {
+            self.report(false);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Opening `<svg>` tag matching what `svg::Document` writes for our exports, so elements can
+/// follow one at a time. `style` is added for the depth map's black background.
+pub fn write_svg_start(out: &mut dyn Write, min_x: f64, min_y: f64, width: f64, height: f64, style: Option<&str>) -> io::Result<()> {
+    write!(out, "<svg height=\"{}mm\"", height)?;
+    if let Some(style) = style {
+        write!(out, " style=\"{}\"", style)?;
+    }
+    writeln!(out, " viewBox=\"{} {} {} {}\" width=\"{}mm\" xmlns=\"http://www.w3.org/2000/svg\">", min_x, min_y, width, height, width)
+}
+
+/// Writes one element; it can be dropped straight after.
+pub fn write_svg_node(out: &mut dyn Write, node: &impl svg::Node) -> io::Result<()> {
+    writeln!(out, "{}", node)
+}
+
+/// Closing tag for `write_svg_start`.
+pub fn write_svg_end(out: &mut dyn Write) -> io::Result<()> {
+    writeln!(out, "</svg>")
+}
+
+fn unknown_stream(id: u64) -> Message {
+    Message::new(MessageCode::ExportStreamUnknown, format!("Unknown export stream {}", id)).with("id", id)
+}
+
+/// Files fed in chunks by the caller (the frontend, in the app) currently open, keyed by
+/// stream id.
+#[derive(Default)]
+pub struct ExportStreams {
+    next_id: AtomicU64,
+    open: Mutex<HashMap<u64, (PathBuf, ProgressWriter)>>,
+}
+
+impl ExportStreams {
+    /// Opens a new stream writing to `path`; returns its id.
+    pub fn begin(&self, path: PathBuf, progress: Option<&ProgressSink>) -> io::Result<u64> {
+        let writer = ProgressWriter::create(&path.to_string_lossy(), progress)?;
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+        self.open.lock().unwrap().insert(id, (path, writer));
+        Ok(id)
+    }
+
+    /// Appends a chunk; returns the bytes written so far.
+    pub fn append(&self, id: u64, chunk: &[u8]) -> Result<u64, Message> {
+        let mut open = self.open.lock().unwrap();
+        let (path, writer) = open.get_mut(&id).ok_or_else(|| unknown_stream(id))?;
+        writer.write_all(chunk).map_err(|e| Message::write_failed(&*path, e))?;
+        Ok(writer.bytes_written())
+    }
+
+    /// Closes the stream; returns the file path and its size.
+    pub fn finish(&self, id: u64) -> Result<(PathBuf, u64), Message> {
+        let (path, writer) = self.open.lock().unwrap().remove(&id).ok_or_else(|| unknown_stream(id))?;
+        let size = writer.finish().map_err(|e| Message::write_failed(&path, e))?;
+        Ok((path, size))
+    }
+
+    /// Abandons the stream and deletes the partial file.
+    pub fn cancel(&self, id: u64) {
+        if let Some((path, writer)) = self.open.lock().unwrap().remove(&id) {
+            drop(writer);
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+//! Re-reads a written SVG/DXF and compares it with the geometry the exporter meant to write.
+use geo::{Coord, LineString};
+use svg::node::element::path::{Command, Data, Position};
+use svg::parser::Event;
+
+/// Largest coordinate drift accepted between source and file (mm). DXF is written with
+/// 4 decimals and SVG path data goes through f32, both well inside this.
+pub const VERIFY_TOLERANCE: f64 = 1e-3;
+
+/// What an exporter wrote, in board coordinates (y up), in the order it wrote it.
+#[derive(Default)]
+pub struct WrittenGeometry {
+    /// Closed rings, exteriors and holes alike
+    pub rings: Vec<LineString<f64>>,
+    /// Circles written as native entities, as (cx, cy, r)
+    pub circles: Vec<(f64, f64, f64)>,
+}
+
+impl WrittenGeometry {
+    /// Records the exterior and every hole of `poly`.
+    pub fn add_polygon(&mut self, poly: &geo::Polygon<f64>) {
+        self.rings.push(poly.exterior().clone());
+        self.rings.extend(poly.interiors().iter().cloned());
+    }
+}
+
+/// Parsed file contents. `open_rings` counts polylines that were not closed.
+#[derive(Default)]
+struct ParsedGeometry {
+    rings: Vec<Vec<Coord<f64>>>,
+    circles: Vec<(f64, f64, f64)>,
+    open_rings: usize,
+}
+
+fn ring_points(ring: &LineString<f64>) -> Vec<Coord<f64>> {
+    let mut pts = ring.0.clone();
+    if pts.len() > 1 && pts.first() == pts.last() {
+        pts.pop();
+    }
+    pts
+}
+
+/// Ring read back from a file, without the repeated closing point.
+pub type Ring = Vec<Coord<f64>>;
+
+/// Rings of an exported path's `d` attribute (absolute M/L/Z only, as the exporters write
+/// them) in SVG coordinates, without closing duplicates, plus how many were left open.
+pub fn path_rings(d: &str) -> Result<(Vec<Ring>, usize), String> {
+    let data = Data::parse(d).map_err(|e| e.to_string())?;
+    let mut rings = Vec::new();
+    let mut open = 0;
+    let mut current: Vec<Coord<f64>> = Vec::new();
+    for command in data.iter() {
+        match command {
+            Command::Move(Position::Absolute, p) => {
+                if !current.is_empty() {
+                    open += 1;
+                    rings.push(std::mem::take(&mut current));
+                }
+                current.push(Coord { x: p[0] as f64, y: p[1] as f64 });
+            }
+            Command::Line(Position::Absolute, p) => {
+                for xy in p.chunks(2) {
+                    current.push(Coord { x: xy[0] as f64, y: xy[1] as f64 });
+                }
+            }
+            Command::Close => {
+                if current.len() > 1 && current.first() == current.last() {
+                    current.pop();
+                }
+                rings.push(std::mem::take(&mut current));
+            }
+            _ => return Err("Unexpected path command in export".into()),
+        }
+    }
+    if !current.is_empty() {
+        open += 1;
+        rings.push(current);
+    }
+    Ok((rings, open))
+}
+
+fn parse_svg(path: &str) -> Result<ParsedGeometry, String> {
+    let mut content = String::new();
+    let mut out = ParsedGeometry::default();
+
+    for event in svg::open(path, &mut content).map_err(|e| e.to_string())? {
+        let Event::Tag(tag, _, attrs) = event else { continue };
+        match tag {
+            "path" => {
+                let Some(d) = attrs.get("d") else { continue };
+                let (rings, open) = path_rings(d)?;
+                // Back to y-up board coordinates
+                out.rings.extend(rings.into_iter().map(|r| r.into_iter().map(|c| Coord { x: c.x, y: -c.y }).collect::<Vec<_>>()));
+                out.open_rings += open;
+            }
+            "circle" => {
+                let num = |k: &str| attrs.get(k).and_then(|v| v.parse::<f64>().ok());
+                if let (Some(cx), Some(cy), Some(r)) = (num("cx"), num("cy"), num("r")) {
+                    out.circles.push((cx, -cy, r));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+fn parse_dxf(path: &str) -> Result<ParsedGeometry, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let lines: Vec<&str> = content.lines().collect();
+    let pairs: Vec<(i32, &str)> = lines.chunks(2)
+        .filter_map(|c| Some((c[0].trim().parse::<i32>().ok()?, c.get(1)?.trim())))
+        .collect();
+
+    let mut out = ParsedGeometry::default();
+    let mut in_entities = false;
+    let mut i = 0;
+    while i < pairs.len() {
+        let (code, value) = pairs[i];
+        i += 1;
+        if code == 2 && value == "ENTITIES" { in_entities = true; }
+        if code != 0 || !in_entities { continue; }
+        if value == "ENDSEC" { break; }
+
+        // Group codes of this entity, up to the next 0
+        let start = i;
+        while i < pairs.len() && pairs[i].0 != 0 { i += 1; }
+        let body = &pairs[start..i];
+        let num = |c: i32| body.iter().find(|p| p.0 == c).and_then(|p| p.1.parse::<f64>().ok());
+
+        match value {
+            "LWPOLYLINE" => {
+                let xs = body.iter().filter(|p| p.0 == 10).filter_map(|p| p.1.parse::<f64>().ok());
+                let ys = body.iter().filter(|p| p.0 == 20).filter_map(|p| p.1.parse::<f64>().ok());
+                let ring: Vec<Coord<f64>> = xs.zip(ys).map(|(x, y)| Coord { x, y }).collect();
+                if num(70).unwrap_or(0.0) as i32 & 1 == 0 {
+                    out.open_rings += 1;
+                }
+                out.rings.push(ring);
+            }
+            "CIRCLE" => {
+                if let (Some(x), Some(y), Some(r)) = (num(10), num(20), num(40)) {
+                    out.circles.push((x, y, r));
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Compares the file at `path` with `expected`, entity by entity in write order. Returns
+/// the problems found; an empty list means the file is verified.
+pub fn verify_export(path: &str, file_type: &str, expected: &WrittenGeometry) -> Vec<String> {
+    let parsed = match file_type {
+        "SVG" => parse_svg(path),
+        "DXF" => parse_dxf(path),
+        other => Err(format!("Cannot verify {} files", other)),
+    };
+    let parsed = match parsed {
+        Ok(p) => p,
+        Err(e) => return vec![format!("Failed to re-read export: {}", e)],
+    };
+
+    let mut issues = Vec::new();
+    if parsed.open_rings > 0 {
+        issues.push(format!("{} polyline(s) are not closed", parsed.open_rings));
+    }
+    if parsed.rings.len() != expected.rings.len() {
+        issues.push(format!("Expected {} closed outlines, found {}", expected.rings.len(), parsed.rings.len()));
+    }
+    for (i, (want, got)) in expected.rings.iter().zip(&parsed.rings).enumerate() {
+        let want = ring_points(want);
+        if want.len() != got.len() {
+            issues.push(format!("Outline {}: expected {} points, found {}", i, want.len(), got.len()));
+            continue;
+        }
+        let drift = want.iter().zip(got).map(|(a, b)| (a.x - b.x).abs().max((a.y - b.y).abs())).fold(0.0, f64::max);
+        if drift > VERIFY_TOLERANCE {
+            issues.push(format!("Outline {}: points moved by up to {:.6} mm", i, drift));
+        }
+    }
+
+    if parsed.circles.len() != expected.circles.len() {
+        issues.push(format!("Expected {} circles, found {}", expected.circles.len(), parsed.circles.len()));
+    }
+    for (i, (want, got)) in expected.circles.iter().zip(&parsed.circles).enumerate() {
+        let drift = (want.0 - got.0).abs().max((want.1 - got.1).abs()).max((want.2 - got.2).abs());
+        if drift > VERIFY_TOLERANCE {
+            issues.push(format!("Circle {}: moved or resized by {:.6} mm", i, drift));
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{ExportRequest, write_profile_dxf, write_profile_svg};
+
+    fn request() -> ExportRequest {
+        let corner = |x: f64, y: f64| serde_json::json!({ "x": x, "y": y, "handle_in": null, "handle_out": null });
+        let shape = |shape_type: &str, width: Option<f64>, diameter: Option<f64>| serde_json::json!({
+            "shape_type": shape_type, "x": 40.0, "y": 30.0, "width": width, "height": width, "diameter": diameter,
+            "angle": null, "corner_radius": null, "thickness": null, "points": null, "depth": 2.0,
+            "endmill_radius": null, "feed": null, "speed": null, "power": null,
+        });
+        serde_json::from_value(serde_json::json!({
+            "filepath": "", "file_type": "DXF", "machining_type": "Cut", "cut_direction": "Top",
+            "outline": [corner(0.0, 0.0), corner(100.0, 0.0), corner(100.0, 60.0), corner(0.0, 60.0)],
+            "shapes": [shape("rect", Some(20.0), None), shape("circle", None, Some(10.0))],
+            "layer_thickness": 2.0, "stl_content": null,
+        })).unwrap()
+    }
+
+    fn write(name: &str, bytes: &[u8]) -> String {
+        let path = std::env::temp_dir().join(format!("shortstack_verify_{}_{}", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_profile_exports_verify_clean() {
+        let mut bytes = Vec::new();
+        let written = write_profile_svg(&request(), &mut bytes).unwrap();
+        let path = write("profile.svg", &bytes);
+        let issues = verify_export(&path, "SVG", &written);
+        std::fs::remove_file(&path).ok();
+        assert!(issues.is_empty(), "{:?}", issues);
+
+        let mut bytes = Vec::new();
+        let written = write_profile_dxf(&request(), &mut bytes).unwrap();
+        let path = write("profile.dxf", &bytes);
+        let issues = verify_export(&path, "DXF", &written);
+        std::fs::remove_file(&path).ok();
+        assert!(issues.is_empty(), "{:?}", issues);
+    }
+
+    #[test]
+    fn test_moved_and_missing_outlines_are_reported() {
+        let mut bytes = Vec::new();
+        let mut written = write_profile_dxf(&request(), &mut bytes).unwrap();
+        let path = write("drift.dxf", &bytes);
+
+        written.rings[0].0[1].x += 0.01;
+        written.rings.push(LineString::from(vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0)]));
+        let issues = verify_export(&path, "DXF", &written);
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(issues.len(), 2, "{:?}", issues);
+        assert!(issues[0].starts_with("Expected"));
+        assert!(issues[1].starts_with("Outline 0: points moved"));
+    }
+
+    #[test]
+    fn test_path_rings_counts_open_paths() {
+        let (rings, open) = path_rings("M 0 0 L 10 0 L 10 10 Z M 20 20 L 30 20 L 30 30").unwrap();
+        assert_eq!(rings.len(), 2);
+        assert_eq!(rings[0].len(), 3);
+        assert_eq!(open, 1);
+
+        assert!(path_rings("M 0 0 C 1 1 2 2 3 3").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+//! Global stiffness assembly with per-element quadrature chosen from Jacobian quality.
+use nalgebra::{SMatrix, Vector3};
+use serde::Serialize;
+use crate::messages::{Message, MessageCode};
+use super::material::Material;
+use super::mesh::TetMesh;
+use super::quadrature::{IntegrationPoint, TetQuadrature};
+use super::tet10::Tet10;
+
+/// min(det J) / max(det J) above which the element is treated as undistorted.
+/// Straight-sided Tet10s have constant J (ratio 1) and B^T C B is quadratic, so the
+/// 4-point rule is exact.
+pub const QUALITY_STANDARD: f64 = 0.8;
+/// Below this ratio even the upgraded rule under-integrates noticeably.
+pub const QUALITY_REDUCED: f64 = 0.3;
+
+/// Elements that did not get the standard treatment.
+#[derive(Debug, Clone, Serialize)]
+pub struct ElementQualityReport {
+    /// Index into the mesh's elements
+    pub element: usize,
+    /// min(det J) / max(det J) over the element
+    pub jacobian_ratio: f64,
+    /// Quadrature points used for this element
+    pub rule_points: u8,
+    /// Results in this element should not be trusted
+    pub reduced_accuracy: bool,
+    /// The element is turned inside out (ratio <= 0); it cannot be assembled
+    pub inverted: bool,
+}
+
+/// Stiffness in coordinate (triplet) form plus the elements that needed special handling.
+#[derive(Debug, Clone, Serialize)]
+pub struct AssemblyResult {
+    /// Number of degrees of freedom (3 per node)
+    pub dofs: usize,
+    /// (row, column, value) entries; duplicates are summed
+    pub triplets: Vec<(usize, usize, f64)>,
+    /// Elements that got an upgraded rule
+    pub flagged: Vec<ElementQualityReport>,
+}
+
+fn element_nodes(mesh: &TetMesh, elem: &[usize; 10]) -> [Vector3<f64>; 10] {
+    let mut nodes = [Vector3::zeros(); 10];
+    for (i, &n) in elem.iter().enumerate() {
+        let v = mesh.vertices[n];
+        nodes[i] = Vector3::new(v[0], v[1], v[2]);
+    }
+    nodes
+}
+
+/// Ratio of the smallest to largest Jacobian determinant over the corners and the
+/// points of the highest rule we have. Negative means the element is inverted.
+pub fn jacobian_ratio(nodes: &[Vector3<f64>; 10]) -> f64 {
+    let corners = [
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+    let samples = corners.iter().copied().chain(TetQuadrature::get_rule(15).into_iter().map(|ip| ip.xi));
+
+    let mut min_det = f64::MAX;
+    let mut max_det = f64::MIN;
+    for xi in samples {
+        let det = Tet10::jacobian(nodes, &Tet10::shape_function_derivatives(&xi)).determinant();
+        min_det = min_det.min(det);
+        max_det = max_det.max(det);
+    }
+    if max_det <= 0.0 { -1.0 } else { min_det / max_det }
+}
+
+/// K_e = sum_q B^T C B |J| w_q
+pub fn element_stiffness(nodes: &[Vector3<f64>; 10], c: &nalgebra::Matrix6<f64>, rule: &[IntegrationPoint]) -> Option<SMatrix<f64, 30, 30>> {
+    let mut k = SMatrix::<f64, 30, 30>::zeros();
+    for ip in rule {
+        let local = Tet10::shape_function_derivatives(&ip.xi);
+        let j = Tet10::jacobian(nodes, &local);
+        let det = j.determinant();
+        let global = j.try_inverse()? * local;
+        let b = Tet10::b_matrix(&global);
+        k += b.transpose() * c * b * (det * ip.weight);
+    }
+    Some(k)
+}
+
+/// Chooses each element's rule from its Jacobian quality: undistorted elements use
+/// 4 points, distorted ones are upgraded to the 15-point (quintic-exact, positive-weight)
+/// rule, and badly distorted ones are additionally flagged as reduced accuracy. Inverted
+/// elements are reported as such rather than failing the whole mesh; assembly refuses them.
+/// Returns the point count per element and the reports for every non-standard element.
+pub fn quadrature_plan(mesh: &TetMesh) -> (Vec<u8>, Vec<ElementQualityReport>) {
+    let mut rules = Vec::with_capacity(mesh.indices.len());
+    let mut flagged = Vec::new();
+
+    for (e, elem) in mesh.indices.iter().enumerate() {
+        let ratio = jacobian_ratio(&element_nodes(mesh, elem));
+        let points = if ratio >= QUALITY_STANDARD { 4 } else { 15 };
+        if points != 4 {
+            flagged.push(ElementQualityReport {
+                element: e,
+                jacobian_ratio: ratio,
+                rule_points: points,
+                reduced_accuracy: ratio < QUALITY_REDUCED,
+                inverted: ratio <= 0.0,
+            });
+        }
+        rules.push(points);
+    }
+    (rules, flagged)
+}
+
+/// Assembles the global stiffness matrix using the rules from `quadrature_plan`.
+pub fn assemble_stiffness(mesh: &TetMesh, material: &dyn Material) -> Result<AssemblyResult, Message> {
+    let c = material.c_matrix();
+    let standard = TetQuadrature::get_rule(4);
+    let upgraded = TetQuadrature::get_rule(15);
+    let (rules, flagged) = quadrature_plan(mesh);
+    // An inverted element's stiffness would be meaningless
+    if let Some(bad) = flagged.iter().find(|r| r.inverted) {
+        return Err(Message::new(MessageCode::ElementInverted, format!("Element {} is inverted (Jacobian ratio {:.3})", bad.element, bad.jacobian_ratio))
+            .with("element", bad.element)
+            .with("jacobian_ratio", bad.jacobian_ratio));
+    }
+
+    let mut triplets = Vec::with_capacity(mesh.indices.len() * 900);
+    for (e, elem) in mesh.indices.iter().enumerate() {
+        let rule = if rules[e] == 4 { &standard } else { &upgraded };
+        let k = element_stiffness(&element_nodes(mesh, elem), &c, rule)
+            .ok_or_else(|| Message::new(MessageCode::ElementSingular, format!("Element {} has a singular Jacobian", e)).with("element", e))?;
+
+        for a in 0..10 {
+            for b in 0..10 {
+                for i in 0..3 {
+                    for j in 0..3 {
+                        let v = k[(a * 3 + i, b * 3 + j)];
+                        if v != 0.0 {
+                            triplets.push((elem[a] * 3 + i, elem[b] * 3 + j, v));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(AssemblyResult { dofs: mesh.vertices.len() * 3, triplets, flagged })
+}
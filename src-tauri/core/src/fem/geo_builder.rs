@@ -0,0 +1,291 @@
+//! Emits OpenCASCADE .geo geometry for layers whose shapes are already resolved to numbers.
+use geo::{Coord, LineString, Polygon, SimplifyVwPreserve};
+use crate::artifacts::project_hash;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Simplification tolerance as a fraction of the target mesh size. Deviations well below
+/// one element are invisible to the mesher but cost OCC a face/edge each.
+pub const SIMPLIFY_TOLERANCE_FRACTION: f64 = 0.1;
+/// Overshoot for through cuts so tool volumes never share a face with the plate.
+const CUT_OVERSHOOT: f64 = 0.01;
+/// Cuts with this id prefix are the gap between the parts of a fabrication split (see
+/// `split_export::inject_split_cuts`). They always go through the layer and are never
+/// simplified, since the slot is narrower than the simplification tolerance.
+pub const SPLIT_CUT_PREFIX: &str = "temp_split_";
+/// Size the BREP checkpoint cache is pruned back to after each meshing run.
+pub const GEO_CACHE_MAX_BYTES: u64 = 512 * 1024 * 1024;
+
+/// One stackup layer with numeric geometry (mm).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GeoLayer {
+    /// Stackup layer id
+    pub id: String,
+    /// Bottom of the layer
+    pub z: f64,
+    /// Layer thickness
+    pub thickness: f64,
+    /// Board outline
+    pub outline: Vec<[f64; 2]>,
+    /// Pockets and through-cuts removed from the layer
+    #[serde(default)]
+    pub cuts: Vec<GeoCut>,
+}
+
+/// A region removed from a layer to a given depth.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GeoCut {
+    /// Id used in Gmsh comments and checkpoint names
+    pub id: String,
+    /// Outer ring
+    pub exterior: Vec<[f64; 2]>,
+    /// Islands left standing inside the cut
+    #[serde(default)]
+    pub interiors: Vec<Vec<[f64; 2]>>,
+    /// Depth from the machined face; the full thickness cuts through
+    pub depth: f64,
+    /// Machined from the bottom face instead of the top
+    #[serde(default)]
+    pub from_bottom: bool,
+}
+
+/// Removes points that deviate less than `tolerance` from their neighbours' chord.
+/// Visvalingam-Whyatt drops the flattest (lowest-curvature) vertices first, so arcs keep
+/// their shape while long straight runs of traced points collapse. The topology-preserving
+/// variant keeps rings from self-intersecting or crossing their holes; anything that would
+/// degenerate is returned unchanged.
+pub fn simplify_polygon(poly: &Polygon<f64>, tolerance: f64) -> Polygon<f64> {
+    if tolerance <= 0.0 {
+        return poly.clone();
+    }
+    // Triangle area for a vertex `tolerance` off a chord of similar length
+    let simplified = poly.simplify_vw_preserve(&(0.5 * tolerance * tolerance));
+    if simplified.exterior().0.len() < 4 || simplified.interiors().len() != poly.interiors().len() {
+        return poly.clone();
+    }
+    simplified
+}
+
+fn to_ring(points: &[[f64; 2]]) -> LineString<f64> {
+    let mut ls = LineString::new(points.iter().map(|p| Coord { x: p[0], y: p[1] }).collect());
+    ls.close();
+    ls
+}
+
+impl GeoCut {
+    /// The cut as a closed polygon.
+    pub fn polygon(&self) -> Polygon<f64> {
+        Polygon::new(to_ring(&self.exterior), self.interiors.iter().map(|r| to_ring(r)).collect())
+    }
+}
+
+impl GeoLayer {
+    /// The outline as a closed polygon.
+    pub fn polygon(&self) -> Polygon<f64> {
+        Polygon::new(to_ring(&self.outline), vec![])
+    }
+}
+
+/// Appends geometry to a script. Entity tags come from `newp`/`newc`/`newcl`/`news`
+/// because OCC booleans and extrusions allocate tags of their own.
+#[derive(Default)]
+pub struct GeoWriter {
+    /// The .geo text written so far
+    pub script: String,
+    next_loop: usize,
+    next_surface: usize,
+    next_var: usize,
+}
+
+impl GeoWriter {
+    fn ring(&mut self, ring: &LineString<f64>, z: f64) -> String {
+        // Closed rings repeat the first coordinate; OCC wants each point once
+        let coords = &ring.0[..ring.0.len().saturating_sub(1)];
+        let n = coords.len();
+        self.script.push_str("p = newp;\n");
+        for (i, c) in coords.iter().enumerate() {
+            self.script.push_str(&format!("Point(p + {}) = {{{}, {}, {}}};\n", i, c.x, c.y, z));
+        }
+        self.script.push_str("l = newc;\n");
+        for i in 0..n {
+            self.script.push_str(&format!("Line(l + {}) = {{p + {}, p + {}}};\n", i, i, (i + 1) % n));
+        }
+        self.next_loop += 1;
+        let var = format!("cl_{}", self.next_loop);
+        self.script.push_str(&format!("{} = newcl; Curve Loop({}) = {{l : l + {}}};\n", var, var, n - 1));
+        var
+    }
+
+    fn surface(&mut self, poly: &Polygon<f64>, z: f64) -> String {
+        let mut loops = vec![self.ring(poly.exterior(), z)];
+        for hole in poly.interiors() {
+            loops.push(self.ring(hole, z));
+        }
+        self.next_surface += 1;
+        let var = format!("s_{}", self.next_surface);
+        self.script.push_str(&format!("{} = news; Plane Surface({}) = {{{}}};\n", var, var, loops.join(", ")));
+        var
+    }
+
+    /// Extrudes `poly` at height `z` by `height`; returns the variable holding the volume.
+    pub fn prism(&mut self, poly: &Polygon<f64>, z: f64, height: f64) -> String {
+        let surface = self.surface(poly, z);
+        self.next_var += 1;
+        let var = format!("ext_{}", self.next_var);
+        self.script.push_str(&format!("{}[] = Extrude {{0, 0, {}}} {{ Surface{{{}}}; }};\n", var, height, surface));
+        format!("{}[1]", var)
+    }
+
+    /// Appends a section marker comment.
+    pub fn comment(&mut self, text: &str) {
+        self.script.push_str(&format!("// --- {} ---\n", comment_text(text)));
+    }
+}
+
+/// `text` with control characters blanked, so request ids can go into a `//` comment
+/// without a line break ending it and turning the rest into Gmsh statements.
+fn comment_text(text: &str) -> String {
+    text.chars().map(|c| if c.is_control() { ' ' } else { c }).collect()
+}
+
+/// One step of the boolean sequence. `hash` covers this op and every op before it, so
+/// equal hashes mean the model state after the op is identical.
+pub struct GeoOp {
+    /// Human-readable label, written as a comment
+    pub name: String,
+    /// Hash of this op and all before it; names the checkpoint
+    pub hash: String,
+    /// .geo statements performing the op
+    pub body: String,
+    /// Geo list expression holding the op's volumes
+    pub result: String,
+}
+
+/// Splits the model into one op per layer (plate minus that layer's cuts).
+pub fn layer_ops(layers: &[GeoLayer], tolerance: f64) -> Vec<GeoOp> {
+    let mut w = GeoWriter::default();
+    let mut ops = Vec::new();
+    let mut prev_hash = String::new();
+
+    for (i, layer) in layers.iter().enumerate() {
+        let start = w.script.len();
+        w.comment(&format!("Layer {}", layer.id));
+        let plate = w.prism(&simplify_polygon(&layer.polygon(), tolerance), layer.z, layer.thickness);
+
+        let mut tools = Vec::new();
+        for cut in &layer.cuts {
+            let split = cut.id.starts_with(SPLIT_CUT_PREFIX);
+            let depth = if split { layer.thickness } else { cut.depth.min(layer.thickness) };
+            if depth <= 1e-6 { continue; }
+            let through = depth >= layer.thickness - 1e-6;
+            let (z0, h) = if through {
+                (layer.z - CUT_OVERSHOOT, layer.thickness + 2.0 * CUT_OVERSHOOT)
+            } else if cut.from_bottom {
+                (layer.z - CUT_OVERSHOOT, depth + CUT_OVERSHOOT)
+            } else {
+                (layer.z + layer.thickness - depth, depth + CUT_OVERSHOOT)
+            };
+            w.comment(&format!("Cut {}", cut.id));
+            let poly = if split { cut.polygon() } else { simplify_polygon(&cut.polygon(), tolerance) };
+            tools.push(w.prism(&poly, z0, h));
+        }
+
+        let var = format!("layer_{}", i);
+        if tools.is_empty() {
+            w.script.push_str(&format!("{}[] = {{{}}};\n", var, plate));
+        } else {
+            w.script.push_str(&format!(
+                "{}[] = BooleanDifference{{ Volume{{{}}}; Delete; }}{{ Volume{{{}}}; Delete; }};\n",
+                var, plate, tools.join(", ")
+            ));
+        }
+
+        // Hash the inputs rather than the script text, whose variable numbering shifts
+        // whenever an earlier layer gains or loses a cut.
+        prev_hash = project_hash(&(&prev_hash, layer, tolerance));
+        ops.push(GeoOp {
+            name: format!("layer:{}", layer.id),
+            hash: prev_hash.clone(),
+            body: w.script[start..].to_string(),
+            result: format!("{}[]", var),
+        });
+    }
+    ops
+}
+
+/// Builds every layer as plate-minus-cuts and fragments the stack so touching layers
+/// share conformal interfaces. Shapes are simplified to `tolerance` first.
+///
+/// With a `cache_dir`, the model is saved as `<hash>.brep` after each op. The longest
+/// prefix already on disk is loaded with `ShapeFromFile` and only the ops after it are
+/// emitted, so editing one layer re-runs the booleans from that layer onward.
+pub fn emit_layers(layers: &[GeoLayer], tolerance: f64, cache_dir: Option<&Path>) -> String {
+    let ops = layer_ops(layers, tolerance);
+    let brep_path = |op: &GeoOp| cache_dir.map(|d| d.join(format!("{}.brep", op.hash)));
+
+    let cached = ops.iter()
+        .rposition(|op| brep_path(op).is_some_and(|p| p.exists()))
+        .map_or(0, |i| i + 1);
+
+    let mut script = String::new();
+    let mut results = Vec::new();
+
+    if cached > 0 {
+        let path = brep_path(&ops[cached - 1]).unwrap();
+        // Mark the checkpoint as recently used so `prune_cache` keeps it
+        let _ = std::fs::File::options().append(true).open(&path).and_then(|f| f.set_modified(SystemTime::now()));
+        let names: Vec<&str> = ops[..cached].iter().map(|op| op.name.as_str()).collect();
+        script.push_str(&format!("// --- Restored from cache: {} ---\n", comment_text(&names.join(", "))));
+        script.push_str(&format!("cached[] = ShapeFromFile(\"{}\");\n", path.to_string_lossy().replace('\\', "/")));
+        results.push("cached[]".to_string());
+    }
+
+    for op in &ops[cached..] {
+        script.push_str(&op.body);
+        results.push(op.result.clone());
+        if let Some(path) = brep_path(op) {
+            script.push_str(&format!("Save \"{}\";\n", path.to_string_lossy().replace('\\', "/")));
+        }
+    }
+
+    if results.len() > 1 {
+        script.push_str("// --- Conformal layer interfaces ---\n");
+        script.push_str(&format!("BooleanFragments{{ Volume{{{}}}; Delete; }}{{}}\n", results.join(", ")));
+    }
+    script
+}
+
+/// Deletes the least recently used `.brep` checkpoints under `cache_dir` (healing
+/// subfolders included) until the rest fit in `max_bytes`. Returns the bytes freed.
+pub fn prune_cache(cache_dir: &Path, max_bytes: u64) -> u64 {
+    fn collect(dir: &Path, out: &mut Vec<(PathBuf, u64, SystemTime)>) {
+        let Ok(entries) = std::fs::read_dir(dir) else { return };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(meta) = entry.metadata() else { continue };
+            if meta.is_dir() {
+                collect(&path, out);
+            } else if path.extension().is_some_and(|e| e == "brep") {
+                out.push((path, meta.len(), meta.modified().unwrap_or(SystemTime::UNIX_EPOCH)));
+            }
+        }
+    }
+
+    let mut files = Vec::new();
+    collect(cache_dir, &mut files);
+    let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+    files.sort_by_key(|(_, _, modified)| *modified);
+
+    let mut freed = 0;
+    for (path, len, _) in files {
+        if total <= max_bytes {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            total -= len;
+            freed += len;
+        }
+    }
+    freed
+}
@@ -0,0 +1,555 @@
+//! Gmsh pipeline: .geo generation from a `FeaRequest`, running Gmsh and reading the mesh back.
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use serde::{Deserialize, Serialize};
+use crate::fem::mesh::TetMesh; // Assuming this exists from previous context
+use crate::artifacts;
+use crate::fem::geo_builder::{self, GeoLayer};
+use crate::fem::assembly::{quadrature_plan, ElementQualityReport};
+use crate::fem::material::IsotropicMaterial;
+use crate::fem::units::{Dimension, ReportQuantity, UnitSystem};
+use crate::messages::{Message, MessageCode};
+
+/// Meshing/FEA request from the simulation editor.
+#[derive(Deserialize, Debug)]
+pub struct FeaRequest {
+    /// Footprint JSON as sent by the frontend
+    pub footprint: serde_json::Value,
+    /// Stackup layers JSON as sent by the frontend
+    pub stackup: Vec<serde_json::Value>,
+    /// Project parameters JSON as sent by the frontend
+    pub params: Vec<serde_json::Value>,
+    /// Mesh quality; higher is finer (element size 10 / quality mm)
+    pub quality: f64,
+    /// Defaults to DEFAULT_MEMORY_BUDGET_MB
+    #[serde(default)]
+    pub memory_budget_mb: Option<f64>,
+    /// Resolved geometry; the mock plate is used when empty
+    #[serde(default)]
+    pub layers: Vec<GeoLayer>,
+    /// Shape healing applied before meshing
+    #[serde(default)]
+    pub healing: HealingOptions,
+    /// Dominant load direction; enables the anisotropic size field
+    #[serde(default)]
+    pub load_direction: Option<[f64; 3]>,
+    /// Units of `material`/`loads` and of the returned report
+    #[serde(default)]
+    pub units: UnitSystem,
+    /// Material to report with the mesh, if any
+    #[serde(default)]
+    pub material: Option<MaterialSpec>,
+    /// Point loads to report with the mesh
+    #[serde(default)]
+    pub loads: Vec<LoadSpec>,
+}
+
+impl FeaRequest {
+    /// Meshes already-resolved layers with default healing, units and no loads, for callers
+    /// that have no footprint/stackup/params (single export layers, replays).
+    pub fn from_layers(layers: Vec<GeoLayer>, quality: f64) -> Self {
+        Self {
+            footprint: serde_json::Value::Null,
+            stackup: Vec::new(),
+            params: Vec::new(),
+            quality,
+            memory_budget_mb: None,
+            layers,
+            healing: Default::default(),
+            load_direction: None,
+            units: Default::default(),
+            material: None,
+            loads: Vec::new(),
+        }
+    }
+}
+
+/// Isotropic material in the request's units.
+#[derive(Deserialize, Debug, Clone)]
+pub struct MaterialSpec {
+    /// Young's modulus (stress unit)
+    pub youngs_modulus: f64,
+    /// Poisson's ratio (dimensionless)
+    pub poisson_ratio: f64,
+}
+
+/// Point force in the request's units.
+#[derive(Deserialize, Debug, Clone)]
+pub struct LoadSpec {
+    /// Application point (length unit)
+    pub position: [f64; 3],
+    /// Force vector (force unit)
+    pub force: [f64; 3],
+}
+
+impl LoadSpec {
+    /// Position in mm and force in N.
+    pub fn to_internal(&self, units: &UnitSystem) -> ([f64; 3], [f64; 3]) {
+        (
+            self.position.map(|v| units.import_value(v, Dimension::Length)),
+            self.force.map(|v| units.import_value(v, Dimension::Force)),
+        )
+    }
+}
+
+/// OpenCASCADE shape healing. Off by default: on clean geometry it can merge or drop
+/// legitimate small features. The "safe_retry" preset is meant for models that fail
+/// with errors like "BRep contains more volumes than expected".
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct HealingOptions {
+    /// "none" | "safe_retry"; explicit flags below override it
+    pub preset: Option<String>,
+    /// Geometry.OCCFixDegenerated
+    pub fix_degenerated: Option<bool>,
+    /// Geometry.OCCFixSmallEdges
+    pub fix_small_edges: Option<bool>,
+    /// Geometry.OCCFixSmallFaces
+    pub fix_small_faces: Option<bool>,
+    /// Geometry.OCCSewFaces
+    pub sew_faces: Option<bool>,
+    /// Geometry.Tolerance (mm)
+    pub tolerance: Option<f64>,
+}
+
+impl HealingOptions {
+    /// Gmsh option lines for the resolved flags; empty when nothing is enabled.
+    fn geo_options(&self) -> String {
+        let safe = self.preset.as_deref() == Some("safe_retry");
+        let flags = [
+            ("Geometry.OCCFixDegenerated", self.fix_degenerated.unwrap_or(safe)),
+            ("Geometry.OCCFixSmallEdges", self.fix_small_edges.unwrap_or(safe)),
+            ("Geometry.OCCFixSmallFaces", self.fix_small_faces.unwrap_or(safe)),
+            ("Geometry.OCCSewFaces", self.sew_faces.unwrap_or(safe)),
+        ];
+        let tolerance = self.tolerance.or(if safe { Some(1e-4) } else { None });
+
+        let mut out = String::new();
+        for (name, enabled) in flags {
+            if enabled {
+                out.push_str(&format!("{} = 1;\n", name));
+            }
+        }
+        if let Some(tol) = tolerance {
+            out.push_str(&format!("Geometry.Tolerance = {};\n", tol));
+            out.push_str(&format!("Geometry.ToleranceBoolean = {};\n", tol));
+        }
+        out
+    }
+}
+
+/// Mesh and report returned to the frontend.
+#[derive(Serialize, Debug)]
+pub struct FeaResult {
+    /// The tetrahedral mesh (mm)
+    pub mesh: TetMesh,
+    /// Mesh volume (mm³)
+    pub volume: f64,
+    /// Boundary surface area (mm²)
+    pub surface_area: f64,
+    /// Gmsh output
+    pub logs: String,
+    /// Elements that need upgraded quadrature, or are inverted
+    pub element_quality: Vec<ElementQualityReport>,
+    /// Inputs and results in the project's units
+    pub report: Vec<ReportQuantity>,
+}
+
+/// Echoes the inputs and the results in the request's units so exported reports never
+/// depend on an implied unit. `volume` and `surface_area` are internal mm values.
+pub fn build_report(req: &FeaRequest, volume: f64, surface_area: f64) -> Vec<ReportQuantity> {
+    let u = &req.units;
+    let mut report = vec![
+        u.report(MessageCode::ReportVolume, "Volume", volume, Dimension::Volume),
+        u.report(MessageCode::ReportSurfaceArea, "Surface area", surface_area, Dimension::Area),
+    ];
+    if let Some(m) = &req.material {
+        let material = IsotropicMaterial::with_units(m.youngs_modulus, m.poisson_ratio, u);
+        report.push(u.report(MessageCode::ReportYoungsModulus, "Young's modulus", material.e, Dimension::Stress));
+        report.push(ReportQuantity {
+            code: MessageCode::ReportPoissonsRatio,
+            name: "Poisson's ratio".into(),
+            value: material.nu,
+            unit: String::new(),
+        });
+    }
+    if !req.loads.is_empty() {
+        let mut total = [0.0; 3];
+        for load in &req.loads {
+            let (_, f) = load.to_internal(u);
+            for k in 0..3 {
+                total[k] += f[k];
+            }
+        }
+        let magnitude = (total[0] * total[0] + total[1] * total[1] + total[2] * total[2]).sqrt();
+        report.push(u.report(MessageCode::ReportTotalLoad, "Total applied load", magnitude, Dimension::Force));
+    }
+    report
+}
+
+/// Memory a single meshing/solve may use before we refuse to load the mesh.
+pub const DEFAULT_MEMORY_BUDGET_MB: f64 = 4096.0;
+
+// Rough per-item costs used to predict peak memory before a mesh is loaded.
+// Parsing keeps the vertex, the node tag map entry and the JSON copy sent to the UI.
+const BYTES_PER_NODE: u64 = 24 + 48 + 64;
+const BYTES_PER_ELEMENT: u64 = 80 + 96;
+// Solver: 3 DOF per node, ~80 nonzeros per row for Tet10 stiffness, 12 bytes per
+// nonzero (value + column index), x4 for factorization fill-in.
+const BYTES_PER_DOF: u64 = 80 * 12 * 4;
+
+fn mesh_size_for_quality(quality: f64) -> f64 {
+    if quality > 0.0 { 10.0 / quality } else { 5.0 }
+}
+
+/// Node and element counts from the `$Nodes` / `$Elements` headers of a 2.2 .msh file,
+/// read without loading the whole file.
+pub fn read_msh_counts(path: &Path) -> Result<(usize, usize), Message> {
+    use std::io::{BufRead, BufReader};
+
+    let file = fs::File::open(path).map_err(|e| msh_read_failed(path, e))?;
+    let mut lines = BufReader::new(file).lines();
+    let mut nodes = None;
+    let mut elements = None;
+
+    while let Some(line) = lines.next() {
+        let line = line.map_err(|e| msh_read_failed(path, e))?;
+        let target = if line.starts_with("$Nodes") {
+            &mut nodes
+        } else if line.starts_with("$Elements") {
+            &mut elements
+        } else {
+            continue;
+        };
+        let header = lines.next()
+            .ok_or_else(|| Message::new(MessageCode::MshTruncated, "Truncated .msh header").with("path", path.to_string_lossy()))?
+            .map_err(|e| msh_read_failed(path, e))?;
+        // 2.2 has a single count; 4.1 starts with numEntityBlocks, so take the second field there
+        let fields: Vec<usize> = header.split_whitespace().filter_map(|f| f.parse().ok()).collect();
+        *target = if fields.len() >= 4 { fields.get(1).copied() } else { fields.first().copied() };
+        if nodes.is_some() && elements.is_some() { break; }
+    }
+
+    Ok((nodes.unwrap_or(0), elements.unwrap_or(0)))
+}
+
+/// Predicted peak bytes for parsing a mesh of this size and solving on it.
+pub fn estimate_mesh_memory(nodes: usize, elements: usize, file_bytes: u64) -> u64 {
+    let nodes = nodes as u64;
+    let elements = elements as u64;
+    // The file is read into a String and split into a Vec<&str> of lines
+    file_bytes * 2 + nodes * BYTES_PER_NODE + elements * BYTES_PER_ELEMENT + nodes * 3 * BYTES_PER_DOF
+}
+
+/// Errors out with a suggested minimum mesh size when the estimate exceeds the budget.
+/// Node/element counts scale with 1/h^3, so the size grows by the cube root of the overshoot.
+pub fn check_memory_budget(estimate_bytes: u64, budget_mb: f64, mesh_size: f64) -> Result<(), Message> {
+    let budget_bytes = budget_mb.max(0.0) * 1024.0 * 1024.0;
+    if (estimate_bytes as f64) <= budget_bytes {
+        return Ok(());
+    }
+    let ratio = estimate_bytes as f64 / budget_bytes.max(1.0);
+    let suggested = mesh_size * ratio.cbrt() * 1.05;
+    let needed_mb = estimate_bytes as f64 / (1024.0 * 1024.0);
+    Err(Message::new(MessageCode::MeshTooLarge, format!(
+        "Mesh too large: needs ~{:.0} MB but the budget is {:.0} MB. Increase mesh size to >= {:.2} mm (currently {:.2} mm) or raise the memory budget.",
+        needed_mb, budget_mb, suggested, mesh_size
+    ))
+        .with("needed_mb", needed_mb)
+        .with("budget_mb", budget_mb)
+        .with("suggested_size", suggested)
+        .with("mesh_size", mesh_size))
+}
+
+/// Through-thickness refinement relative to the isotropic size when no layer is thinner.
+const ANISO_RATIO: f64 = 4.0;
+/// Elements across the thinnest layer along the load direction.
+const ANISO_ELEMENTS_ACROSS: f64 = 3.0;
+
+/// Background field with metric M = I / h_coarse^2 + (1/h_fine^2 - 1/h_coarse^2) d d^T.
+/// Bending stress varies fastest along the load direction `d` (through the plate),
+/// so elements are fine along `d` and coarse (`mesh_size`) across it.
+fn anisotropic_size_field(direction: [f64; 3], mesh_size: f64, layers: &[GeoLayer]) -> Option<String> {
+    let norm = (direction[0].powi(2) + direction[1].powi(2) + direction[2].powi(2)).sqrt();
+    if norm < 1e-12 {
+        return None;
+    }
+    let d = direction.map(|v| v / norm);
+
+    let thinnest = layers.iter().map(|l| l.thickness).filter(|t| *t > 0.0).reduce(f64::min);
+    let h_fine = thinnest.map_or(mesh_size / ANISO_RATIO, |t| (t / ANISO_ELEMENTS_ACROSS).min(mesh_size));
+    let h_coarse = mesh_size;
+    let a = 1.0 / (h_coarse * h_coarse);
+    let b = 1.0 / (h_fine * h_fine) - a;
+    let m = |i: usize, j: usize| if i == j { a + b * d[i] * d[j] } else { b * d[i] * d[j] };
+
+    let mut out = String::from("// --- Anisotropic size field ---\n");
+    out.push_str("Field[1] = MathEvalAniso;\n");
+    for (name, i, j) in [("M11", 0, 0), ("M12", 0, 1), ("M13", 0, 2), ("M22", 1, 1), ("M23", 1, 2), ("M33", 2, 2)] {
+        out.push_str(&format!("Field[1].{} = \"{}\";\n", name, m(i, j)));
+    }
+    out.push_str("Background Field = 1;\n");
+    out.push_str(&format!("Mesh.CharacteristicLengthMin = {};\n", h_fine));
+    // HXT ignores metric fields; MMG3D is the 3D mesher that honours them
+    out.push_str("Mesh.Algorithm3D = 7;\n");
+    Some(out)
+}
+
+/// Generates a Gmsh .geo script using OpenCASCADE kernel
+/// `cache_dir` holds intermediate BREP checkpoints for resolved layers (see `geo_builder::emit_layers`).
+fn generate_geo_script(req: &FeaRequest, output_msh_path: &str, cache_dir: Option<&Path>) -> String {
+    let mut script = String::new();
+    
+    // Header: Use OpenCASCADE for Boolean operations
+    script.push_str("SetFactory(\"OpenCASCADE\");\n");
+    script.push_str("Mesh.Algorithm3D = 10; // HXT algorithm (parallel, robust)\n");
+
+    // Healing must be configured before any shape is created or imported
+    let healing = req.healing.geo_options();
+    if !healing.is_empty() {
+        script.push_str("// --- OCC Healing ---\n");
+        script.push_str(&healing);
+    }
+    
+    // Determine Global Mesh Size based on quality param (heuristic)
+    let mesh_size = mesh_size_for_quality(req.quality);
+    script.push_str(&format!("Mesh.CharacteristicLengthMin = {};\n", mesh_size * 0.5));
+    script.push_str(&format!("Mesh.CharacteristicLengthMax = {};\n", mesh_size));
+
+    // --- GEOMETRY GENERATION ---
+    if !req.layers.is_empty() {
+        // Resolved shapes are simplified to a fraction of the mesh size before emission
+        let tolerance = mesh_size * geo_builder::SIMPLIFY_TOLERANCE_FRACTION;
+        // Healed and unhealed checkpoints differ, so keep them apart
+        let cache_dir = cache_dir.map(|d| if healing.is_empty() { d.to_path_buf() } else { d.join(artifacts::project_hash(&healing)) });
+        if let Some(dir) = &cache_dir {
+            let _ = fs::create_dir_all(dir);
+        }
+        script.push_str(&geo_builder::emit_layers(&req.layers, tolerance, cache_dir.as_deref()));
+    } else {
+        // In a real implementation, you would traverse req.footprint['shapes']
+        // recursively, resolving expressions via `meval` or similar in Rust.
+        // For this proof of concept, we mock a simple boolean operation.
+        
+        // Example: Plate with a hole
+        script.push_str("// --- Base Plate ---\n");
+        script.push_str("Rectangle(1) = {-50, -50, 0, 100, 100, 5};\n"); // Rounded rect support in OCC
+        
+        script.push_str("// --- Cutout Hole ---\n");
+        script.push_str("Disk(2) = {0, 0, 0, 20};\n");
+        
+        script.push_str("// --- Boolean Cut (2D Surface) ---\n");
+        script.push_str("BooleanDifference(3) = { Surface{1}; Delete; }{ Surface{2}; Delete; };\n");
+        
+        script.push_str("// --- Extrusion (3D) ---\n");
+        // Extrude the resulting surface (3) by 5mm in Z
+        script.push_str("Extrude {0, 0, 5} { Surface{3}; }\n");
+    }
+
+    if let Some(field) = req.load_direction.and_then(|d| anisotropic_size_field(d, mesh_size, &req.layers)) {
+        script.push_str(&field);
+    }
+
+    // --- MESH GENERATION COMMANDS ---
+    script.push_str("Mesh.ElementOrder = 2;\n"); // 10-node tets, the only elements parse_msh reads
+    script.push_str("Mesh 3;\n"); // Generate 3D Mesh
+    // Legacy 2.2 (ASCII): options only apply to later Saves, so set it before saving
+    script.push_str("Mesh.Format = 1;\n");
+    script.push_str("Mesh.MshFileVersion = 2.2;\n");
+    script.push_str(&format!("Save \"{}\";\n", output_msh_path.replace("\\", "/")));
+    
+    script
+}
+
+/// Gmsh numbers the last two mid-edge nodes of a 10-node tet 2-3, 1-3; Tet10 uses 1-3, 2-3.
+/// The swap is its own inverse, so it converts in both directions.
+const GMSH_TET10_ORDER: [usize; 10] = [0, 1, 2, 3, 4, 5, 6, 7, 9, 8];
+
+/// Writes a TetMesh as Gmsh 2.2 ASCII (element type 11).
+/// `partition` is written as the elementary entity tag so solvers can tell pieces apart.
+pub fn write_msh(path: &Path, mesh: &TetMesh, partition: usize) -> Result<(), String> {
+    use std::io::Write;
+
+    let file = fs::File::create(path).map_err(|e| e.to_string())?;
+    let mut out = std::io::BufWriter::new(file);
+    let io = |e: std::io::Error| e.to_string();
+
+    writeln!(out, "$MeshFormat\n2.2 0 8\n$EndMeshFormat").map_err(io)?;
+    writeln!(out, "$Nodes\n{}", mesh.vertices.len()).map_err(io)?;
+    for (i, v) in mesh.vertices.iter().enumerate() {
+        writeln!(out, "{} {} {} {}", i + 1, v[0], v[1], v[2]).map_err(io)?;
+    }
+    writeln!(out, "$EndNodes\n$Elements\n{}", mesh.indices.len()).map_err(io)?;
+    for (i, elem) in mesh.indices.iter().enumerate() {
+        let nodes: Vec<String> = GMSH_TET10_ORDER.iter().map(|&k| (elem[k] + 1).to_string()).collect();
+        writeln!(out, "{} 11 2 1 {} {}", i + 1, partition + 1, nodes.join(" ")).map_err(io)?;
+    }
+    writeln!(out, "$EndElements").map_err(io)?;
+    out.flush().map_err(io)
+}
+
+/// Parses a Gmsh .msh file (Format 4.1 ASCII) into our TetMesh struct
+fn parse_msh(path: &Path) -> Result<TetMesh, Message> {
+    let content = fs::read_to_string(path).map_err(|e| msh_read_failed(path, e))?;
+    let lines: Vec<&str> = content.lines().collect();
+    
+    let mut vertices = Vec::new();
+    let mut indices = Vec::new();
+    
+    // VERY Basic Parser for Gmsh 4.1
+    // A robust parser would handle sections $Nodes and $Elements more gracefully
+    
+    let mut reading_nodes = false;
+    let mut reading_elements = false;
+    
+    // Maps Gmsh Node Tag -> Index in our vertices vector
+    let mut node_map = std::collections::HashMap::new(); 
+    
+    let mut iter = lines.iter();
+    while let Some(line) = iter.next() {
+        if line.starts_with("$Nodes") {
+            reading_nodes = true;
+            // Skip header info line in 4.1
+            iter.next(); 
+            continue;
+        }
+        if line.starts_with("$EndNodes") { reading_nodes = false; continue; }
+        
+        if line.starts_with("$Elements") {
+            reading_elements = true;
+            // Skip header info
+            iter.next();
+            continue;
+        }
+        if line.starts_with("$EndElements") { reading_elements = false; continue; }
+
+        if reading_nodes {
+            // Gmsh 4.1 Node logic is complex (blocks). 
+            // Simplified logic: If line looks like "tag x y z", parse it.
+            // Note: This is simplified. Real Gmsh 4.1 has blocks. 
+            // For production, use format 2.2 (`Mesh.MshFileVersion = 2.2;`) 
+            // or a proper parser crate. 
+            // Here we assume 2.2 for simplicity of parsing implementation below:
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() == 4
+                && let (Ok(id), Ok(x), Ok(y), Ok(z)) = (parts[0].parse::<usize>(), parts[1].parse::<f64>(), parts[2].parse::<f64>(), parts[3].parse::<f64>()) {
+                node_map.insert(id, vertices.len());
+                vertices.push([x, y, z]);
+            }
+        }
+
+        if reading_elements {
+            // Format 2.2 Element: id type tags... node1 node2 ...
+            // Type 4 = 4-node Tet
+            // Type 11 = 10-node Tet
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() > 3 {
+                let elem_type = parts[1].parse::<usize>().unwrap_or(0);
+                
+                // Handling 10-node Tetrahedrons
+                if elem_type == 11 {
+                    // Extract last 10 items
+                    let count = parts.len();
+                    if count >= 10 {
+                        let raw_nodes = &parts[count-10..count];
+                        let mut tet_indices = [0usize; 10];
+                        let mut valid = true;
+                        for (i, node_str) in raw_nodes.iter().enumerate() {
+                            let tag = node_str.parse::<usize>().unwrap_or(0);
+                            if let Some(&idx) = node_map.get(&tag) {
+                                tet_indices[GMSH_TET10_ORDER[i]] = idx;
+                            } else {
+                                valid = false;
+                            }
+                        }
+                        if valid {
+                            indices.push(tet_indices);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(TetMesh { vertices, indices })
+}
+
+/// What one meshing run produced, before archiving and unit conversion.
+pub struct PipelineOutput {
+    /// See `FeaResult::mesh`
+    pub mesh: TetMesh,
+    /// See `FeaResult::volume`
+    pub volume: f64,
+    /// See `FeaResult::surface_area`
+    pub surface_area: f64,
+    /// See `FeaResult::logs`
+    pub logs: String,
+    /// See `FeaResult::element_quality`
+    pub element_quality: Vec<ElementQualityReport>,
+}
+
+fn msh_read_failed(path: &Path, e: impl std::fmt::Display) -> Message {
+    Message::new(MessageCode::MshReadFailed, format!("Failed to read {}: {}", path.display(), e))
+        .with("path", path.to_string_lossy())
+        .with("error", e.to_string())
+}
+
+/// The Gmsh process could not be started.
+pub fn gmsh_launch_failed(e: impl std::fmt::Display) -> Message {
+    Message::new(MessageCode::GmshLaunchFailed, format!("Failed to run gmsh: {}", e)).with("error", e.to_string())
+}
+
+/// Gmsh exited with an error; `stderr` is passed through untranslated.
+pub fn gmsh_failed(stderr: &[u8]) -> Message {
+    let stderr = String::from_utf8_lossy(stderr);
+    Message::new(MessageCode::GmshFailed, format!("Gmsh failed: {}", stderr)).with("stderr", stderr)
+}
+
+/// Writes the .geo for `req` into `work_dir`; returns the .geo path and the .msh path
+/// the script will save to.
+pub fn prepare_geo(req: &FeaRequest, work_dir: &Path, cache_dir: Option<&Path>) -> Result<(PathBuf, PathBuf), Message> {
+    let geo_path = work_dir.join("temp_model.geo");
+    let msh_path = work_dir.join("temp_model.msh");
+
+    let script = generate_geo_script(req, msh_path.to_str().unwrap(), cache_dir);
+    fs::write(&geo_path, &script).map_err(|e| {
+        Message::new(MessageCode::GeoWriteFailed, format!("Failed to write .geo: {}", e))
+            .with("path", geo_path.to_string_lossy())
+            .with("error", e.to_string())
+    })?;
+    Ok((geo_path, msh_path))
+}
+
+/// Loads the mesh Gmsh wrote (after checking it fits in memory) and measures it.
+pub fn collect_mesh(req: &FeaRequest, msh_path: &Path, logs: String) -> Result<PipelineOutput, Message> {
+    let (nodes, elements) = read_msh_counts(msh_path)?;
+    let file_bytes = fs::metadata(msh_path).map(|m| m.len()).unwrap_or(0);
+    check_memory_budget(
+        estimate_mesh_memory(nodes, elements, file_bytes),
+        req.memory_budget_mb.unwrap_or(DEFAULT_MEMORY_BUDGET_MB),
+        mesh_size_for_quality(req.quality),
+    )?;
+    let mesh = parse_msh(msh_path)?;
+    let (_, element_quality) = quadrature_plan(&mesh);
+    let volume = mesh.volume();
+    let surface_area = mesh.surface_area();
+
+    Ok(PipelineOutput { mesh, volume, surface_area, logs, element_quality })
+}
+
+/// The full geo -> Gmsh -> mesh pipeline against a Gmsh executable on disk, without Tauri.
+/// For tools and the regression tests; the app goes through the bundled sidecar instead.
+pub fn run_gmsh_pipeline(gmsh: &Path, req: &FeaRequest, work_dir: &Path) -> Result<PipelineOutput, Message> {
+    fs::create_dir_all(work_dir).map_err(|e| Message::write_failed(work_dir, e))?;
+    let (geo_path, msh_path) = prepare_geo(req, work_dir, None)?;
+
+    let output = Command::new(gmsh)
+        .args([geo_path.to_str().unwrap(), "-"])
+        .output()
+        .map_err(gmsh_launch_failed)?;
+    if !output.status.success() {
+        return Err(gmsh_failed(&output.stderr));
+    }
+
+    collect_mesh(req, &msh_path, String::from_utf8_lossy(&output.stdout).to_string())
+}
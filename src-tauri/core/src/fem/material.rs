@@ -1,13 +1,28 @@
-use nalgebra::{Matrix6, Matrix6x1, Vector3};
+//! Linear elastic material models and their constitutive matrices.
+use nalgebra::Matrix6;
+use super::units::{Dimension, UnitSystem};
 
+/// A linear elastic material.
 pub trait Material {
+    /// 6x6 stiffness matrix in Voigt order (xx, yy, zz, xy, yz, zx), MPa.
     fn c_matrix(&self) -> Matrix6<f64>;
 }
 
+/// Material with the same stiffness in every direction.
 #[derive(Debug, Clone, Copy)]
 pub struct IsotropicMaterial {
-    pub e: f64,  // Young's Modulus
-    pub nu: f64, // Poisson's Ratio
+    /// Young's Modulus
+    pub e: f64,
+    /// Poisson's Ratio
+    pub nu: f64,
+}
+
+impl IsotropicMaterial {
+    /// Builds the material from a modulus given in the project's stress unit; the solver
+    /// itself always works in MPa.
+    pub fn with_units(e: f64, nu: f64, units: &UnitSystem) -> Self {
+        Self { e: units.import_value(e, Dimension::Stress), nu }
+    }
 }
 
 impl Material for IsotropicMaterial {
@@ -38,9 +53,24 @@ impl Material for IsotropicMaterial {
 /// We store the "Major" Poisson's ratios (nu_xy corresponds to strain in y due to stress in x).
 #[derive(Debug, Clone, Copy)]
 pub struct OrthotropicMaterial {
-    pub ex: f64, pub ey: f64, pub ez: f64,
-    pub nu_xy: f64, pub nu_yz: f64, pub nu_xz: f64,
-    pub g_xy: f64, pub g_yz: f64, pub g_zx: f64,
+    /// Young's modulus along x (MPa)
+    pub ex: f64,
+    /// Young's modulus along y (MPa)
+    pub ey: f64,
+    /// Young's modulus along z (MPa)
+    pub ez: f64,
+    /// Poisson's ratio, strain in y from stress in x
+    pub nu_xy: f64,
+    /// Poisson's ratio, strain in z from stress in y
+    pub nu_yz: f64,
+    /// Poisson's ratio, strain in z from stress in x
+    pub nu_xz: f64,
+    /// Shear modulus in the xy plane (MPa)
+    pub g_xy: f64,
+    /// Shear modulus in the yz plane (MPa)
+    pub g_yz: f64,
+    /// Shear modulus in the zx plane (MPa)
+    pub g_zx: f64,
 }
 
 impl OrthotropicMaterial {
@@ -1,14 +1,19 @@
+//! Quadratic tetrahedral mesh with volume, surface and quality measures.
 use serde::{Deserialize, Serialize};
-use nalgebra::{Vector3, Matrix3, SVector};
+use nalgebra::Vector3;
 use super::tet10::Tet10;
 
+/// Tet10 mesh: node positions and 10-node elements.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TetMesh {
+    /// Node positions (mm)
     pub vertices: Vec<[f64; 3]>,
-    pub indices: Vec<[usize; 10]>, // 10-node connectivity
+    /// 10-node connectivity
+    pub indices: Vec<[usize; 10]>,
 }
 
 impl TetMesh {
+    /// Wraps existing node and element arrays without checking them.
     pub fn new(vertices: Vec<[f64; 3]>, indices: Vec<[usize; 10]>) -> Self {
         Self { vertices, indices }
     }
@@ -54,6 +59,30 @@ impl TetMesh {
 
         bad_elements
     }
+
+    /// Volume from the corner nodes (exact for straight-sided elements).
+    pub fn volume(&self) -> f64 {
+        self.indices.iter().map(|e| {
+            let p = |i: usize| Vector3::from(self.vertices[e[i]]);
+            ((p(1) - p(0)).cross(&(p(2) - p(0)))).dot(&(p(3) - p(0))).abs() / 6.0
+        }).sum()
+    }
+
+    /// Area of the boundary faces (faces used by exactly one element), from corner nodes.
+    pub fn surface_area(&self) -> f64 {
+        let mut faces: std::collections::HashMap<[usize; 3], usize> = std::collections::HashMap::new();
+        for e in &self.indices {
+            for f in [[e[0], e[1], e[2]], [e[0], e[1], e[3]], [e[1], e[2], e[3]], [e[0], e[2], e[3]]] {
+                let mut key = f;
+                key.sort_unstable();
+                *faces.entry(key).or_insert(0) += 1;
+            }
+        }
+        faces.iter().filter(|(_, count)| **count == 1).map(|(f, _)| {
+            let p = |i: usize| Vector3::from(self.vertices[f[i]]);
+            (p(1) - p(0)).cross(&(p(2) - p(0))).norm() / 2.0
+        }).sum()
+    }
 }
 
 // --- Inverse Mapping Implementation ---
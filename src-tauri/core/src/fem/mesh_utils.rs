@@ -1,3 +1,4 @@
+//! Helpers for cleaning up triangle soups before meshing.
 use std::collections::HashMap;
 
 /// Quantizes float coordinates to merge vertices closer than epsilon.
@@ -74,8 +75,6 @@ pub fn extract_surface(indices: &[usize]) -> Vec<usize> {
     // However, for a quick visualization fix, we'll re-iterate the tets.
     
     // Pass 2: Re-scan to keep valid winding order
-    let mut final_counts: HashMap<[usize; 3], usize> = HashMap::new();
-    
     // We already know which keys are boundary, but we want the winding from the Tet.
     // Actually, simply checking the map is faster.
     
@@ -94,12 +93,9 @@ pub fn extract_surface(indices: &[usize]) -> Vec<usize> {
             key.sort_unstable();
             
             // If count is 1, it's a boundary face
-            if let Some(&count) = face_counts.get(&key) {
-                if count == 1 {
-                    surface_indices.extend_from_slice(&f);
-                    // Remove from map so we don't add it twice (though count==1 implies unique ownership)
-                    // But strictly, a face belongs to only one tet if count==1, so we are good.
-                }
+            // A face belongs to only one tet if count==1, so it is added once
+            if face_counts.get(&key) == Some(&1) {
+                surface_indices.extend_from_slice(&f);
             }
         }
     }
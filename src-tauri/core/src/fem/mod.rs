@@ -0,0 +1,20 @@
+//! Finite element meshing and analysis: geometry scripts for Gmsh, TetGen, quadratic
+//! tetrahedra, materials, stiffness assembly and units.
+pub mod tet10;
+pub mod quadrature;
+pub mod material;
+pub mod mesh;
+#[cfg(feature = "native")]
+pub mod tetgen;
+pub mod mesh_utils;
+#[cfg(feature = "native")]
+pub mod regularizer;
+
+#[cfg(test)]
+#[cfg(test)]
+mod tests;
+pub mod gmsh_interop;
+pub mod geo_builder;
+pub mod partition;
+pub mod assembly;
+pub mod units;
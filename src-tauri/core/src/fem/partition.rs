@@ -0,0 +1,114 @@
+//! Splits a TetMesh into balanced chunks for distributed (MPI) solvers.
+use serde::Serialize;
+use super::mesh::TetMesh;
+
+/// One partition as a standalone mesh plus the bookkeeping a parallel solver needs
+/// to stitch the pieces back together.
+#[derive(Debug, Clone, Serialize)]
+pub struct MeshPartition {
+    /// Partition number, 0-based
+    pub id: usize,
+    /// The partition's elements with locally renumbered nodes
+    pub mesh: TetMesh,
+    /// Local node index -> node index in the source mesh
+    pub global_nodes: Vec<usize>,
+    /// Local element index -> element index in the source mesh
+    pub global_elements: Vec<usize>,
+    /// Local indices of nodes shared with other partitions
+    pub interface_nodes: Vec<usize>,
+}
+
+fn element_centroid(mesh: &TetMesh, elem: &[usize; 10]) -> [f64; 3] {
+    let mut c = [0.0; 3];
+    // Corner nodes are enough to locate the element
+    for &n in &elem[..4] {
+        for (k, ck) in c.iter_mut().enumerate() {
+            *ck += mesh.vertices[n][k] * 0.25;
+        }
+    }
+    c
+}
+
+/// Recursive coordinate bisection: repeatedly splits the element centroids across the
+/// longest axis of their bounding box. Counts are split in proportion to the number
+/// of parts on each side, so any `parts` (not just powers of two) stays balanced.
+/// Returns the owning partition of every element.
+pub fn partition_rcb(mesh: &TetMesh, parts: usize) -> Vec<usize> {
+    let parts = parts.max(1).min(mesh.indices.len().max(1));
+    let centroids: Vec<[f64; 3]> = mesh.indices.iter().map(|e| element_centroid(mesh, e)).collect();
+    let mut owner = vec![0; mesh.indices.len()];
+    let mut elems: Vec<usize> = (0..mesh.indices.len()).collect();
+    bisect(&centroids, &mut elems, parts, 0, &mut owner);
+    owner
+}
+
+fn bisect(centroids: &[[f64; 3]], elems: &mut [usize], parts: usize, first_id: usize, owner: &mut [usize]) {
+    if parts <= 1 || elems.len() <= 1 {
+        for &e in elems.iter() {
+            owner[e] = first_id;
+        }
+        return;
+    }
+
+    let mut min = [f64::MAX; 3];
+    let mut max = [f64::MIN; 3];
+    for &e in elems.iter() {
+        for k in 0..3 {
+            min[k] = min[k].min(centroids[e][k]);
+            max[k] = max[k].max(centroids[e][k]);
+        }
+    }
+    let axis = (0..3).max_by(|&a, &b| (max[a] - min[a]).total_cmp(&(max[b] - min[b]))).unwrap();
+
+    let left_parts = parts / 2;
+    let split = elems.len() * left_parts / parts;
+    elems.select_nth_unstable_by(split, |&a, &b| centroids[a][axis].total_cmp(&centroids[b][axis]));
+
+    let (left, right) = elems.split_at_mut(split);
+    bisect(centroids, left, left_parts, first_id, owner);
+    bisect(centroids, right, parts - left_parts, first_id + left_parts, owner);
+}
+
+/// Builds a standalone mesh for every partition id in `owner`.
+pub fn split_partitions(mesh: &TetMesh, owner: &[usize]) -> Vec<MeshPartition> {
+    let count = owner.iter().copied().max().map_or(0, |m| m + 1);
+
+    // How many partitions touch each node
+    let mut node_parts: Vec<Vec<usize>> = vec![Vec::new(); mesh.vertices.len()];
+    for (e, elem) in mesh.indices.iter().enumerate() {
+        for &n in elem {
+            if !node_parts[n].contains(&owner[e]) {
+                node_parts[n].push(owner[e]);
+            }
+        }
+    }
+
+    (0..count).map(|id| {
+        let mut local_of = vec![usize::MAX; mesh.vertices.len()];
+        let mut global_nodes = Vec::new();
+        let mut global_elements = Vec::new();
+        let mut indices = Vec::new();
+
+        for (e, elem) in mesh.indices.iter().enumerate() {
+            if owner[e] != id { continue; }
+            let mut local = [0usize; 10];
+            for (i, &n) in elem.iter().enumerate() {
+                if local_of[n] == usize::MAX {
+                    local_of[n] = global_nodes.len();
+                    global_nodes.push(n);
+                }
+                local[i] = local_of[n];
+            }
+            indices.push(local);
+            global_elements.push(e);
+        }
+
+        let vertices = global_nodes.iter().map(|&n| mesh.vertices[n]).collect();
+        let interface_nodes = global_nodes.iter().enumerate()
+            .filter(|(_, n)| node_parts[**n].len() > 1)
+            .map(|(local, _)| local)
+            .collect();
+
+        MeshPartition { id, mesh: TetMesh::new(vertices, indices), global_nodes, global_elements, interface_nodes }
+    }).collect()
+}
@@ -1,11 +1,16 @@
+//! Gauss quadrature rules on the reference tetrahedron.
 use serde::{Deserialize, Serialize};
 
+/// One quadrature point on the reference tetrahedron.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct IntegrationPoint {
-    pub xi: [f64; 4], // Barycentric coordinates (L1, L2, L3, L4)
+    /// Barycentric coordinates (L1, L2, L3, L4)
+    pub xi: [f64; 4],
+    /// Weight, scaled so the weights sum to the reference volume
     pub weight: f64,
 }
 
+/// Quadrature rules for tetrahedra.
 pub struct TetQuadrature;
 
 impl TetQuadrature {
@@ -55,6 +60,42 @@ impl TetQuadrature {
                     IntegrationPoint { xi: [p2_b, p2_b, p2_b, p2_a], weight: w2 },
                 ]
             }
+            15 => {
+                // Order 5, Keast #6: every weight positive, unlike the 5-point rule
+                // Point 1: Centroid
+                // Points 2-5: (0, 1/3, 1/3, 1/3) permutations (face centroids)
+                // Points 6-9: (8/11, 1/11, 1/11, 1/11) permutations
+                // Points 10-15: (c, c, d, d) permutations, d = 1/2 - c
+                let w1 = 0.0302836780970892;
+                let w2 = 0.006026785714285714;
+                let w3 = 0.011645249086029;
+                let w4 = 0.0109491415613864;
+
+                let p1 = 0.25;
+                let p2 = 1.0 / 3.0;
+                let p3_a = 8.0 / 11.0;
+                let p3_b = 1.0 / 11.0;
+                let c = 0.0665501535736643;
+                let d = 0.5 - c;
+
+                vec![
+                    IntegrationPoint { xi: [p1, p1, p1, p1], weight: w1 },
+                    IntegrationPoint { xi: [0.0, p2, p2, p2], weight: w2 },
+                    IntegrationPoint { xi: [p2, 0.0, p2, p2], weight: w2 },
+                    IntegrationPoint { xi: [p2, p2, 0.0, p2], weight: w2 },
+                    IntegrationPoint { xi: [p2, p2, p2, 0.0], weight: w2 },
+                    IntegrationPoint { xi: [p3_a, p3_b, p3_b, p3_b], weight: w3 },
+                    IntegrationPoint { xi: [p3_b, p3_a, p3_b, p3_b], weight: w3 },
+                    IntegrationPoint { xi: [p3_b, p3_b, p3_a, p3_b], weight: w3 },
+                    IntegrationPoint { xi: [p3_b, p3_b, p3_b, p3_a], weight: w3 },
+                    IntegrationPoint { xi: [c, c, d, d], weight: w4 },
+                    IntegrationPoint { xi: [c, d, c, d], weight: w4 },
+                    IntegrationPoint { xi: [c, d, d, c], weight: w4 },
+                    IntegrationPoint { xi: [d, c, c, d], weight: w4 },
+                    IntegrationPoint { xi: [d, c, d, c], weight: w4 },
+                    IntegrationPoint { xi: [d, d, c, c], weight: w4 },
+                ]
+            }
             _ => panic!("Unsupported integration rule"),
         }
     }
@@ -1,7 +1,10 @@
+//! Surface remeshing towards a target edge length before tetrahedralization.
 use std::collections::HashMap;
 use nalgebra::Vector3;
 use meshopt::{VertexDataAdapter, SimplifyOptions};
 
+/// Decimates or subdivides a triangle surface so its edges approach `target_edge_len`.
+/// Takes and returns flat vertex (xyz) and triangle index arrays.
 pub fn regularize(
     vertices: &[f64], 
     indices: &[usize], 
@@ -23,11 +26,8 @@ pub fn regularize(
     let target_tri_count = (surface_area / ideal_tri_area) as usize;
     let current_tri_count = tris.len() / 3;
 
-    println!("Regularizer: Current Tris: {}, Target: {}", current_tri_count, target_tri_count);
-
     // 3. DECIMATE (Simplify) if too dense
     if current_tri_count > target_tri_count {
-        println!("Regularizer: Decimating...");
         let (d_verts, d_tris) = decimate_mesh(&verts, &tris, target_tri_count, target_edge_len * 0.25);
         verts = d_verts;
         tris = d_tris;
@@ -37,12 +37,11 @@ pub fn regularize(
     let max_len_sq = (target_edge_len * 1.5).powi(2);
     let max_iters = 3;
     
-    for i in 0..max_iters {
+    for _ in 0..max_iters {
         let (new_verts, new_tris, split_count) = subdivide_long_edges(&verts, &tris, max_len_sq);
         verts = new_verts;
         tris = new_tris;
         if split_count == 0 { break; }
-        println!("Regularizer: Subdivision Pass {} - Split {} edges", i+1, split_count);
     }
 
     // 5. Prune Degenerates & Duplicates (Fixes "self-intersecting facets" errors)
@@ -119,14 +118,14 @@ fn subdivide_long_edges(
         let edges = [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])];
         for (a, b) in edges {
             let key = if a < b { (a, b) } else { (b, a) };
-            if !edge_split_map.contains_key(&key) {
+            if let std::collections::hash_map::Entry::Vacant(e) = edge_split_map.entry(key) {
                 // Use squared distance check for perf
                 let dist_sq = (verts[a] - verts[b]).norm_squared();
                 if dist_sq > max_len_sq {
                     let mid = (verts[a] + verts[b]) * 0.5;
                     let idx = new_verts.len();
                     new_verts.push(mid);
-                    edge_split_map.insert(key, idx);
+                    e.insert(idx);
                 }
             }
         }
@@ -1,5 +1,5 @@
-use nalgebra::{Matrix3, Matrix6x3, SMatrix, Vector3, Vector6};
-use super::quadrature::IntegrationPoint;
+//! Quadratic (10-node) tetrahedron shape functions and element matrices.
+use nalgebra::{Matrix3, SMatrix, Vector3};
 
 /// Tet10: 10-node Quadratic Tetrahedron
 /// Node ordering (VTK convention):
@@ -128,8 +128,7 @@ impl Tet10 {
     /// J = sum( dNi/dxi * xi )
     pub fn jacobian(node_coords: &[Vector3<f64>; 10], local_derivs: &SMatrix<f64, 3, 10>) -> Matrix3<f64> {
         let mut j = Matrix3::zeros();
-        for i in 0..10 {
-            let coords = node_coords[i];
+        for (i, coords) in node_coords.iter().enumerate() {
             let d_n = local_derivs.column(i);
             // J = [dx/dL1 dy/dL1 dz/dL1; ...]
             j += d_n * coords.transpose();
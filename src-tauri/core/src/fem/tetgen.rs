@@ -1,16 +1,23 @@
+//! TetGen bindings: surface repair and tetrahedralization of triangle soups.
 use std::os::raw::{c_double, c_int, c_char};
 use serde::Serialize;
 use super::mesh_utils::weld_mesh;
 use std::ffi::CString;
+use crate::fem::gmsh_interop::gmsh_launch_failed;
+use crate::messages::{Message, MessageCode};
 use std::process::{Command, Stdio};
 use std::fs::File;
 use std::io::{Write, Read};
 
+/// Linear tet mesh returned by TetGen.
 #[derive(Serialize, Clone)]
 pub struct TetrahedralizedMesh {
-    pub vertices: Vec<[f64; 3]>, // 3D points
-    pub indices: Vec<usize>,     // Flattened tet indices
-    pub surface_indices: Vec<usize>, // Flattened surface triangle indices
+    /// Node positions
+    pub vertices: Vec<[f64; 3]>,
+    /// Flattened tet indices
+    pub indices: Vec<usize>,
+    /// Flattened surface triangle indices
+    pub surface_indices: Vec<usize>,
 }
 
 // MATCHING C++ LAYOUT: Pointers first!
@@ -34,54 +41,56 @@ unsafe extern "C" {
     fn free_mesh_result(result: *mut MeshResult);
 }
 
+/// Remeshed triangle soup returned by `repair_mesh`.
 #[derive(Serialize)]
 pub struct SurfaceMesh {
+    /// Flat xyz coordinates, three vertices per triangle
     pub vertices: Vec<f64>,
 }
 
-fn write_stl_ascii(path: &str, verts: &[f64]) -> Result<(), String> {
-    let mut file = File::create(path).map_err(|e| e.to_string())?;
-    writeln!(file, "solid gmsh_tmp").map_err(|e| e.to_string())?;
+fn write_stl_ascii(path: &str, verts: &[f64]) -> Result<(), Message> {
+    let io = |e: std::io::Error| Message::write_failed(path, e);
+    let mut file = File::create(path).map_err(io)?;
+    writeln!(file, "solid gmsh_tmp").map_err(io)?;
     
     // We assume simple triangle soup input (every 3 vertices = 1 face)
     for chunk in verts.chunks(9) {
         if chunk.len() < 9 { break; }
         // Normal (dummy)
-        writeln!(file, "facet normal 0 0 0").map_err(|e| e.to_string())?;
-        writeln!(file, "  outer loop").map_err(|e| e.to_string())?;
-        writeln!(file, "    vertex {:.6} {:.6} {:.6}", chunk[0], chunk[1], chunk[2]).map_err(|e| e.to_string())?;
-        writeln!(file, "    vertex {:.6} {:.6} {:.6}", chunk[3], chunk[4], chunk[5]).map_err(|e| e.to_string())?;
-        writeln!(file, "    vertex {:.6} {:.6} {:.6}", chunk[6], chunk[7], chunk[8]).map_err(|e| e.to_string())?;
-        writeln!(file, "  endloop").map_err(|e| e.to_string())?;
-        writeln!(file, "endfacet").map_err(|e| e.to_string())?;
+        writeln!(file, "facet normal 0 0 0").map_err(io)?;
+        writeln!(file, "  outer loop").map_err(io)?;
+        writeln!(file, "    vertex {:.6} {:.6} {:.6}", chunk[0], chunk[1], chunk[2]).map_err(io)?;
+        writeln!(file, "    vertex {:.6} {:.6} {:.6}", chunk[3], chunk[4], chunk[5]).map_err(io)?;
+        writeln!(file, "    vertex {:.6} {:.6} {:.6}", chunk[6], chunk[7], chunk[8]).map_err(io)?;
+        writeln!(file, "  endloop").map_err(io)?;
+        writeln!(file, "endfacet").map_err(io)?;
     }
-    writeln!(file, "endsolid gmsh_tmp").map_err(|e| e.to_string())?;
+    writeln!(file, "endsolid gmsh_tmp").map_err(io)?;
     Ok(())
 }
 
-fn read_stl_ascii(path: &str) -> Result<Vec<f64>, String> {
-    let mut file = File::open(path).map_err(|e| e.to_string())?;
+fn read_stl_ascii(path: &str) -> Result<Vec<f64>, Message> {
+    let mut file = File::open(path).map_err(|e| Message::read_failed(path, e))?;
     let mut content = String::new();
-    file.read_to_string(&mut content).map_err(|e| e.to_string())?;
+    file.read_to_string(&mut content).map_err(|e| Message::read_failed(path, e))?;
 
     let mut vertices = Vec::new();
     
     // Very naive ASCII STL parser for Gmsh output
     for line in content.lines() {
-        let parts: Vec<&str> = line.trim().split_whitespace().collect();
-        if parts.len() == 4 && parts[0] == "vertex" {
-            if let (Ok(x), Ok(y), Ok(z)) = (parts[1].parse::<f64>(), parts[2].parse::<f64>(), parts[3].parse::<f64>()) {
-                vertices.push(x);
-                vertices.push(y);
-                vertices.push(z);
-            }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() == 4 && parts[0] == "vertex"
+            && let (Ok(x), Ok(y), Ok(z)) = (parts[1].parse::<f64>(), parts[2].parse::<f64>(), parts[3].parse::<f64>()) {
+            vertices.push(x);
+            vertices.push(y);
+            vertices.push(z);
         }
     }
     Ok(vertices)
 }
 
-#[tauri::command]
-pub async fn cmd_repair_mesh(vertices: Vec<f64>, target_len: f64) -> Result<SurfaceMesh, String> {
+/// Remeshes a triangle soup with Gmsh (external `./gmsh`) towards `target_len` edges.
+pub fn repair_mesh(vertices: Vec<f64>, target_len: f64) -> Result<SurfaceMesh, Message> {
     let in_file = "temp_input.stl";
     let out_file = "temp_output.stl";
     let geo_file = "temp_repair.geo";
@@ -138,13 +147,12 @@ pub async fn cmd_repair_mesh(vertices: Vec<f64>, target_len: f64) -> Result<Surf
     geo_content.push_str("Exit;\\n");
 
     {
-        let mut f = File::create(geo_file).map_err(|e| e.to_string())?;
-        f.write_all(geo_content.as_bytes()).map_err(|e| e.to_string())?;
-        f.flush().map_err(|e| e.to_string())?;
+        let io = |e: std::io::Error| Message::write_failed(geo_file, e);
+        let mut f = File::create(geo_file).map_err(io)?;
+        f.write_all(geo_content.as_bytes()).map_err(io)?;
+        f.flush().map_err(io)?;
     }
 
-    println!("Running Gmsh repair (headless) on {} vertices...", vertices.len() / 3);
-
     // 3. Run Gmsh
     // ADDED: -nopopup flag to prevent GUI
     let status = Command::new("./gmsh")
@@ -153,15 +161,18 @@ pub async fn cmd_repair_mesh(vertices: Vec<f64>, target_len: f64) -> Result<Surf
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .status()
-        .map_err(|e| format!("Failed to execute gmsh: {}", e))?;
+        .map_err(gmsh_launch_failed)?;
 
+    // Gmsh's output went to the console, so there is no stderr to pass on
     if !status.success() {
-        return Err("Gmsh process exited with error code. See console for details.".into());
+        return Err(Message::new(MessageCode::GmshFailed, "Gmsh process exited with error code. See console for details.")
+            .with("stderr", format!("exit status {}", status)));
     }
 
     // 4. Read Result
     if !std::path::Path::new(out_file).exists() {
-        return Err("Gmsh failed to generate output file.".into());
+        return Err(Message::new(MessageCode::GmshFailed, "Gmsh failed to generate output file.")
+            .with("stderr", format!("no {} was written", out_file)));
     }
 
     let new_verts = read_stl_ascii(out_file)?;
@@ -171,13 +182,11 @@ pub async fn cmd_repair_mesh(vertices: Vec<f64>, target_len: f64) -> Result<Surf
     let _ = std::fs::remove_file(out_file);
     let _ = std::fs::remove_file(geo_file);
 
-    println!("Gmsh repair complete. New vertex count: {}", new_verts.len() / 3);
-
     Ok(SurfaceMesh { vertices: new_verts })
 }
 
-#[tauri::command]
-pub async fn cmd_tetrahedralize(vertices: Vec<f64>, options: String, target_len: Option<f64>) -> Result<TetrahedralizedMesh, String> {
+/// Welds, optionally regularizes, and tetrahedralizes a triangle soup with TetGen.
+pub fn tetrahedralize(vertices: Vec<f64>, options: String, target_len: Option<f64>) -> Result<TetrahedralizedMesh, Message> {
     
     // 1. Manually spawn a thread with LARGE STACK SIZE (8MB)
     let builder = std::thread::Builder::new()
@@ -192,24 +201,25 @@ pub async fn cmd_tetrahedralize(vertices: Vec<f64>, options: String, target_len:
         let (mut verts, mut faces) = weld_mesh(&vertices, weld_epsilon);
 
         // --- STEP 2: Regularization (Optional) ---
-        if let Some(len) = target_len {
-            if len > 0.0 {
-                // Convert i32 faces to usize for the regularizer
-                let faces_usize: Vec<usize> = faces.iter().map(|&x| x as usize).collect();
+        if let Some(len) = target_len
+            && len > 0.0 {
+            // Convert i32 faces to usize for the regularizer
+            let faces_usize: Vec<usize> = faces.iter().map(|&x| x as usize).collect();
                 
-                // Run Decimation/Subdivision
-                let (reg_verts, reg_faces) = crate::fem::regularizer::regularize(&verts, &faces_usize, len);
+            // Run Decimation/Subdivision
+            let (reg_verts, reg_faces) = crate::fem::regularizer::regularize(&verts, &faces_usize, len);
                 
-                // Update buffers
-                verts = reg_verts;
-                faces = reg_faces.iter().map(|&x| x as i32).collect();
-            }
+            // Update buffers
+            verts = reg_verts;
+            faces = reg_faces.iter().map(|&x| x as i32).collect();
         }
 
         let num_verts = (verts.len() / 3) as i32;
         let num_faces = (faces.len() / 3) as i32;
         
-        let c_options = CString::new(options).map_err(|_| "Invalid options string")?;
+        let c_options = CString::new(options.as_str()).map_err(|_| {
+            Message::new(MessageCode::InvalidMeshOptions, "Invalid options string").with("options", options.as_str())
+        })?;
 
         unsafe {
             // --- STEP 3: C++ Call ---
@@ -222,19 +232,22 @@ pub async fn cmd_tetrahedralize(vertices: Vec<f64>, options: String, target_len:
             );
 
             if result_ptr.is_null() {
-                return Err("TetGen returned null.".into());
+                return Err(Message::new(MessageCode::TetgenFailed, "TetGen returned null."));
             }
 
             let res = &*result_ptr;
             if res.num_tetrahedra == 0 {
                 free_mesh_result(result_ptr);
-                return Err("TetGen failed to generate elements.".into());
+                return Err(Message::new(MessageCode::TetgenFailed, "TetGen failed to generate elements."));
             }
             
             // Safety Check for "Mesh Explosion"
             if res.num_tetrahedra > 3_000_000 {
                  free_mesh_result(result_ptr);
-                 return Err(format!("Mesh Explosion: Generated {} tetrahedra. Try increasing Max Edge Length.", res.num_tetrahedra));
+                 return Err(Message::new(
+                     MessageCode::TetgenTooManyElements,
+                     format!("Mesh Explosion: Generated {} tetrahedra. Try increasing Max Edge Length.", res.num_tetrahedra),
+                 ).with("tetrahedra", res.num_tetrahedra));
             }
 
             // --- STEP 4: Copy Results ---
@@ -259,7 +272,7 @@ pub async fn cmd_tetrahedralize(vertices: Vec<f64>, options: String, target_len:
                 surface_indices,
             })
         }
-    }).map_err(|e| e.to_string())?;
+    }).map_err(|e| Message::new(MessageCode::MeshingPanicked, format!("Failed to start TetGen thread: {}", e)))?;
 
-    handle.join().map_err(|_| "Thread panicked".to_string())?
+    handle.join().map_err(|_| Message::new(MessageCode::MeshingPanicked, "Thread panicked"))?
 }
\ No newline at end of file
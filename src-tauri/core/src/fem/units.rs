@@ -0,0 +1,160 @@
+//! Unit handling for solver inputs and reported results.
+//!
+//! Everything inside the solver is mm-N-MPa (consistent: N/mm^2 = MPa). Conversion happens
+//! only at the edges: when materials/loads come in and when results are reported.
+use serde::{Deserialize, Serialize};
+use crate::messages::MessageCode;
+
+/// Length unit for inputs and reports.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LengthUnit {
+    /// Millimetres
+    #[default]
+    Mm,
+    /// Inches
+    In,
+}
+
+/// Force unit for inputs and reports.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ForceUnit {
+    /// Newtons
+    #[default]
+    N,
+    /// Kilograms-force
+    Kgf,
+    /// Pounds-force
+    Lbf,
+}
+
+/// Stress unit for inputs and reports.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StressUnit {
+    /// Megapascals (N/mm²)
+    #[default]
+    Mpa,
+    /// Pounds per square inch
+    Psi,
+    /// Kilograms-force per mm²
+    #[serde(rename = "kgf/mm2")]
+    KgfPerMm2,
+}
+
+impl LengthUnit {
+    /// How many of this unit make one millimetre.
+    fn per_mm(self) -> f64 {
+        match self { LengthUnit::Mm => 1.0, LengthUnit::In => 1.0 / 25.4 }
+    }
+    fn label(self) -> &'static str {
+        match self { LengthUnit::Mm => "mm", LengthUnit::In => "in" }
+    }
+}
+
+impl ForceUnit {
+    /// How many of this unit make one newton.
+    fn per_newton(self) -> f64 {
+        match self { ForceUnit::N => 1.0, ForceUnit::Kgf => 1.0 / 9.80665, ForceUnit::Lbf => 1.0 / 4.4482216152605 }
+    }
+    fn label(self) -> &'static str {
+        match self { ForceUnit::N => "N", ForceUnit::Kgf => "kgf", ForceUnit::Lbf => "lbf" }
+    }
+}
+
+impl StressUnit {
+    /// How many of this unit make one MPa.
+    fn per_mpa(self) -> f64 {
+        match self { StressUnit::Mpa => 1.0, StressUnit::Psi => 145.037_737_73, StressUnit::KgfPerMm2 => 1.0 / 9.80665 }
+    }
+    fn label(self) -> &'static str {
+        match self { StressUnit::Mpa => "MPa", StressUnit::Psi => "psi", StressUnit::KgfPerMm2 => "kgf/mm²" }
+    }
+}
+
+/// Physical dimension of a value, selecting which unit converts it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dimension {
+    /// Length unit
+    Length,
+    /// Length unit squared
+    Area,
+    /// Length unit cubed
+    Volume,
+    /// Force unit
+    Force,
+    /// Stress unit
+    Stress,
+}
+
+/// Units chosen by the project for inputs and reports. Defaults to mm-N-MPa.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct UnitSystem {
+    /// Length unit
+    #[serde(default)]
+    pub length: LengthUnit,
+    /// Force unit
+    #[serde(default)]
+    pub force: ForceUnit,
+    /// Stress unit, including Young's modulus
+    #[serde(default)]
+    pub stress: StressUnit,
+}
+
+/// A reported value with its unit spelled out. `code` names the row for translation;
+/// `name` is its English label.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReportQuantity {
+    /// Translation key for the row
+    pub code: MessageCode,
+    /// English label
+    pub name: String,
+    /// Value in `unit`
+    pub value: f64,
+    /// Unit label, e.g. "mm³"
+    pub unit: String,
+}
+
+impl UnitSystem {
+    fn factor(&self, dim: Dimension) -> f64 {
+        match dim {
+            Dimension::Length => self.length.per_mm(),
+            Dimension::Area => self.length.per_mm().powi(2),
+            Dimension::Volume => self.length.per_mm().powi(3),
+            Dimension::Force => self.force.per_newton(),
+            Dimension::Stress => self.stress.per_mpa(),
+        }
+    }
+
+    /// Unit label for `dim`, e.g. "mm²".
+    pub fn label(&self, dim: Dimension) -> String {
+        match dim {
+            Dimension::Length => self.length.label().to_string(),
+            Dimension::Area => format!("{}²", self.length.label()),
+            Dimension::Volume => format!("{}³", self.length.label()),
+            Dimension::Force => self.force.label().to_string(),
+            Dimension::Stress => self.stress.label().to_string(),
+        }
+    }
+
+    /// Internal (mm-N-MPa) value -> project units.
+    pub fn export_value(&self, value: f64, dim: Dimension) -> f64 {
+        value * self.factor(dim)
+    }
+
+    /// Project units -> internal (mm-N-MPa) value.
+    pub fn import_value(&self, value: f64, dim: Dimension) -> f64 {
+        value / self.factor(dim)
+    }
+
+    /// Internal value converted to a labelled report row.
+    pub fn report(&self, code: MessageCode, name: &str, internal_value: f64, dim: Dimension) -> ReportQuantity {
+        ReportQuantity {
+            code,
+            name: name.to_string(),
+            value: self.export_value(internal_value, dim),
+            unit: self.label(dim),
+        }
+    }
+}
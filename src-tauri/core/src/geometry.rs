@@ -0,0 +1,454 @@
+//! Smart-split data types (optimizer input, cuts, anchors) and the geometric helpers they share.
+use serde::{Deserialize, Serialize};
+use geo::{
+    algorithm::{convex_hull::ConvexHull},
+    Point, LineString, Line, Euclidean, Distance
+};
+use std::f64::consts::PI;
+
+// --- Data Structures ---
+
+/// Board, obstacles and bed size handed to the cut optimizer.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GeometryInput {
+    /// Board outline as a closed polygon (mm)
+    pub outline: Vec<[f64; 2]>,
+    /// Holes and keep-out regions the cut must avoid
+    pub obstacles: Vec<Obstacle>,
+    /// Usable machine bed size (mm); every split part must fit within it
+    pub bed_width: f64,
+    /// See `bed_width`
+    pub bed_height: f64,
+    /// User-drawn starting line, if any
+    pub initial_line: Option<[[f64; 2]; 2]>, 
+    /// Optional named geometry used to express the result parametrically
+    pub references: Option<Vec<ReferenceGeometry>>,
+    /// Optional manufacturing tolerances for Monte Carlo robustness scoring
+    pub tolerance: Option<ToleranceSpec>,
+    /// Grid spacing (mm) for a sampled distance field; exact checks when absent
+    pub sdf_resolution: Option<f64>,
+    /// Dovetail corner radius (usually the endmill radius) and extra clearance
+    /// added to the mating convex corners so tail and socket still fit together
+    pub fillet_radius: Option<f64>,
+    /// See `fillet_radius`
+    pub joint_clearance: Option<f64>,
+}
+
+/// Symmetric tolerances (+/- mm) used to perturb the input when scoring a cut.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ToleranceSpec {
+    /// Maximum shift applied to each obstacle
+    pub obstacle_position: f64,
+    /// Maximum shift applied to each outline vertex
+    pub outline_dimension: f64,
+    /// Number of Monte Carlo samples (defaults when absent)
+    pub samples: Option<usize>,
+}
+
+/// Region the cut must keep clear of.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")] 
+pub enum Obstacle {
+    /// Round hole or keep-out
+    Circle {
+        /// Center x (mm)
+        x: f64,
+        /// Center y (mm)
+        y: f64,
+        /// Radius (mm)
+        r: f64,
+    },
+    /// Arbitrary closed polygon
+    Poly {
+        /// Polygon vertices (mm)
+        points: Vec<[f64; 2]>,
+    },
+}
+
+/// Named project geometry the frontend can re-evaluate after a parameter change.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ReferenceGeometry {
+    /// Straight edge of the outline
+    Edge {
+        /// Name anchors refer to
+        name: String,
+        /// Edge start point (mm)
+        start: [f64; 2],
+        /// Edge end point (mm)
+        end: [f64; 2],
+    },
+    /// Hole center
+    Hole {
+        /// Name anchors refer to
+        name: String,
+        /// Center x (mm)
+        x: f64,
+        /// Center y (mm)
+        y: f64,
+    },
+}
+
+/// A cut endpoint expressed relative to a `ReferenceGeometry`.
+/// Edge: `fraction` along start->end, `offset` along the left-hand normal.
+/// Hole: raw offset from the hole center.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum AnchoredPoint {
+    /// Point relative to a `ReferenceGeometry::Edge`
+    Edge {
+        /// Name of the referenced edge
+        reference: String,
+        /// Position along the edge, 0 at start and 1 at end
+        fraction: f64,
+        /// Distance from the edge along its left-hand normal (mm)
+        offset: f64,
+    },
+    /// Point relative to a `ReferenceGeometry::Hole`
+    Hole {
+        /// Name of the referenced hole
+        reference: String,
+        /// Offset from the hole center in x (mm)
+        dx: f64,
+        /// Offset from the hole center in y (mm)
+        dy: f64,
+    },
+}
+
+/// Both endpoints of a cut expressed against reference geometry.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CutAnchors {
+    /// Anchor for `GeneratedCut::start`
+    pub start: AnchoredPoint,
+    /// Anchor for `GeneratedCut::end`
+    pub end: AnchoredPoint,
+}
+
+/// Outcome of a cut optimization run.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OptimizationResult {
+    /// Whether the best cut satisfies every constraint
+    pub success: bool,
+    /// Final cost of the best cut (lower is better)
+    pub cost: f64,
+    /// The cut(s) found, one per split
+    pub shapes: Vec<GeneratedCut>,
+    /// Lowest robustness among `shapes`, i.e. the weakest cut (if requested)
+    pub robustness: Option<f64>,
+    /// Stored run this result came from, for replay_optimization (None if it was not saved)
+    #[serde(default)]
+    pub run_id: Option<String>,
+}
+
+/// A straight cut line with a single dovetail joint.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GeneratedCut {
+    /// Stable id used by the frontend
+    pub id: String,
+    /// Cut line start point (mm)
+    pub start: [f64; 2],
+    /// Cut line end point (mm)
+    pub end: [f64; 2],
+    /// Dovetail width at its widest point (mm)
+    pub dovetail_width: f64,
+    /// Dovetail depth perpendicular to the cut (mm)
+    pub dovetail_height: f64,
+    /// Dovetail position along the cut, 0 at start and 1 at end
+    pub dovetail_t: f64, 
+    /// Whether the dovetail points to the right of start->end instead of the left
+    pub flipped: bool,
+    /// Endpoints relative to reference geometry, if references were given
+    pub anchors: Option<CutAnchors>,
+    /// Dovetail corner radius (mm), copied from `GeometryInput`
+    pub fillet_radius: f64,
+    /// Joint clearance (mm), copied from `GeometryInput`
+    pub joint_clearance: f64,
+    /// Fraction of tolerance samples where this cut stays valid (if requested)
+    #[serde(default)]
+    pub robustness: Option<f64>,
+}
+
+impl GeneratedCut {
+    /// Full cut polyline including the dovetail (see `dovetail_points`).
+    pub fn polyline(&self) -> [Point<f64>; 6] {
+        let p1 = Point::new(self.start[0], self.start[1]);
+        let p2 = Point::new(self.end[0], self.end[1]);
+        let dx = p2.x() - p1.x();
+        let dy = p2.y() - p1.y();
+        let len = (dx * dx + dy * dy).sqrt().max(1e-9);
+        dovetail_points(p1, p2, (dx / len, dy / len), self.dovetail_width, self.dovetail_height, self.dovetail_t, self.flipped)
+    }
+
+    /// Cut polyline as machined on the tail part (the side the head belongs to).
+    /// The tail's concave corners are at the base and get the tool radius; its
+    /// convex head corners get radius + clearance so they clear the socket fillets.
+    pub fn tail_polyline(&self) -> Vec<Point<f64>> {
+        let r = self.fillet_radius;
+        let c = self.joint_clearance;
+        fillet_corners(&self.polyline(), &[0.0, r, r + c, r + c, r, 0.0])
+    }
+
+    /// Cut polyline as machined on the socket part (the side with the cavity).
+    /// Mirror of `tail_polyline`: concave head corners get the tool radius,
+    /// convex base corners get radius + clearance.
+    pub fn socket_polyline(&self) -> Vec<Point<f64>> {
+        let r = self.fillet_radius;
+        let c = self.joint_clearance;
+        fillet_corners(&self.polyline(), &[0.0, r + c, r, r, r + c, 0.0])
+    }
+}
+
+// --- Geometric Helpers ---
+
+/// Builds the cut polyline p1 -> base_l -> head_l -> head_r -> base_r -> p2.
+/// `u` is the unit direction p1->p2. The head is 1.5x the base width and
+/// protrudes `h` along the left normal (right normal when `flipped`).
+pub fn dovetail_points(
+    p1: Point<f64>,
+    p2: Point<f64>,
+    u: (f64, f64),
+    w: f64,
+    h: f64,
+    t: f64,
+    flipped: bool,
+) -> [Point<f64>; 6] {
+    let (ux, uy) = u;
+    let (vx, vy) = if flipped { (uy, -ux) } else { (-uy, ux) };
+
+    let center = Point::new(p1.x() + (p2.x() - p1.x()) * t, p1.y() + (p2.y() - p1.y()) * t);
+    let base_half = w / 2.0;
+    let head_half = (w * 1.5) / 2.0;
+    let base_l = Point::new(center.x() - ux * base_half, center.y() - uy * base_half);
+    let base_r = Point::new(center.x() + ux * base_half, center.y() + uy * base_half);
+    let head_l = Point::new(center.x() - ux * head_half + vx * h, center.y() - uy * head_half + vy * h);
+    let head_r = Point::new(center.x() + ux * head_half + vx * h, center.y() + uy * head_half + vy * h);
+
+    [p1, base_l, head_l, head_r, base_r, p2]
+}
+
+/// Checks if a set of points fits in the bed (Standard or Rotated)
+/// Returns a penalty score (0.0 = fits, >0.0 = excess area/length)
+pub fn check_fit(points: &[Point<f64>], bed_w: f64, bed_h: f64) -> f64 {
+    // 1. Compute Convex Hull (Geo crate makes this easy)
+    // We need a LineString or Polygon for convex_hull
+    let poly = LineString::from_iter(points.iter().copied()).convex_hull();
+    let hull_points: Vec<Point<f64>> = poly.exterior().points().collect();
+
+    if hull_points.len() < 3 {
+        return 0.0;
+    }
+
+    let mut min_excess = f64::MAX;
+
+    // 2. Rotating Calipers (Simplified for Rectangle Fitting)
+    let n = hull_points.len();
+    for i in 0..n {
+        let p1 = hull_points[i];
+        let p2 = hull_points[(i + 1) % n];
+
+        let dx = p2.x() - p1.x();
+        let dy = p2.y() - p1.y();
+        let len = (dx * dx + dy * dy).sqrt();
+        if len < 1e-6 { continue; }
+
+        let ux = dx / len;
+        let uy = dy / len;
+        let vx = -uy;
+        let vy = ux;
+
+        // Project all points onto this axis system
+        let (mut min_u, mut max_u) = (f64::MAX, f64::MIN);
+        let (mut min_v, mut max_v) = (f64::MAX, f64::MIN);
+
+        for p in &hull_points {
+            let u = p.x() * ux + p.y() * uy;
+            let v = p.x() * vx + p.y() * vy;
+            min_u = min_u.min(u);
+            max_u = max_u.max(u);
+            min_v = min_v.min(v);
+            max_v = max_v.max(v);
+        }
+
+        let w = max_u - min_u;
+        let h = max_v - min_v;
+
+        // Check Orientation 1
+        let exc_1 = (w - bed_w).max(0.0) + (h - bed_h).max(0.0);
+        // Check Orientation 2 (Rotated 90 deg)
+        let exc_2 = (w - bed_h).max(0.0) + (h - bed_w).max(0.0);
+
+        let current_excess = exc_1.min(exc_2);
+        if current_excess < min_excess {
+            min_excess = current_excess;
+        }
+
+        // Optimization: If it fits perfectly, return immediately
+        if min_excess < 1e-4 {
+            return 0.0;
+        }
+    }
+
+    // Return squared penalty to encourage optimizer to fix big overflows fast
+    min_excess * min_excess
+}
+
+/// Intersection point of segments p1-p2 and p3-p4, if they cross.
+pub fn get_intersection(p1: Point<f64>, p2: Point<f64>, p3: Point<f64>, p4: Point<f64>) -> Option<Point<f64>> {
+    let s1_x = p2.x() - p1.x();
+    let s1_y = p2.y() - p1.y();
+    let s2_x = p4.x() - p3.x();
+    let s2_y = p4.y() - p3.y();
+
+    let denom = -s2_x * s1_y + s1_x * s2_y;
+    // Parallel lines
+    if denom.abs() < 1e-9 { return None; }
+
+    let s = (-s1_y * (p1.x() - p3.x()) + s1_x * (p1.y() - p3.y())) / denom;
+    let t = ( s2_x * (p1.y() - p3.y()) - s2_y * (p1.x() - p3.x())) / denom;
+
+    if (0.0..=1.0).contains(&s) && (0.0..=1.0).contains(&t) {
+        // Collision detected
+        return Some(Point::new(p1.x() + (t * s1_x), p1.y() + (t * s1_y)));
+    }
+    None
+}
+
+/// Helper to get distance from a point to a line segment
+pub fn dist_point_segment(p: Point<f64>, s_start: Point<f64>, s_end: Point<f64>) -> f64 {
+    let line = Line::new(s_start, s_end);
+    // p.euclidean_distance(&line)
+    Euclidean::distance(&p, &line)
+}
+
+/// Expresses `p` relative to the closest reference.
+/// Edges win ties over holes since cut endpoints normally sit on the outline.
+pub fn anchor_point(p: [f64; 2], references: &[ReferenceGeometry]) -> Option<AnchoredPoint> {
+    let mut best: Option<(f64, AnchoredPoint)> = None;
+
+    for reference in references {
+        let (dist, anchored) = match reference {
+            ReferenceGeometry::Edge { name, start, end } => {
+                let dx = end[0] - start[0];
+                let dy = end[1] - start[1];
+                let len_sq = dx * dx + dy * dy;
+                if len_sq < 1e-12 { continue; }
+
+                let rx = p[0] - start[0];
+                let ry = p[1] - start[1];
+                let fraction = (rx * dx + ry * dy) / len_sq;
+                let offset = (dx * ry - dy * rx) / len_sq.sqrt();
+
+                // Distance to the segment itself (not the infinite line)
+                let t = fraction.clamp(0.0, 1.0);
+                let cx = start[0] + dx * t - p[0];
+                let cy = start[1] + dy * t - p[1];
+                let dist = (cx * cx + cy * cy).sqrt();
+
+                (dist, AnchoredPoint::Edge { reference: name.clone(), fraction, offset })
+            },
+            ReferenceGeometry::Hole { name, x, y } => {
+                let dx = p[0] - x;
+                let dy = p[1] - y;
+                ((dx * dx + dy * dy).sqrt(), AnchoredPoint::Hole { reference: name.clone(), dx, dy })
+            }
+        };
+
+        // Within tolerance an edge replaces a hole; otherwise the first reference found stays
+        let is_better = match &best {
+            Some((best_dist, best_anchor)) => {
+                dist < *best_dist - 1e-9
+                    || (dist <= *best_dist + 1e-9
+                        && matches!(anchored, AnchoredPoint::Edge { .. })
+                        && matches!(best_anchor, AnchoredPoint::Hole { .. }))
+            }
+            None => true,
+        };
+        if is_better {
+            best = Some((dist, anchored));
+        }
+    }
+
+    best.map(|(_, anchored)| anchored)
+}
+
+/// Replaces interior polyline vertices with circular arcs of `radii[i]`.
+/// Radii are clamped so neighbouring fillets never overlap on a shared edge.
+/// Endpoints (and vertices with radius ~0) are kept as-is.
+pub fn fillet_corners(points: &[Point<f64>], radii: &[f64]) -> Vec<Point<f64>> {
+    const ARC_STEPS: usize = 8;
+    let n = points.len();
+    if n < 3 {
+        return points.to_vec();
+    }
+
+    let mut out = vec![points[0]];
+    for i in 1..n - 1 {
+        let prev = points[i - 1];
+        let corner = points[i];
+        let next = points[i + 1];
+        let radius = radii.get(i).copied().unwrap_or(0.0);
+
+        let (ax, ay) = (prev.x() - corner.x(), prev.y() - corner.y());
+        let (bx, by) = (next.x() - corner.x(), next.y() - corner.y());
+        let len_a = (ax * ax + ay * ay).sqrt();
+        let len_b = (bx * bx + by * by).sqrt();
+        if radius <= 1e-6 || len_a < 1e-9 || len_b < 1e-9 {
+            out.push(corner);
+            continue;
+        }
+        let (ax, ay) = (ax / len_a, ay / len_a);
+        let (bx, by) = (bx / len_b, by / len_b);
+
+        // Angle between the two edges at the corner
+        let theta = (ax * bx + ay * by).clamp(-1.0, 1.0).acos();
+        if theta < 1e-6 || (PI - theta) < 1e-6 {
+            out.push(corner);
+            continue;
+        }
+
+        // Tangent distance from the corner, limited to half of each edge
+        let half_tan = (theta / 2.0).tan();
+        let tangent = (radius / half_tan).min(len_a * 0.5).min(len_b * 0.5);
+        let radius = tangent * half_tan;
+
+        let t1 = Point::new(corner.x() + ax * tangent, corner.y() + ay * tangent);
+        let t2 = Point::new(corner.x() + bx * tangent, corner.y() + by * tangent);
+
+        // Arc center lies on the bisector
+        let (mx, my) = (ax + bx, ay + by);
+        let m_len = (mx * mx + my * my).sqrt();
+        let center_dist = radius / (theta / 2.0).sin();
+        let center = Point::new(corner.x() + mx / m_len * center_dist, corner.y() + my / m_len * center_dist);
+
+        let start = (t1.y() - center.y()).atan2(t1.x() - center.x());
+        let end = (t2.y() - center.y()).atan2(t2.x() - center.x());
+        let mut sweep = end - start;
+        if sweep > PI { sweep -= 2.0 * PI; }
+        if sweep < -PI { sweep += 2.0 * PI; }
+
+        for k in 0..=ARC_STEPS {
+            let a = start + sweep * (k as f64 / ARC_STEPS as f64);
+            out.push(Point::new(center.x() + radius * a.cos(), center.y() + radius * a.sin()));
+        }
+    }
+    out.push(points[n - 1]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anchor_prefers_edge_on_tie() {
+        // The point is 5 mm from both the hole centre and the edge; the hole is listed first
+        let references = vec![
+            ReferenceGeometry::Hole { name: "h".into(), x: 10.0, y: -10.0 },
+            ReferenceGeometry::Edge { name: "e".into(), start: [0.0, 0.0], end: [20.0, 0.0] },
+        ];
+        match anchor_point([10.0, -5.0], &references) {
+            Some(AnchoredPoint::Edge { reference, .. }) => assert_eq!(reference, "e"),
+            other => panic!("expected the edge, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Contact (glue) area between adjacent layers after both layers' cutouts are removed.
+use crate::export::{ExportRequest, discretize_path_closed, shape_to_polygon};
+use crate::messages::{Message, MessageCode};
+use csgrs::sketch::Sketch;
+use csgrs::traits::CSG;
+use geo::{Area, Polygon};
+use serde::Serialize;
+
+/// Contact between two adjacent layers of the stack.
+#[derive(Debug, Serialize, Clone)]
+pub struct GlueInterface {
+    /// Index into the request's layers (bottom first)
+    pub lower: usize,
+    /// Index of the layer above, `lower + 1`
+    pub upper: usize,
+    /// mm^2 of material present on both faces
+    pub contact_area: f64,
+    /// Top face of the lower layer
+    pub lower_face_area: f64,
+    /// Bottom face of the upper layer
+    pub upper_face_area: f64,
+    /// Contact area is under the requested minimum
+    pub below_threshold: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Face {
+    Top,
+    Bottom,
+}
+
+/// Material left on one face of a layer: the outline minus every cut that breaks through
+/// that face. Laser-cut layers and cuts at least as deep as the layer go through both faces;
+/// pockets only open the face they are cut from.
+fn face_material(request: &ExportRequest, face: Face) -> Sketch<()> {
+    let to_sketch = |p: Polygon<f64>| Sketch::<()>::from_geo(geo::Geometry::Polygon(p).into(), None);
+    let cut_face = if request.cut_direction == "Bottom" { Face::Bottom } else { Face::Top };
+    let through_all = request.machining_type == "Cut";
+
+    let mut material = to_sketch(Polygon::new(discretize_path_closed(&request.outline), vec![]));
+    for shape in &request.shapes {
+        if shape.depth <= 1e-6 { continue; }
+        let through = through_all || shape.depth >= request.layer_thickness - 1e-6;
+        if !through && face != cut_face { continue; }
+        if let Some(poly) = shape_to_polygon(shape) {
+            material = material.difference(&to_sketch(poly));
+        }
+    }
+    material
+}
+
+fn sketch_area(sketch: &Sketch<()>) -> f64 {
+    sketch.geometry.iter().map(|g| match g {
+        geo::Geometry::Polygon(p) => p.unsigned_area(),
+        geo::Geometry::MultiPolygon(mp) => mp.unsigned_area(),
+        _ => 0.0,
+    }).sum()
+}
+
+/// For each adjacent pair in `layers` (ordered bottom to top), intersects the lower layer's
+/// top face with the upper layer's bottom face. Interfaces with less than `min_area` of
+/// contact are flagged.
+pub fn glue_area_report(layers: &[ExportRequest], min_area: f64) -> Result<Vec<GlueInterface>, Message> {
+    if let Some(i) = layers.iter().position(|l| l.outline.is_empty()) {
+        return Err(Message::new(MessageCode::OutlineMissing, format!("Layer {} has no outline", i)).with("layer", i));
+    }
+
+    let mut report = Vec::new();
+    for (lower, pair) in layers.windows(2).enumerate() {
+        let top = face_material(&pair[0], Face::Top);
+        let bottom = face_material(&pair[1], Face::Bottom);
+        let contact_area = sketch_area(&top.intersection(&bottom));
+
+        report.push(GlueInterface {
+            lower,
+            upper: lower + 1,
+            contact_area,
+            lower_face_area: sketch_area(&top),
+            upper_face_area: sketch_area(&bottom),
+            below_threshold: contact_area < min_area,
+        });
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use serde_json::json;
+
+    /// 100 x 40 x 5 mm layer with one square cut of `size` centred at (x, 20).
+    fn layer(machining_type: &str, cut_direction: &str, x: f64, size: f64, depth: f64) -> ExportRequest {
+        let corner = |x: f64, y: f64| json!({ "x": x, "y": y, "handle_in": null, "handle_out": null });
+        let square = json!({
+            "shape_type": "rect", "x": x, "y": 20.0, "width": size, "height": size, "diameter": null,
+            "angle": null, "corner_radius": null, "thickness": null, "points": null, "depth": depth,
+            "endmill_radius": null, "feed": null, "speed": null, "power": null,
+        });
+        serde_json::from_value(json!({
+            "filepath": "", "file_type": "SVG", "machining_type": machining_type, "cut_direction": cut_direction,
+            "outline": [corner(0.0, 0.0), corner(100.0, 0.0), corner(100.0, 40.0), corner(0.0, 40.0)],
+            "shapes": [square], "layer_thickness": 5.0, "stl_content": null,
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_pockets_only_open_their_own_face() {
+        let layers = [
+            layer("Carved/Printed", "Top", 30.0, 20.0, 2.0),    // Top face loses 400
+            layer("Carved/Printed", "Bottom", 80.0, 10.0, 2.0), // Bottom face loses 100
+            layer("Cut", "Top", 50.0, 30.0, 5.0),               // Both faces lose 900
+        ];
+        let report = glue_area_report(&layers, 3200.0).unwrap();
+        assert_eq!(report.len(), 2);
+
+        assert_relative_eq!(report[0].lower_face_area, 3600.0, epsilon = 1e-6);
+        assert_relative_eq!(report[0].upper_face_area, 3900.0, epsilon = 1e-6);
+        // The two pockets do not overlap, so both come off the contact
+        assert_relative_eq!(report[0].contact_area, 3500.0, epsilon = 1e-6);
+        assert!(!report[0].below_threshold);
+
+        assert_relative_eq!(report[1].lower_face_area, 4000.0, epsilon = 1e-6);
+        assert_relative_eq!(report[1].contact_area, 3100.0, epsilon = 1e-6);
+        assert!(report[1].below_threshold);
+    }
+
+    #[test]
+    fn test_deep_pocket_goes_through_both_faces() {
+        // Cut from the top of the upper layer, but as deep as the layer, so its bottom face opens too
+        let layers = [layer("Cut", "Top", 50.0, 1.0, 0.0), layer("Carved/Printed", "Top", 50.0, 20.0, 5.0)];
+        let report = glue_area_report(&layers, 0.0).unwrap();
+        assert_relative_eq!(report[0].upper_face_area, 3600.0, epsilon = 1e-6);
+        assert_relative_eq!(report[0].contact_area, 3600.0, epsilon = 1e-6);
+    }
+}
@@ -0,0 +1,62 @@
+//! Converts a layer's resolved shapes into optimizer obstacles.
+use crate::geometry::Obstacle;
+use crate::export::{ExportShape, shape_to_polygon};
+use csgrs::sketch::Sketch;
+use serde::Deserialize;
+
+/// Clearance (mm) added around each feature class.
+#[derive(Debug, Deserialize, Clone)]
+pub struct KeepOutMargins {
+    /// Through cuts
+    pub hole: f64,
+    /// Partial-depth cuts
+    pub pocket: f64,
+    /// "line" shapes
+    pub wire_guide: f64,
+}
+
+/// Maps every shape to one or more obstacles grown by its class margin.
+/// Circles stay circles (the optimizer treats them as hard keep-outs for the whole cut);
+/// everything else becomes a polygon buffered with rounded corners.
+pub fn extract_keepouts(shapes: &[ExportShape], layer_thickness: f64, margins: &KeepOutMargins) -> Vec<Obstacle> {
+    let mut obstacles = Vec::new();
+
+    for shape in shapes {
+        let margin = if shape.shape_type == "line" {
+            margins.wire_guide
+        } else if shape.depth >= layer_thickness - 1e-6 {
+            margins.hole
+        } else {
+            margins.pocket
+        }.max(0.0);
+
+        if shape.shape_type == "circle" {
+            let r = shape.diameter.unwrap_or(0.0) / 2.0;
+            if r > 1e-6 {
+                obstacles.push(Obstacle::Circle { x: shape.x, y: shape.y, r: r + margin });
+            }
+            continue;
+        }
+
+        let Some(poly) = shape_to_polygon(shape) else { continue };
+        let sketch = Sketch::<()>::from_geo(geo::Geometry::Polygon(poly).into(), None);
+        let grown = if margin > 1e-6 { sketch.offset_rounded(margin) } else { sketch };
+
+        for geom in grown.geometry {
+            let polys = match geom {
+                geo::Geometry::Polygon(p) => vec![p],
+                geo::Geometry::MultiPolygon(mp) => mp.0,
+                _ => vec![],
+            };
+            for p in polys {
+                // Interiors are irrelevant for a keep-out, only the outer boundary matters
+                let points: Vec<[f64; 2]> = p.exterior().0.iter().map(|c| [c.x, c.y]).collect();
+                if points.len() >= 3 {
+                    obstacles.push(Obstacle::Poly { points });
+                }
+            }
+        }
+    }
+
+    obstacles
+}
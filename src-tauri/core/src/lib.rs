@@ -0,0 +1,59 @@
+//! ShortStack's fabrication core: everything the app does to a design that does not need a
+//! window. It has no Tauri dependency, so other Rust tools can drive it directly; the app's
+//! commands are thin wrappers around these modules.
+//!
+//! - [`export`]: export requests (one board layer and its shapes), shape polygons, depth
+//!   regions, and the SVG/DXF writers. [`export_stream`] streams files with progress
+//!   reports, [`export_verify`] and [`depth_map_verify`] read them back.
+//! - [`geometry`] and [`optimizer`]: the smart-split optimizer; [`optimization_store`]
+//!   keeps its inputs for replay and [`split_export`] turns accepted cuts into parts.
+//! - [`fem`]: .geo generation, Gmsh and TetGen meshing, quadratic tets and units.
+//! - Layer analyses: [`thin_webs`], [`mesh_sizing`], [`glue_area`], [`balance`],
+//!   [`tool_reach`], [`scallop`], [`keepout`].
+//! - Shop-floor helpers: [`calibration`] grids, [`probe_fit`], image [`trace`].
+//! - [`artifacts`]: the content-hash index of files the app has written.
+//! - [`messages`]: coded, parameterized user-facing errors and progress.
+//! - [`session`]: recording of invoked commands and their replay outside the app.
+//!
+//! The default `native` feature brings in the optimizer, distance fields, TetGen and the
+//! artifact index. Without it the geometry and export modules build for `wasm32`, and the
+//! `wasm` feature exposes them to the frontend's preview (see `wasm`). The `python` feature
+//! builds the FEM kernels as a Python module with maturin (see `python`).
+//!
+//! Lengths are millimetres throughout unless a type says otherwise.
+#![warn(missing_docs)]
+pub mod artifacts;
+pub mod balance;
+pub mod calibration;
+pub mod cut_groups;
+pub mod depth_map_verify;
+pub mod export;
+pub mod export_stream;
+pub mod export_verify;
+pub mod fem;
+pub mod geometry;
+pub mod glue_area;
+pub mod keepout;
+pub mod mesh_sizing;
+pub mod messages;
+#[cfg(feature = "native")]
+pub mod optimization_store;
+#[cfg(feature = "native")]
+pub mod optimizer;
+pub mod overlap_groups;
+pub mod probe_fit;
+pub mod scallop;
+#[cfg(feature = "native")]
+pub mod sdf;
+#[cfg(feature = "native")]
+pub mod session;
+pub mod split_export;
+pub mod thin_webs;
+pub mod tool_reach;
+pub mod trace;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+pub use export::{ExportPoint, ExportRequest, ExportShape};
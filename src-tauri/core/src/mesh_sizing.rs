@@ -0,0 +1,150 @@
+//! Suggests Gmsh characteristic lengths from a layer's resolved geometry.
+use crate::fem::gmsh_interop::estimate_mesh_memory;
+use crate::thin_webs::web_candidates;
+use crate::export::{ExportRequest, discretize_path_closed, export_request_layer, shape_to_polygon};
+use crate::messages::Message;
+use geo::{Area, BoundingRect, Euclidean, Length, Polygon};
+use serde::Serialize;
+
+/// Elements across the thinnest feature (quadratic tets resolve bending with two).
+const ELEMENTS_ACROSS_FEATURE: f64 = 2.0;
+/// Segments used to approximate the smallest hole's circumference.
+const SEGMENTS_PER_HOLE: f64 = 12.0;
+// Gmsh typically produces ~1.5x the ideal count of regular tets; Tet10 has ~1.4 nodes per element.
+const ELEMENT_OVERHEAD: f64 = 1.5;
+const NODES_PER_ELEMENT: f64 = 1.4;
+
+/// Suggested Gmsh element size range for a layer, with the features that set it.
+#[derive(Debug, Serialize, Clone)]
+pub struct MeshSizeSuggestion {
+    /// Smallest element size (mm)
+    pub min_size: f64,
+    /// Largest element size (mm)
+    pub max_size: f64,
+    /// Diameter (or hydraulic diameter) of the smallest through cut
+    pub smallest_hole: Option<f64>,
+    /// Narrowest material between cuts, the outline or pocket floors, when it limits min_size
+    pub thinnest_web: Option<f64>,
+    /// Layer thickness (mm)
+    pub layer_thickness: f64,
+    /// Rough Tet10 count at these sizes
+    pub estimated_elements: usize,
+    /// Rough solver memory for that many elements
+    pub estimated_memory_mb: f64,
+}
+
+/// Picks `min_size` so the smallest hole and thinnest web are resolved, and `max_size`
+/// from the layer thickness and overall board size. The element estimate assumes a
+/// graded mesh averaging the geometric mean of the two sizes.
+/// Webs are only searched up to the width that could still shrink `min_size`, and the
+/// volume comes from the unioned cuts the mesher sees, so overlapping cuts count once.
+pub fn suggest_mesh_size(request: &ExportRequest) -> Result<MeshSizeSuggestion, Message> {
+    if request.outline.is_empty() {
+        return Err(Message::outline_missing());
+    }
+    let thickness = request.layer_thickness;
+    if thickness <= 0.0 {
+        return Err(Message::not_positive("layer_thickness", "Layer thickness must be positive"));
+    }
+
+    let board = Polygon::new(discretize_path_closed(&request.outline), vec![]);
+    let rect = board.bounding_rect().ok_or_else(Message::outline_degenerate)?;
+    let board_extent = rect.width().max(rect.height());
+
+    let mut smallest_hole: Option<f64> = None;
+    let mut thinnest_web: Option<f64> = None;
+    let mut cuts: Vec<(usize, Polygon<f64>)> = Vec::new();
+
+    for (i, shape) in request.shapes.iter().enumerate() {
+        let Some(poly) = shape_to_polygon(shape) else { continue };
+        let depth = shape.depth.min(thickness);
+
+        if depth >= thickness - 1e-6 {
+            let diameter = match shape.shape_type.as_str() {
+                "circle" => shape.diameter.unwrap_or(0.0),
+                _ => hydraulic_diameter(&poly),
+            };
+            if diameter > 1e-6 {
+                smallest_hole = Some(smallest_hole.map_or(diameter, |d| d.min(diameter)));
+            }
+        } else if depth > 1e-6 {
+            // Material left under a pocket
+            let floor = thickness - depth;
+            thinnest_web = Some(thinnest_web.map_or(floor, |w| w.min(floor)));
+        }
+        cuts.push((i, poly));
+    }
+
+    let mut min_size = thickness / ELEMENTS_ACROSS_FEATURE;
+    if let Some(d) = smallest_hole {
+        min_size = min_size.min(std::f64::consts::PI * d / SEGMENTS_PER_HOLE);
+    }
+    if let Some(w) = thinnest_web {
+        min_size = min_size.min(w / ELEMENTS_ACROSS_FEATURE);
+    }
+
+    // Only webs narrower than this can lower min_size further
+    let narrowest_gap = web_candidates(&board, &cuts, min_size * ELEMENTS_ACROSS_FEATURE).into_iter()
+        .map(|c| c.width)
+        .reduce(f64::min);
+    if let Some(web) = narrowest_gap {
+        thinnest_web = Some(thinnest_web.map_or(web, |w| w.min(web)));
+        min_size = min_size.min(web / ELEMENTS_ACROSS_FEATURE);
+    }
+    let max_size = (thickness * 2.0).min(board_extent / 10.0).max(min_size);
+
+    let removed: f64 = export_request_layer(request)?.cuts.iter()
+        .map(|c| c.polygon().unsigned_area() * c.depth.min(thickness))
+        .sum();
+    let volume = board.unsigned_area() * thickness - removed;
+
+    let h = (min_size * max_size).sqrt();
+    let tet_volume = h.powi(3) / (6.0 * std::f64::consts::SQRT_2);
+    let estimated_elements = (volume.max(0.0) / tet_volume * ELEMENT_OVERHEAD).ceil() as usize;
+    let estimated_nodes = (estimated_elements as f64 * NODES_PER_ELEMENT) as usize;
+    // ~60 bytes per node line and ~70 per element line in a 2.2 .msh file
+    let file_bytes = (estimated_nodes * 60 + estimated_elements * 70) as u64;
+    let estimated_memory_mb = estimate_mesh_memory(estimated_nodes, estimated_elements, file_bytes) as f64 / (1024.0 * 1024.0);
+
+    Ok(MeshSizeSuggestion {
+        min_size,
+        max_size,
+        smallest_hole,
+        thinnest_web,
+        layer_thickness: thickness,
+        estimated_elements,
+        estimated_memory_mb,
+    })
+}
+
+/// 4A/P: the diameter for circles, the short side for long slots.
+fn hydraulic_diameter(poly: &Polygon<f64>) -> f64 {
+    let perimeter = poly.exterior().length::<Euclidean>();
+    if perimeter > 0.0 { 4.0 * poly.unsigned_area() / perimeter } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(rects: usize) -> ExportRequest {
+        let corner = |x: f64, y: f64| serde_json::json!({ "x": x, "y": y, "handle_in": null, "handle_out": null });
+        let rect = serde_json::json!({
+            "shape_type": "rect", "x": 50.0, "y": 50.0, "width": 10.0, "height": 10.0, "diameter": null,
+            "angle": null, "corner_radius": null, "thickness": null, "points": null, "depth": 2.0,
+            "endmill_radius": null, "feed": null, "speed": null, "power": null,
+        });
+        serde_json::from_value(serde_json::json!({
+            "filepath": "", "file_type": "STEP", "machining_type": "Cut", "cut_direction": "Top",
+            "outline": [corner(0.0, 0.0), corner(100.0, 0.0), corner(100.0, 100.0), corner(0.0, 100.0)],
+            "shapes": vec![rect; rects], "layer_thickness": 2.0, "stl_content": null,
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_overlapping_cuts_removed_once() {
+        let single = suggest_mesh_size(&request(1)).unwrap();
+        let doubled = suggest_mesh_size(&request(2)).unwrap();
+        assert_eq!(single.estimated_elements, doubled.estimated_elements);
+    }
+}
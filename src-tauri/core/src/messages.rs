@@ -0,0 +1,206 @@
+//! User-facing messages as stable codes plus parameters.
+//!
+//! Errors and progress that reach the UI carry a `MessageCode` (serialized as e.g.
+//! "MESH_TOO_LARGE") and the values the text needs, so the frontend can localize and style
+//! them and tests can match on the code. `message` is the English rendering, kept for logs
+//! and for codes the frontend has no translation for.
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+
+/// Stable identifier of a user-facing message; the frontend keys its texts on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MessageCode {
+    // Meshing progress (`meshing-progress` event)
+    /// Writing the .geo script.
+    MeshingWriteGeo,
+    /// Gmsh is running.
+    MeshingRunGmsh,
+    /// Reading the .msh Gmsh wrote.
+    MeshingReadMesh,
+    /// Archiving the run's inputs and outputs.
+    MeshingArchive,
+    /// The app data directory (meshing runs, stored optimizations, sessions) is unavailable
+    AppDataUnavailable,
+    // Meshing errors
+    /// The .geo script could not be written (`path`, `error`).
+    GeoWriteFailed,
+    /// The Gmsh executable could not be started (`error`).
+    GmshLaunchFailed,
+    /// Gmsh exited with an error (`stderr`).
+    GmshFailed,
+    /// The .msh file could not be read (`error`).
+    MshReadFailed,
+    /// The .msh file ended early.
+    MshTruncated,
+    /// The mesh would exceed the memory budget (`needed_mb`, `budget_mb`, `suggested_size`, `mesh_size`).
+    MeshTooLarge,
+    /// An element has a negative Jacobian (`element`, `jacobian_ratio`).
+    ElementInverted,
+    /// An element's Jacobian cannot be inverted (`element`).
+    ElementSingular,
+    // Surface repair and TetGen
+    /// The TetGen switches could not be passed on (`options`).
+    InvalidMeshOptions,
+    /// TetGen produced no mesh.
+    TetgenFailed,
+    /// TetGen produced more elements than it is allowed to (`tetrahedra`).
+    TetgenTooManyElements,
+    /// The meshing thread crashed.
+    MeshingPanicked,
+    // Mesh import and partition export
+    /// A mesh was imported (`elements`).
+    MeshImported,
+    /// Imported elements failed the Jacobian check (`count`).
+    MeshQualityFailed,
+    /// The mesh has no elements.
+    MeshEmpty,
+    /// An export path has no parent folder or file name (`path`).
+    InvalidExportPath,
+    /// A file could not be read (`path`, `error`).
+    ReadFailed,
+    /// A file could not be written (`path`, `error`).
+    WriteFailed,
+    /// The requested file format is not supported (`format`).
+    UnsupportedFormat,
+    /// The shapes could not be unioned (`error`).
+    BooleanUnionFailed,
+    /// An export stream id is not open (`id`).
+    ExportStreamUnknown,
+    /// An STL export arrived without the mesh to write.
+    StlContentMissing,
+    // Request validation shared by the analysis commands
+    /// The request has no board outline.
+    OutlineMissing,
+    /// The board outline has no area.
+    OutlineDegenerate,
+    /// The board outline has fewer points than needed (`min_points`).
+    OutlineTooFewPoints,
+    /// A numeric input must be positive (`name`).
+    ValueNotPositive,
+    // Export sandbox (`PermissionError` codes in the app)
+    /// A path could not be normalized (`path`, `error`).
+    InvalidPath,
+    /// A path is outside the folders approved for the project (`path`).
+    PathNotApproved,
+    // Smart split
+    /// The split kerf is not positive (`kerf`).
+    SplitKerfNotPositive,
+    /// The optimization result has no cuts.
+    SplitNoCuts,
+    /// A cut does not cross the board or any part split off so far (`cut`).
+    SplitMissesBoard,
+    /// The optimizer thread crashed.
+    OptimizationPanicked,
+    /// A stored run id is malformed (`id`).
+    OptimizationRunInvalid,
+    /// A stored run's input does not match its hash (`id`).
+    OptimizationRunCorrupt,
+    /// The debug split evaluation crashed.
+    EvalPanicked,
+    // Analysis and calibration
+    /// Image tracing found no part.
+    TraceNoPart,
+    /// A depth map's aspect ratio differs from the board's (`image_aspect`, `board_aspect`).
+    DepthMapAspectMismatch,
+    /// The tool library is empty.
+    ToolLibraryEmpty,
+    /// A tool has an unknown kind or no diameter (`tool`).
+    InvalidTool,
+    /// Scallop estimation was asked for a layer that is not carved.
+    ScallopNeedsCarvedLayer,
+    /// The balance request has no layers.
+    BalanceNoLayers,
+    /// A balance configuration weighs nothing (`configuration`).
+    BalanceNoMass,
+    /// Fewer probe points than unknowns (`needed`).
+    ProbeTooFewPoints,
+    /// The probe points cannot fix the board's position.
+    ProbeUnderconstrained,
+    // Names of `ReportQuantity` rows
+    /// Mesh volume.
+    ReportVolume,
+    /// Mesh surface area.
+    ReportSurfaceArea,
+    /// Young's modulus of the material.
+    ReportYoungsModulus,
+    /// Poisson's ratio of the material.
+    ReportPoissonsRatio,
+    /// Sum of the applied loads.
+    ReportTotalLoad,
+    // Session recording and replay
+    /// The replay thread crashed.
+    ReplayPanicked,
+    /// A replayed layer or cut id contains control characters (`id`).
+    ReplayInvalidId,
+}
+
+/// A coded message with its parameters and English text.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Message {
+    /// What happened, for the frontend's lookup.
+    pub code: MessageCode,
+    /// Values substituted into the frontend's text.
+    pub params: Map<String, Value>,
+    /// English fallback
+    pub message: String,
+}
+
+impl Message {
+    /// Message with no parameters; add them with `with`.
+    pub fn new(code: MessageCode, message: impl Into<String>) -> Self {
+        Self { code, params: Map::new(), message: message.into() }
+    }
+
+    /// Adds a parameter the frontend substitutes into its translation.
+    pub fn with(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.params.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Numeric parameter `key`, if present.
+    pub fn param_f64(&self, key: &str) -> Option<f64> {
+        self.params.get(key).and_then(Value::as_f64)
+    }
+
+    /// A file the command had to read could not be opened or parsed.
+    pub fn read_failed(path: impl AsRef<Path>, e: impl fmt::Display) -> Self {
+        let path = path.as_ref();
+        Message::new(MessageCode::ReadFailed, format!("Failed to read {}: {}", path.display(), e))
+            .with("path", path.to_string_lossy())
+            .with("error", e.to_string())
+    }
+
+    /// A file the command had to write could not be created or written.
+    pub fn write_failed(path: impl AsRef<Path>, e: impl fmt::Display) -> Self {
+        let path = path.as_ref();
+        Message::new(MessageCode::WriteFailed, format!("Failed to write {}: {}", path.display(), e))
+            .with("path", path.to_string_lossy())
+            .with("error", e.to_string())
+    }
+
+    /// A numeric input that must be > 0; `name` is the request field, e.g. "layer_thickness".
+    pub fn not_positive(name: &str, message: impl Into<String>) -> Self {
+        Message::new(MessageCode::ValueNotPositive, message).with("name", name)
+    }
+
+    /// The request has no board outline.
+    pub fn outline_missing() -> Self {
+        Message::new(MessageCode::OutlineMissing, "Board outline is missing")
+    }
+
+    /// The board outline encloses no area.
+    pub fn outline_degenerate() -> Self {
+        Message::new(MessageCode::OutlineDegenerate, "Board outline is degenerate")
+    }
+}
+
+impl fmt::Display for Message {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Message {}
@@ -0,0 +1,154 @@
+//! Keeps the exact optimizer input behind every smart-split result so a run can be replayed
+//! and debugged after the project has been edited.
+//!
+//! Inputs are content-addressed (`inputs/<hash>.json`, shared by identical runs); each run
+//! gets `runs/<id>.json` holding the input hash and the result it produced.
+use crate::artifacts::project_hash;
+use crate::geometry::{GeometryInput, OptimizationResult};
+use crate::messages::{Message, MessageCode};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// One saved optimizer run, `runs/<id>.json`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoredRun {
+    /// Random UUID, returned to the frontend as `OptimizationResult::run_id`
+    pub id: String,
+    /// Names the input file, `inputs/<hash>.json`
+    pub input_hash: String,
+    /// Unix seconds
+    pub created_at: u64,
+    /// Seed the run was optimized with; replaying with it reproduces `result`
+    pub seed: u64,
+    /// What the optimizer returned
+    pub result: OptimizationResult,
+}
+
+/// The stored run next to a fresh run on the same input and seed. Unless the optimizer has
+/// changed since, the two agree; `cost_delta` (replayed - original) shows whether it got worse.
+#[derive(Debug, Serialize)]
+pub struct ReplayReport {
+    /// The replayed run
+    pub id: String,
+    /// The stored input both runs used
+    pub input: GeometryInput,
+    /// The stored result
+    pub original: OptimizationResult,
+    /// The fresh result
+    pub replayed: OptimizationResult,
+    /// `replayed.cost - original.cost`
+    pub cost_delta: f64,
+}
+
+/// Saved optimizer inputs and results under the app data directory.
+pub struct OptimizationStore {
+    dir: Option<PathBuf>,
+}
+
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), Message> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| Message::write_failed(parent, e))?;
+    }
+    let json = serde_json::to_vec(value).map_err(|e| Message::write_failed(path, e))?;
+    std::fs::write(path, json).map_err(|e| Message::write_failed(path, e))
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, Message> {
+    let bytes = std::fs::read(path).map_err(|e| Message::read_failed(path, e))?;
+    serde_json::from_slice(&bytes).map_err(|e| Message::read_failed(path, e))
+}
+
+impl OptimizationStore {
+    /// Without a directory nothing is persisted and replay is unavailable.
+    pub fn open(dir: Option<PathBuf>) -> Self {
+        Self { dir }
+    }
+
+    fn dir(&self) -> Result<&Path, Message> {
+        self.dir.as_deref()
+            .ok_or_else(|| Message::new(MessageCode::AppDataUnavailable, "No app data directory for optimization runs"))
+    }
+
+    /// Saves `input` (once per distinct input) and `result`, optimized with `seed`, as a new
+    /// run; returns the run file and the stored run.
+    pub fn save(&self, input: &GeometryInput, seed: u64, result: &OptimizationResult) -> Result<(PathBuf, StoredRun), Message> {
+        let dir = self.dir()?;
+        let input_hash = project_hash(input);
+        let input_path = dir.join("inputs").join(format!("{}.json", input_hash));
+        if !input_path.exists() {
+            write_json(&input_path, input)?;
+        }
+
+        let run = StoredRun {
+            id: uuid::Uuid::new_v4().to_string(),
+            input_hash,
+            created_at: SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0),
+            seed,
+            result: OptimizationResult { run_id: None, ..result.clone() },
+        };
+        let run_path = dir.join("runs").join(format!("{}.json", run.id));
+        write_json(&run_path, &run)?;
+        Ok((run_path, run))
+    }
+
+    /// Loads a stored run and the exact input it was computed from.
+    pub fn load(&self, id: &str) -> Result<(GeometryInput, StoredRun), Message> {
+        // Ids are uuids; refuse anything that could name a path outside the store
+        if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(Message::new(MessageCode::OptimizationRunInvalid, format!("Invalid run id '{}'", id)).with("id", id));
+        }
+        let dir = self.dir()?;
+        let run: StoredRun = read_json(&dir.join("runs").join(format!("{}.json", id)))?;
+        let input: GeometryInput = read_json(&dir.join("inputs").join(format!("{}.json", run.input_hash)))?;
+        if project_hash(&input) != run.input_hash {
+            return Err(Message::new(MessageCode::OptimizationRunCorrupt, format!("Stored input for run {} does not match its hash", id))
+                .with("id", id));
+        }
+        Ok((input, run))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Obstacle;
+    use crate::optimizer::run_optimization;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn test_replay_reproduces_stored_cost() {
+        let dir = std::env::temp_dir().join(format!("shortstack_runs_{}", std::process::id()));
+        let store = OptimizationStore::open(Some(dir.clone()));
+        let input = GeometryInput {
+            outline: vec![[0.0, 0.0], [200.0, 0.0], [200.0, 100.0], [0.0, 100.0]],
+            obstacles: vec![Obstacle::Circle { x: 100.0, y: 50.0, r: 8.0 }],
+            bed_width: 150.0,
+            bed_height: 150.0,
+            initial_line: Some([[100.0, -10.0], [100.0, 110.0]]),
+            references: None,
+            tolerance: None,
+            sdf_resolution: None,
+            fillet_radius: None,
+            joint_clearance: None,
+        };
+
+        let seed = 0xfeed;
+        let original = run_optimization(input.clone(), seed);
+        let (_, saved) = store.save(&input, seed, &original).unwrap();
+        let (stored_input, run) = store.load(&saved.id).unwrap();
+        assert_eq!(run.seed, seed);
+
+        // Same search; only the JSON round trip of the stored result can cost a last bit
+        let replayed = run_optimization(stored_input, run.seed);
+        assert_relative_eq!(replayed.cost, run.result.cost, max_relative = 1e-12);
+        assert_eq!(replayed.shapes.len(), run.result.shapes.len());
+        for (a, b) in replayed.shapes.iter().zip(&run.result.shapes) {
+            for (p, q) in [(a.start, b.start), (a.end, b.end)] {
+                assert_relative_eq!(p[0], q[0], max_relative = 1e-12);
+                assert_relative_eq!(p[1], q[1], max_relative = 1e-12);
+            }
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -1,24 +1,38 @@
+//! Smart-split optimizer: CMA-ES over the cut line and dovetail parameters against collision,
+//! bed-fit and machinability costs.
 use crate::geometry::*;
+use crate::sdf::DistanceField;
+use std::sync::Arc;
 use cmaes::{CMAESOptions, DVector};
 use geo::{Point, LineString, Polygon, Euclidean, Distance};
 use std::f64::consts::PI;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
 
 const OBS_MARGIN: f64 = 2.0;
 const MIN_W: f64 = 5.0;
 const MAX_W: f64 = 25.0;
 const MIN_H: f64 = 4.0;
 const MAX_H: f64 = 12.0;
+const DEFAULT_TOLERANCE_SAMPLES: usize = 200;
+const SENSOR_RANGE: f64 = 4.0; // mm
 
 struct DovetailShape { 
     t: f64, 
     w: f64, 
     h: f64, 
+    fillet: f64, // Corner radius, already clamped to what fits this w/h
 }
 
+/// Cost breakdown of the input's initial line, for the split debugger overlay.
 #[derive(serde::Serialize)]
 pub struct DebugEvalResult {
     log: String,
     cost: f64,
+    // Overlay data: dovetail trapezoid (base_l, head_l, head_r, base_r) and the
+    // clearance to each input obstacle, in input order (negative = collision)
+    dovetail_polygon: Vec<[f64; 2]>,
+    obstacle_sdf: Vec<f64>,
 }
 
 #[derive(Clone)]
@@ -32,6 +46,11 @@ struct CostContext {
     // Inductive Bias: Target normalized Angle/Offset from PSO
     target_angle: Option<f64>,
     target_offset: Option<f64>,
+    // Sampled replacement for the exact circle checks (large obstacle counts)
+    sdf: Option<Arc<DistanceField>>,
+    // Machinability: requested dovetail corner radius and tail/socket clearance
+    fillet_radius: f64,
+    joint_clearance: f64,
 }
 
 fn line_to_params(start: [f64; 2], end: [f64; 2], ctx: &CostContext) -> (f64, f64, f64) {
@@ -92,7 +111,9 @@ fn line_to_params(start: [f64; 2], end: [f64; 2], ctx: &CostContext) -> (f64, f6
     (angle_norm.clamp(0.0, 1.0), offset_norm.clamp(0.0, 1.0), t_seed.clamp(0.0, 1.0))
 }
 
-pub fn run_optimization(input: GeometryInput) -> OptimizationResult {
+/// Searches for the cut line and dovetail with the lowest cost for `input`.
+/// CMA-ES sampling is driven by `seed`, so the same input and seed give the same result.
+pub fn run_optimization(input: GeometryInput, seed: u64) -> OptimizationResult {
     // Convert Input to Geo Types & Precompute center
     let poly_points: Vec<Point<f64>> = input.outline.iter().map(|p| Point::new(p[0], p[1])).collect();
     
@@ -116,8 +137,22 @@ pub fn run_optimization(input: GeometryInput) -> OptimizationResult {
         radius,
         target_angle: None,
         target_offset: None,
+        sdf: None,
+        fillet_radius: input.fillet_radius.unwrap_or(0.0),
+        joint_clearance: input.joint_clearance.unwrap_or(0.0),
     };
 
+    if let Some(cell) = input.sdf_resolution.filter(|c| *c > 0.0) {
+        // Pad so the dovetail head and proximity sensor stay inside the grid
+        let pad = MAX_W * 0.75 + MAX_H + SENSOR_RANGE;
+        ctx.sdf = Some(Arc::new(DistanceField::build(
+            &ctx.obstacles,
+            [min_x - pad, min_y - pad],
+            [max_x + pad, max_y + pad],
+            cell,
+        )));
+    }
+
     let mut seeds = Vec::new();
 
     if let Some(line) = input.initial_line {
@@ -150,10 +185,10 @@ pub fn run_optimization(input: GeometryInput) -> OptimizationResult {
     }
 
     let mut best_overall_cost = f64::MAX;
-    let mut best_overall_cut: Option<GeneratedCut> = None;
+    let mut best_overall_params: Option<(DVector<f64>, bool)> = None;
 
-    for flip_state in [false, true] {
-        for (seed_vec, run_sigma) in &seeds {
+    for (flip_index, flip_state) in [false, true].into_iter().enumerate() {
+        for (seed_index, (seed_vec, run_sigma)) in seeds.iter().enumerate() {
             
             // --- FAST CHECK & LOGGING ---
             let seed_dvec = DVector::from_vec(seed_vec.clone());
@@ -162,24 +197,9 @@ pub fn run_optimization(input: GeometryInput) -> OptimizationResult {
             
 
 
-            if seed_cost < 1.0 {
-                let (_, p1, p2, dt) = decode_params(&seed_dvec, &ctx);
-                
-                let cut = GeneratedCut {
-                    id: uuid::Uuid::new_v4().to_string(),
-                    start: [p1.x(), p1.y()],
-                    end: [p2.x(), p2.y()],
-                    dovetail_width: dt.w,
-                    dovetail_height: dt.h,
-                    dovetail_t: dt.t,
-                    flipped: flip_state,
-                };
-
-                return OptimizationResult {
-                    success: seed_cost < 1.0,
-                    cost: seed_cost,
-                    shapes: vec![cut],
-                };
+            if seed_cost < 1.0 && exact_cost(&seed_dvec, &ctx, flip_state) < 1.0 {
+                let cut = result_cut(&seed_dvec, flip_state, &ctx, input.references.as_deref(), input.tolerance.as_ref());
+                return result_with_cuts(seed_cost, vec![cut]);
             }
             // ----------------------------
 
@@ -190,26 +210,16 @@ pub fn run_optimization(input: GeometryInput) -> OptimizationResult {
                 .population_size(40)
                 .max_generations(250)
                 .enable_printing(2000) // Silent mostly
+                .seed(seed.wrapping_add((flip_index * seeds.len() + seed_index) as u64)) // Distinct stream per start
                 .build(move |x: &DVector<f64>| evaluate_cost(x, &ctx_clone, flip_state))
                 .unwrap();
 
             let result = cmaes_state.run();
 
-            if let Some(best) = result.overall_best {
-                if best.value < best_overall_cost {
-                    best_overall_cost = best.value;
-                    
-                    let (_, p1, p2, dt) = decode_params(&best.point, &ctx);
-                    best_overall_cut = Some(GeneratedCut {
-                        id: uuid::Uuid::new_v4().to_string(),
-                        start: [p1.x(), p1.y()],
-                        end: [p2.x(), p2.y()],
-                        dovetail_width: dt.w,
-                        dovetail_height: dt.h,
-                        dovetail_t: dt.t,
-                        flipped: flip_state,
-                    });
-                }
+            if let Some(best) = result.overall_best
+                && best.value < best_overall_cost {
+                best_overall_cost = best.value;
+                best_overall_params = Some((best.point.clone(), flip_state));
             }
             // Stopping Condition: If nearly zero, we found a valid, non-colliding, compliant fit.
             if best_overall_cost < 1.0 { break; }
@@ -217,16 +227,152 @@ pub fn run_optimization(input: GeometryInput) -> OptimizationResult {
         if best_overall_cost < 1.0 { break; }
     }
 
-    match best_overall_cut {
-        Some(cut) => OptimizationResult {
-            success: best_overall_cost < 1.0,
-            cost: best_overall_cost,
-            shapes: vec![cut],
-        },
+    // The field is an approximation; the reported cost must come from the exact check
+    if let Some((x, flipped)) = &best_overall_params
+        && ctx.sdf.is_some() {
+        best_overall_cost = exact_cost(x, &ctx, *flipped);
+    }
+
+    match best_overall_params {
+        Some((x, flipped)) => {
+            let cut = result_cut(&x, flipped, &ctx, input.references.as_deref(), input.tolerance.as_ref());
+            result_with_cuts(best_overall_cost, vec![cut])
+        }
         None => OptimizationResult { 
-            success: false, cost: f64::MAX, shapes: vec![],
+            success: false, cost: f64::MAX, shapes: vec![], robustness: None, run_id: None,
+        }
+    }
+}
+
+/// The cut that parameters `x` decode to, anchored to `references` and scored against
+/// `tolerance` when given.
+fn result_cut(
+    x: &DVector<f64>,
+    flipped: bool,
+    ctx: &CostContext,
+    references: Option<&[ReferenceGeometry]>,
+    tolerance: Option<&ToleranceSpec>,
+) -> GeneratedCut {
+    let (_, p1, p2, dt) = decode_params(x, ctx);
+    let cut = GeneratedCut {
+        id: uuid::Uuid::new_v4().to_string(),
+        start: [p1.x(), p1.y()],
+        end: [p2.x(), p2.y()],
+        dovetail_width: dt.w,
+        dovetail_height: dt.h,
+        dovetail_t: dt.t,
+        flipped,
+        anchors: None,
+        fillet_radius: dt.fillet,
+        joint_clearance: ctx.joint_clearance,
+        robustness: tolerance.map(|tol| robustness_score(x, flipped, ctx, tol)),
+    };
+    anchor_cut(cut, references)
+}
+
+/// Fresh seed for `run_optimization`, to be stored with the run so it can be replayed.
+pub fn random_seed() -> u64 {
+    rand::random()
+}
+
+/// Result for `cuts`; its robustness is that of the weakest cut.
+fn result_with_cuts(cost: f64, cuts: Vec<GeneratedCut>) -> OptimizationResult {
+    OptimizationResult {
+        success: cost < 1.0,
+        cost,
+        robustness: cuts.iter().filter_map(|c| c.robustness).reduce(f64::min),
+        shapes: cuts,
+        run_id: None,
+    }
+}
+
+/// Attaches parametric anchors to the cut endpoints when references were supplied,
+/// so the frontend can rebuild the cut after the board is moved or resized.
+fn anchor_cut(mut cut: GeneratedCut, references: Option<&[ReferenceGeometry]>) -> GeneratedCut {
+    if let Some(refs) = references
+        && let (Some(start), Some(end)) = (anchor_point(cut.start, refs), anchor_point(cut.end, refs)) {
+        cut.anchors = Some(CutAnchors { start, end });
+    }
+    cut
+}
+
+/// Monte Carlo check of a solved cut against manufacturing tolerances.
+/// Obstacles are shifted and the outline is stretched by uniform random amounts
+/// within `tol`, and we report the fraction of samples where the same parameters
+/// still give a collision-free, bed-fitting cut (cost < 1.0, same as `success`).
+/// Seeded deterministically so repeated runs on the same input agree.
+fn robustness_score(x: &DVector<f64>, flipped: bool, ctx: &CostContext, tol: &ToleranceSpec) -> f64 {
+    let samples = tol.samples.unwrap_or(DEFAULT_TOLERANCE_SAMPLES).max(1);
+    let mut rng = StdRng::seed_from_u64(0x5eed);
+    let pos_tol = tol.obstacle_position.abs();
+    let dim_tol = tol.outline_dimension.abs();
+
+    // Outline extents, used to turn a dimension error into a scale factor
+    let mut min_x = f64::MAX; let mut max_x = f64::MIN;
+    let mut min_y = f64::MAX; let mut max_y = f64::MIN;
+    for p in &ctx.outline {
+        min_x = min_x.min(p.x()); max_x = max_x.max(p.x());
+        min_y = min_y.min(p.y()); max_y = max_y.max(p.y());
+    }
+    let width = (max_x - min_x).max(1e-6);
+    let height = (max_y - min_y).max(1e-6);
+
+    let jitter = |range: f64, rng: &mut StdRng| if range > 0.0 { rng.gen_range(-range..=range) } else { 0.0 };
+
+    let mut passed = 0;
+    for _ in 0..samples {
+        let sx = (width + jitter(dim_tol, &mut rng)) / width;
+        let sy = (height + jitter(dim_tol, &mut rng)) / height;
+
+        // Keep center/radius so the same parameters decode to the same physical line, and the
+        // target bias so a sample passes by the same rule as `success`
+        let mut sample = ctx.clone();
+        sample.sdf = None; // Obstacles move, so the precomputed field no longer applies
+        sample.outline = ctx.outline.iter().map(|p| Point::new(
+            ctx.center.x() + (p.x() - ctx.center.x()) * sx,
+            ctx.center.y() + (p.y() - ctx.center.y()) * sy,
+        )).collect();
+        sample.obstacles = ctx.obstacles.iter().map(|obs| {
+            let dx = jitter(pos_tol, &mut rng);
+            let dy = jitter(pos_tol, &mut rng);
+            match obs {
+                Obstacle::Circle { x, y, r } => Obstacle::Circle { x: x + dx, y: y + dy, r: *r },
+                Obstacle::Poly { points } => Obstacle::Poly {
+                    points: points.iter().map(|p| [p[0] + dx, p[1] + dy]).collect(),
+                },
+            }
+        }).collect();
+
+        if evaluate_cost(x, &sample, flipped) < 1.0 {
+            passed += 1;
         }
     }
+
+    passed as f64 / samples as f64
+}
+
+/// Cost with exact per-obstacle distances, bypassing any sampled distance field.
+fn exact_cost(x: &DVector<f64>, ctx: &CostContext, flipped: bool) -> f64 {
+    if ctx.sdf.is_none() {
+        return evaluate_cost(x, ctx, flipped);
+    }
+    let mut exact = ctx.clone();
+    exact.sdf = None;
+    evaluate_cost(x, &exact, flipped)
+}
+
+/// Penalty for a circle clearance `sdf` (mm), split into (hard, soft) parts.
+fn clearance_penalty(sdf: f64) -> (f64, f64) {
+    if sdf < 0.0 {
+        (10000.0 + sdf.powi(2) * 500000.0, 0.0)
+    } else if sdf < OBS_MARGIN {
+        ((OBS_MARGIN - sdf).powi(2) * 5000.0, 0.0)
+    } else if sdf < SENSOR_RANGE {
+        let weight = (1.0 - sdf / SENSOR_RANGE).powi(2);
+        (0.0, weight * 0.1)
+    } else {
+        (0.0, 0.0)
+    }
 }
 
 fn decode_params(
@@ -264,7 +410,10 @@ fn decode_params(
     let w_val = MIN_W + safe_x[3] * (MAX_W - MIN_W);
     let h_val = MIN_H + safe_x[4] * (MAX_H - MIN_H);
 
-    (angle, p1, p2, DovetailShape { t: t_val, w: w_val, h: h_val })
+    // Corners share the short edges, so keep the radius well inside them
+    let fillet = ctx.fillet_radius.min(w_val * 0.25).min(h_val * 0.5).max(0.0);
+
+    (angle, p1, p2, DovetailShape { t: t_val, w: w_val, h: h_val, fillet })
 }
 
 // Wrapper for optimizer
@@ -316,40 +465,33 @@ fn evaluate_cost_detailed(x: &DVector<f64>, ctx: &CostContext, flipped: bool) ->
     let (vx, vy) = if flipped { (uy, -ux) } else { (-uy, ux) };
 
     // Geometry Generation
-    let center = Point::new(p1.x() + (p2.x() - p1.x()) * dt.t, p1.y() + (p2.y() - p1.y()) * dt.t);
-    let base_half = dt.w / 2.0;
-    let head_half = (dt.w * 1.5) / 2.0; 
-    let base_l = Point::new(center.x() - ux * base_half, center.y() - uy * base_half);
-    let base_r = Point::new(center.x() + ux * base_half, center.y() + uy * base_half);
-    let head_l = Point::new(center.x() - ux * head_half + vx * dt.h, center.y() - uy * head_half + vy * dt.h);
-    let head_r = Point::new(center.x() + ux * head_half + vx * dt.h, center.y() + uy * head_half + vy * dt.h);
+    let [_, base_l, head_l, head_r, base_r, _] = dovetail_points(p1, p2, (ux, uy), dt.w, dt.h, dt.t, flipped);
     let cut_path = vec![(p1, base_l), (base_l, head_l), (head_l, head_r), (head_r, base_r), (base_r, p2)];
 
     // 3. Obstacle Check (SDF)
-    let sensor_range = 4.0; // mm
-    let mut min_sdf = f64::MAX;
+    if let Some(field) = &ctx.sdf {
+        // Sampled: one lookup per point along the path instead of one pass per circle.
+        // The field only knows the nearest circle, so this approximates the exact sum below;
+        // the reported cost is re-scored exactly (see `exact_cost`).
+        let (hit, prox) = clearance_penalty(field.min_along_path(&cut_path));
+        c_obs_hit += hit;
+        c_obs_prox += prox;
+    }
 
     for obs in &ctx.obstacles {
         match obs {
+            // Circles are already covered by the distance field
+            Obstacle::Circle { .. } if ctx.sdf.is_some() => {},
             Obstacle::Circle { x, y, r } => {
                 let obs_p = Point::new(*x, *y);
-                let mut min_dist_segment = f64::MAX;
                 // Rule 1: NO part of the line (Straight or Dovetail) can touch circles
-                for (s, e) in &cut_path {
-                    min_dist_segment = min_dist_segment.min(dist_point_segment(obs_p, *s, *e));
-                }
-                
-                let sdf = min_dist_segment - r;
-                min_sdf = min_sdf.min(sdf);
-
-                if sdf < 0.0 {
-                    c_obs_hit += 10000.0 + sdf.powi(2) * 500000.0;
-                } else if sdf < OBS_MARGIN {
-                    c_obs_hit += (OBS_MARGIN - sdf).powi(2) * 5000.0;
-                } else if sdf < sensor_range {
-                    let weight = (1.0 - sdf / sensor_range).powi(2);
-                    c_obs_prox += weight * 0.1; 
-                }
+                let sdf = cut_path.iter()
+                    .map(|(s, e)| dist_point_segment(obs_p, *s, *e))
+                    .fold(f64::MAX, f64::min) - r;
+
+                let (hit, prox) = clearance_penalty(sdf);
+                c_obs_hit += hit;
+                c_obs_prox += prox;
             },
             Obstacle::Poly { points } => {
                 // Construct Polygon
@@ -358,8 +500,7 @@ fn evaluate_cost_detailed(x: &DVector<f64>, ctx: &CostContext, flipped: bool) ->
 
                 // Rule 2: Only DOVETAIL segments (Indices 1, 2, 3) cannot touch Polygons.
                 // Straight segments (0 and 4) are allowed to bridge across holes.
-                for i in 1..=3 {
-                    let (s, e) = cut_path[i];
+                for &(s, e) in &cut_path[1..=3] {
                     let seg = geo::Line::new(s, e);
                     
                     // distance is 0 if intersecting or inside
@@ -436,6 +577,7 @@ fn evaluate_cost_detailed(x: &DVector<f64>, ctx: &CostContext, flipped: bool) ->
 }
 
 
+/// Evaluates the cost of `input.initial_line` without optimizing it.
 pub fn debug_split_eval(input: GeometryInput) -> DebugEvalResult {
     // Reconstruct Context
     let poly_points: Vec<Point<f64>> = input.outline.iter().map(|p| Point::new(p[0], p[1])).collect();
@@ -457,6 +599,9 @@ pub fn debug_split_eval(input: GeometryInput) -> DebugEvalResult {
         radius,
         target_angle: None,
         target_offset: None,
+        sdf: None,
+        fillet_radius: input.fillet_radius.unwrap_or(0.0),
+        joint_clearance: input.joint_clearance.unwrap_or(0.0),
     };
 
     if let Some(line) = input.initial_line {
@@ -467,17 +612,106 @@ pub fn debug_split_eval(input: GeometryInput) -> DebugEvalResult {
         let (c2, log2) = evaluate_cost_detailed(&params, &ctx, true);
         
         if c1 < c2 {
+            let (dovetail_polygon, obstacle_sdf) = debug_overlay(&params, &ctx, false);
             return DebugEvalResult {
                 log: format!("=== Normal State ===\\nCost: {:.4}\\n{}", c1, log1),
                 cost: c1,
+                dovetail_polygon,
+                obstacle_sdf,
             };
         } else {
+            let (dovetail_polygon, obstacle_sdf) = debug_overlay(&params, &ctx, true);
             return DebugEvalResult {
                 log: format!("=== Flipped State ===\\nCost: {:.4}\\n{}", c2, log2),
                 cost: c2,
+                dovetail_polygon,
+                obstacle_sdf,
             };
         }
     }
     
-    DebugEvalResult { log: "Error: No line provided".to_string(), cost: -1.0 }
-}
\ No newline at end of file
+    DebugEvalResult {
+        log: "Error: No line provided".to_string(),
+        cost: -1.0,
+        dovetail_polygon: vec![],
+        obstacle_sdf: vec![],
+    }
+}
+
+/// Exact geometry behind a debug evaluation, for frontend overlays.
+/// Circle clearances use the whole cut path and polygon clearances only the
+/// dovetail segments, mirroring the rules in `evaluate_cost_detailed`.
+/// Polygons report 0.0 when touched since the distance has no interior sign.
+fn debug_overlay(x: &DVector<f64>, ctx: &CostContext, flipped: bool) -> (Vec<[f64; 2]>, Vec<f64>) {
+    let (angle, p1, p2, dt) = decode_params(x, ctx);
+    let path = dovetail_points(p1, p2, (angle.cos(), angle.sin()), dt.w, dt.h, dt.t, flipped);
+    let dovetail_polygon = path[1..5].iter().map(|p| [p.x(), p.y()]).collect();
+
+    let obstacle_sdf = ctx.obstacles.iter().map(|obs| match obs {
+        Obstacle::Circle { x, y, r } => {
+            let c = Point::new(*x, *y);
+            path.windows(2)
+                .map(|seg| dist_point_segment(c, seg[0], seg[1]))
+                .fold(f64::MAX, f64::min) - r
+        },
+        Obstacle::Poly { points } => {
+            let coords: Vec<Point<f64>> = points.iter().map(|p| Point::new(p[0], p[1])).collect();
+            let poly = Polygon::new(LineString::from(coords), vec![]);
+            path[1..5].windows(2)
+                .map(|seg| Euclidean::distance(&geo::Line::new(seg[0], seg[1]), &poly))
+                .fold(f64::MAX, f64::min)
+        }
+    }).collect();
+
+    (dovetail_polygon, obstacle_sdf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn input(tolerance: f64) -> GeometryInput {
+        GeometryInput {
+            outline: vec![[0.0, 0.0], [200.0, 0.0], [200.0, 100.0], [0.0, 100.0]],
+            obstacles: vec![Obstacle::Circle { x: 60.0, y: 50.0, r: 5.0 }],
+            bed_width: 150.0,
+            bed_height: 150.0,
+            initial_line: Some([[100.0, -10.0], [100.0, 110.0]]),
+            references: None,
+            tolerance: Some(ToleranceSpec { obstacle_position: tolerance, outline_dimension: tolerance, samples: Some(20) }),
+            sdf_resolution: None,
+            fillet_radius: None,
+            joint_clearance: None,
+        }
+    }
+
+    #[test]
+    fn test_robustness_scores_every_cut() {
+        let result = run_optimization(input(0.5), 0);
+        assert!(!result.shapes.is_empty());
+        assert!(result.shapes.iter().all(|c| c.robustness.is_some()));
+        assert_eq!(result.robustness, result.shapes.iter().filter_map(|c| c.robustness).reduce(f64::min));
+    }
+
+    #[test]
+    fn test_robustness_without_tolerance_matches_success() {
+        // Unperturbed samples keep the target bias, so they pass exactly when the result does
+        let result = run_optimization(input(0.0), 0);
+        assert_eq!(result.robustness, Some(if result.success { 1.0 } else { 0.0 }));
+    }
+
+    #[test]
+    fn test_exact_mode_penalizes_each_close_circle() {
+        // Circles 2.5 mm clear of the x = 100 line, far from the dovetail: soft proximity cost only
+        let near = |y: f64| Obstacle::Circle { x: 103.0, y, r: 0.5 };
+        let cost = |obstacles: Vec<Obstacle>| {
+            debug_split_eval(GeometryInput { obstacles, tolerance: None, ..input(0.0) }).cost
+        };
+        let base = cost(vec![]);
+        let low = cost(vec![near(15.0)]);
+        let high = cost(vec![near(85.0)]);
+        assert!(low > base && high > base);
+        assert_relative_eq!(cost(vec![near(15.0), near(85.0)]), low + high - base, epsilon = 1e-9);
+    }
+}
@@ -0,0 +1,92 @@
+//! Cheap overlap pre-pass so exports only run csgrs booleans between shapes that can interact.
+use geo::{BoundingRect, ConvexHull, Intersects, Polygon, Rect};
+
+/// Splits `polys` into groups whose members can only touch shapes in the same group.
+/// Two shapes are linked when their bounding boxes overlap and their convex hulls
+/// intersect; groups are the connected components of that relation. Hulls over-approximate
+/// the shapes, so a group may contain a pair that does not really touch, but shapes in
+/// different groups never do.
+/// Each group lists indices into `polys` in ascending order.
+pub fn group_overlapping(polys: &[Polygon<f64>]) -> Vec<Vec<usize>> {
+    let boxes: Vec<Option<Rect<f64>>> = polys.iter().map(|p| p.bounding_rect()).collect();
+    let hulls: Vec<Polygon<f64>> = polys.iter().map(|p| p.convex_hull()).collect();
+
+    // Sweep along x so each shape is only compared with boxes that overlap it in x
+    let mut order: Vec<usize> = (0..polys.len()).filter(|&i| boxes[i].is_some()).collect();
+    order.sort_by(|&a, &b| boxes[a].unwrap().min().x.total_cmp(&boxes[b].unwrap().min().x));
+
+    let mut parent: Vec<usize> = (0..polys.len()).collect();
+    for (k, &i) in order.iter().enumerate() {
+        let a = boxes[i].unwrap();
+        for &j in &order[k + 1..] {
+            let b = boxes[j].unwrap();
+            if b.min().x > a.max().x { break; }
+            if b.min().y > a.max().y || a.min().y > b.max().y { continue; }
+            if find(&mut parent, i) != find(&mut parent, j) && hulls[i].intersects(&hulls[j]) {
+                let (ri, rj) = (find(&mut parent, i), find(&mut parent, j));
+                parent[ri.max(rj)] = ri.min(rj);
+            }
+        }
+    }
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let mut group_of_root = vec![usize::MAX; polys.len()];
+    for i in 0..polys.len() {
+        let root = find(&mut parent, i);
+        if group_of_root[root] == usize::MAX {
+            group_of_root[root] = groups.len();
+            groups.push(Vec::new());
+        }
+        groups[group_of_root[root]].push(i);
+    }
+    groups
+}
+
+fn find(parent: &mut [usize], mut i: usize) -> usize {
+    while parent[i] != i {
+        parent[i] = parent[parent[i]];
+        i = parent[i];
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::LineString;
+
+    fn poly(points: &[(f64, f64)]) -> Polygon<f64> {
+        Polygon::new(LineString::from(points.to_vec()), vec![])
+    }
+
+    fn square(x: f64, y: f64, size: f64) -> Polygon<f64> {
+        poly(&[(x, y), (x + size, y), (x + size, y + size), (x, y + size)])
+    }
+
+    #[test]
+    fn test_chains_and_isolated_shapes() {
+        let polys = vec![
+            square(0.0, 0.0, 10.0),   // 0: overlaps 3
+            square(30.0, 0.0, 10.0),  // 1: alone
+            square(17.0, 17.0, 8.0),  // 2: overlaps 3 only, so joins 0 through it
+            square(8.0, 8.0, 10.0),   // 3
+        ];
+        assert_eq!(group_overlapping(&polys), vec![vec![0, 2, 3], vec![1]]);
+    }
+
+    #[test]
+    fn test_boxes_overlap_but_hulls_do_not() {
+        // Facing triangles: their boxes share [4, 10] x [4, 10], but x + y <= 10 on one and >= 14 on the other
+        let polys = vec![
+            poly(&[(0.0, 0.0), (10.0, 0.0), (0.0, 10.0)]),
+            poly(&[(10.0, 10.0), (10.0, 4.0), (4.0, 10.0)]),
+        ];
+        assert_eq!(group_overlapping(&polys), vec![vec![0], vec![1]]);
+    }
+
+    #[test]
+    fn test_touching_edges_are_grouped() {
+        let polys = vec![square(0.0, 0.0, 10.0), square(10.0, 0.0, 10.0), square(20.5, 0.0, 10.0)];
+        assert_eq!(group_overlapping(&polys), vec![vec![0, 1], vec![2]]);
+    }
+}
@@ -0,0 +1,211 @@
+//! Fits the board outline to touch-probe points measured on the machine.
+use crate::export::{ExportPoint, discretize_path_closed};
+use crate::messages::{Message, MessageCode};
+use geo::{Closest, ClosestPoint, Distance, Euclidean, LineString, Point};
+use nalgebra::{DMatrix, DVector};
+use serde::Serialize;
+
+const MAX_ITERATIONS: usize = 50;
+const CONVERGED: f64 = 1e-12;
+
+/// Design -> machine: `machine = scale * R(rotation) * design + (dx, dy)`.
+#[derive(Debug, Serialize, Clone, Copy)]
+pub struct ProbeFit {
+    /// Translation along x (mm)
+    pub dx: f64,
+    /// Translation along y (mm)
+    pub dy: f64,
+    /// Degrees, counter-clockwise
+    pub rotation: f64,
+    /// Uniform scale; 1 unless `allow_scale` was set
+    pub scale: f64,
+    /// mm between probe points and the fitted outline
+    pub rms_error: f64,
+    /// Worst probe point (mm)
+    pub max_error: f64,
+    /// Iterations until convergence
+    pub iterations: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Similarity {
+    cos: f64,
+    sin: f64,
+    scale: f64,
+    tx: f64,
+    ty: f64,
+}
+
+impl Similarity {
+    const IDENTITY: Similarity = Similarity { cos: 1.0, sin: 0.0, scale: 1.0, tx: 0.0, ty: 0.0 };
+
+    fn rotate(&self, v: [f64; 2]) -> [f64; 2] {
+        [self.cos * v[0] - self.sin * v[1], self.sin * v[0] + self.cos * v[1]]
+    }
+
+    fn apply(&self, p: Point<f64>) -> [f64; 2] {
+        let r = self.rotate([p.x(), p.y()]);
+        [self.scale * r[0] + self.tx, self.scale * r[1] + self.ty]
+    }
+
+    fn inverse_apply(&self, p: Point<f64>) -> Point<f64> {
+        let (x, y) = ((p.x() - self.tx) / self.scale, (p.y() - self.ty) / self.scale);
+        Point::new(self.cos * x + self.sin * y, -self.sin * x + self.cos * y)
+    }
+
+    /// Applies a small correction (rotation `da`, relative scale `ds`, shift `dx, dy`)
+    /// after this transform.
+    fn then(&self, da: f64, ds: f64, dx: f64, dy: f64) -> Similarity {
+        let (sin, cos) = da.sin_cos();
+        let step = Similarity { cos, sin, scale: 1.0 + ds, tx: dx, ty: dy };
+        let t = step.apply(Point::new(self.tx, self.ty));
+        Similarity {
+            cos: cos * self.cos - sin * self.sin,
+            sin: sin * self.cos + cos * self.sin,
+            scale: self.scale * (1.0 + ds),
+            tx: t[0],
+            ty: t[1],
+        }
+    }
+}
+
+/// Nearest point on the outline and the unit normal of the segment it lies on.
+fn closest_with_normal(outline: &LineString<f64>, p: Point<f64>) -> (Point<f64>, [f64; 2]) {
+    let mut best = (p, [0.0, 0.0], f64::MAX);
+    for line in outline.lines() {
+        let c = match line.closest_point(&p) {
+            Closest::Intersection(c) | Closest::SinglePoint(c) => c,
+            Closest::Indeterminate => continue,
+        };
+        let d = Euclidean::distance(&c, &p);
+        let len = Euclidean::distance(&line.start_point(), &line.end_point());
+        if d < best.2 && len > 0.0 {
+            let (ex, ey) = (line.dx() / len, line.dy() / len);
+            best = (c, [-ey, ex], d);
+        }
+    }
+    (best.0, best.1)
+}
+
+/// Point-to-line ICP. Each probe point is paired with its nearest point on the outline
+/// under the current estimate, and a Gauss-Newton step minimises the distance from each
+/// probe to the tangent line there. This lets points slide along straight edges, so it
+/// converges in a few iterations where point-to-point matching crawls.
+/// Probe points should sit on the stock edge and be spread around the part. Points
+/// along a single straight edge cannot fix the fit and are rejected.
+pub fn fit_probe_points(outline: &[ExportPoint], probes: &[[f64; 2]], allow_scale: bool) -> Result<ProbeFit, Message> {
+    if outline.len() < 3 {
+        return Err(Message::new(MessageCode::OutlineTooFewPoints, "Board outline needs at least 3 points").with("min_points", 3));
+    }
+    let unknowns = if allow_scale { 4 } else { 3 };
+    if probes.len() < unknowns {
+        return Err(Message::new(MessageCode::ProbeTooFewPoints, format!("At least {} probe points are needed", unknowns))
+            .with("needed", unknowns));
+    }
+
+    let ring = discretize_path_closed(outline);
+    let probes: Vec<Point<f64>> = probes.iter().map(|p| Point::new(p[0], p[1])).collect();
+    let mut t = Similarity::IDENTITY;
+    let mut iterations = 0;
+
+    while iterations < MAX_ITERATIONS {
+        iterations += 1;
+        let mut jtj = DMatrix::<f64>::zeros(unknowns, unknowns);
+        let mut jtr = DVector::<f64>::zeros(unknowns);
+
+        for &p in &probes {
+            let (c, n) = closest_with_normal(&ring, t.inverse_apply(p));
+            let m = t.apply(c);
+            let n = t.rotate(n);
+            let r = n[0] * (m[0] - p.x()) + n[1] * (m[1] - p.y());
+            // d(m)/d(rotation) = perp(m), d(m)/d(scale) = m, d(m)/d(shift) = identity
+            let mut row = vec![n[0] * -m[1] + n[1] * m[0], n[0], n[1]];
+            if allow_scale {
+                row.push(n[0] * m[0] + n[1] * m[1]);
+            }
+            let row = DVector::from_vec(row);
+            jtj += &row * row.transpose();
+            jtr += &row * r;
+        }
+
+        let step = jtj.cholesky()
+            .ok_or_else(|| Message::new(
+                MessageCode::ProbeUnderconstrained,
+                "Probe points do not constrain the fit; spread them around more than one edge",
+            ))?
+            .solve(&-jtr);
+        let ds = if allow_scale { step[3] } else { 0.0 };
+        t = t.then(step[0], ds, step[1], step[2]);
+        if step.norm() < CONVERGED {
+            break;
+        }
+    }
+
+    let errors: Vec<f64> = probes.iter()
+        .map(|&p| Euclidean::distance(&t.inverse_apply(p), &ring) * t.scale)
+        .collect();
+    let rms_error = (errors.iter().map(|e| e * e).sum::<f64>() / errors.len() as f64).sqrt();
+    let max_error = errors.iter().copied().fold(0.0, f64::max);
+
+    Ok(ProbeFit {
+        dx: t.tx,
+        dy: t.ty,
+        rotation: t.sin.atan2(t.cos).to_degrees(),
+        scale: t.scale,
+        rms_error,
+        max_error,
+        iterations,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn rect(w: f64, h: f64) -> Vec<ExportPoint> {
+        [[0.0, 0.0], [w, 0.0], [w, h], [0.0, h]].iter()
+            .map(|p| ExportPoint { x: p[0], y: p[1], handle_in: None, handle_out: None })
+            .collect()
+    }
+
+    /// Design points on every edge of the 100 x 60 rectangle, moved onto the machine.
+    fn probes(t: &Similarity) -> Vec<[f64; 2]> {
+        [[20.0, 0.0], [70.0, 0.0], [100.0, 15.0], [100.0, 45.0], [80.0, 60.0], [30.0, 60.0], [0.0, 40.0], [0.0, 10.0]]
+            .iter()
+            .map(|p| t.apply(Point::new(p[0], p[1])))
+            .collect()
+    }
+
+    #[test]
+    fn test_recovers_rotation_and_translation() {
+        let (sin, cos) = 2f64.to_radians().sin_cos();
+        let truth = Similarity { cos, sin, scale: 1.0, tx: 3.0, ty: -1.5 };
+        let fit = fit_probe_points(&rect(100.0, 60.0), &probes(&truth), false).unwrap();
+
+        assert_relative_eq!(fit.rotation, 2.0, epsilon = 1e-6);
+        assert_relative_eq!(fit.dx, 3.0, epsilon = 1e-6);
+        assert_relative_eq!(fit.dy, -1.5, epsilon = 1e-6);
+        assert_relative_eq!(fit.scale, 1.0);
+        assert!(fit.max_error < 1e-6);
+    }
+
+    #[test]
+    fn test_recovers_scale_when_allowed() {
+        let (sin, cos) = (-1f64).to_radians().sin_cos();
+        let truth = Similarity { cos, sin, scale: 1.01, tx: -2.0, ty: 4.0 };
+        let fit = fit_probe_points(&rect(100.0, 60.0), &probes(&truth), true).unwrap();
+
+        assert_relative_eq!(fit.rotation, -1.0, epsilon = 1e-6);
+        assert_relative_eq!(fit.scale, 1.01, epsilon = 1e-9);
+        assert_relative_eq!(fit.dx, -2.0, epsilon = 1e-6);
+        assert_relative_eq!(fit.dy, 4.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn test_single_edge_is_underconstrained() {
+        let along_bottom = [[10.0, 0.0], [40.0, 0.0], [70.0, 0.0], [90.0, 0.0]];
+        let err = fit_probe_points(&rect(100.0, 60.0), &along_bottom, false).unwrap_err();
+        assert_eq!(err.code, MessageCode::ProbeUnderconstrained);
+    }
+}
@@ -0,0 +1,265 @@
+//! Python bindings for the FEM kernels (`python` feature), so parameter studies and
+//! post-processing can be scripted against the same code the app runs. Build and install
+//! into the active environment with maturin from `src-tauri/core`:
+//!
+//! ```sh
+//! maturin develop --release
+//! ```
+//!
+//! Arrays cross as nested lists (`vertices` is [[x, y, z], ...], `indices` is 10-node
+//! connectivity). Lengths are mm and stresses MPa, as inside the solver; the materials'
+//! `with_units` constructors convert from a project unit system given as its JSON.
+use crate::fem::assembly::{self, ElementQualityReport};
+use crate::fem::material::{IsotropicMaterial, Material, OrthotropicMaterial};
+use crate::fem::mesh::TetMesh;
+use crate::fem::units::UnitSystem;
+use crate::fem::{partition, regularizer};
+use nalgebra::Matrix6;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// (element, jacobian_ratio, rule_points, reduced_accuracy, inverted)
+type QualityTuple = (usize, f64, u8, bool, bool);
+
+fn quality_tuples(reports: Vec<ElementQualityReport>) -> Vec<QualityTuple> {
+    reports.into_iter().map(|r| (r.element, r.jacobian_ratio, r.rule_points, r.reduced_accuracy, r.inverted)).collect()
+}
+
+fn unit_system(units: Option<&str>) -> PyResult<UnitSystem> {
+    units.map_or(Ok(UnitSystem::default()), |json| {
+        serde_json::from_str(json).map_err(|e| PyValueError::new_err(e.to_string()))
+    })
+}
+
+fn matrix_rows(c: Matrix6<f64>) -> Vec<Vec<f64>> {
+    c.row_iter().map(|row| row.iter().copied().collect()).collect()
+}
+
+/// Python wrapper around `TetMesh`.
+#[pyclass(name = "TetMesh", module = "shortstack_core")]
+pub struct PyTetMesh {
+    inner: TetMesh,
+}
+
+/// Rejects connectivity that points past the vertex list, which the kernels would index out of bounds.
+fn check_node_indices(vertices: &[[f64; 3]], indices: &[[usize; 10]]) -> PyResult<()> {
+    match indices.iter().flatten().find(|&&n| n >= vertices.len()) {
+        Some(n) => Err(PyValueError::new_err(format!("Node index {} out of range ({} vertices)", n, vertices.len()))),
+        None => Ok(()),
+    }
+}
+
+#[pymethods]
+impl PyTetMesh {
+    #[new]
+    fn new(vertices: Vec<[f64; 3]>, indices: Vec<[usize; 10]>) -> PyResult<Self> {
+        check_node_indices(&vertices, &indices)?;
+        Ok(Self { inner: TetMesh::new(vertices, indices) })
+    }
+
+    /// Reads a mesh saved by the app (the JSON form of `TetMesh`).
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let inner: TetMesh = serde_json::from_str(json).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        check_node_indices(&inner.vertices, &inner.indices)?;
+        Ok(Self { inner })
+    }
+
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.inner).map_err(|e| PyValueError::new_err(e.to_string()))
+    }
+
+    #[getter]
+    fn vertices(&self) -> Vec<[f64; 3]> {
+        self.inner.vertices.clone()
+    }
+
+    #[getter]
+    fn indices(&self) -> Vec<[usize; 10]> {
+        self.inner.indices.clone()
+    }
+
+    fn volume(&self) -> f64 {
+        self.inner.volume()
+    }
+
+    fn surface_area(&self) -> f64 {
+        self.inner.surface_area()
+    }
+
+    /// Elements whose Jacobian quality falls below `threshold`.
+    fn check_jacobian_quality(&self, threshold: f64) -> Vec<usize> {
+        self.inner.check_jacobian_quality(threshold)
+    }
+
+    /// Owning part of each element after recursive coordinate bisection into `parts`.
+    fn partition(&self, parts: usize) -> Vec<usize> {
+        partition::partition_rcb(&self.inner, parts)
+    }
+
+    fn __len__(&self) -> usize {
+        self.inner.indices.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("TetMesh({} vertices, {} elements)", self.inner.vertices.len(), self.inner.indices.len())
+    }
+}
+
+/// Python wrapper around `IsotropicMaterial`.
+#[pyclass(name = "IsotropicMaterial", module = "shortstack_core")]
+#[derive(Clone, Copy)]
+pub struct PyIsotropicMaterial {
+    inner: IsotropicMaterial,
+}
+
+#[pymethods]
+impl PyIsotropicMaterial {
+    #[new]
+    fn new(e: f64, nu: f64) -> Self {
+        Self { inner: IsotropicMaterial { e, nu } }
+    }
+
+    /// `e` in the stress unit of `units`, e.g. '{"stress": "psi"}'.
+    #[staticmethod]
+    #[pyo3(signature = (e, nu, units=None))]
+    fn with_units(e: f64, nu: f64, units: Option<&str>) -> PyResult<Self> {
+        Ok(Self { inner: IsotropicMaterial::with_units(e, nu, &unit_system(units)?) })
+    }
+
+    #[getter]
+    fn e(&self) -> f64 {
+        self.inner.e
+    }
+
+    #[getter]
+    fn nu(&self) -> f64 {
+        self.inner.nu
+    }
+
+    /// 6x6 stiffness in Voigt order (xx, yy, zz, xy, yz, zx).
+    fn c_matrix(&self) -> Vec<Vec<f64>> {
+        matrix_rows(self.inner.c_matrix())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("IsotropicMaterial(e={}, nu={})", self.inner.e, self.inner.nu)
+    }
+}
+
+/// Python wrapper around `OrthotropicMaterial`.
+#[pyclass(name = "OrthotropicMaterial", module = "shortstack_core")]
+#[derive(Clone, Copy)]
+pub struct PyOrthotropicMaterial {
+    inner: OrthotropicMaterial,
+}
+
+#[pymethods]
+impl PyOrthotropicMaterial {
+    #[new]
+    #[pyo3(signature = (*, ex, ey, ez, nu_xy, nu_yz, nu_xz, g_xy, g_yz, g_zx))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(ex: f64, ey: f64, ez: f64, nu_xy: f64, nu_yz: f64, nu_xz: f64, g_xy: f64, g_yz: f64, g_zx: f64) -> Self {
+        Self { inner: OrthotropicMaterial { ex, ey, ez, nu_xy, nu_yz, nu_xz, g_xy, g_yz, g_zx } }
+    }
+
+    /// A printed part whose weak (layer) direction is Z.
+    #[staticmethod]
+    fn from_transverse_isotropy(e_fill: f64, e_layer: f64, nu_fill: f64, nu_layer: f64, g_layer: f64) -> Self {
+        Self { inner: OrthotropicMaterial::from_transverse_isotropy(e_fill, e_layer, nu_fill, nu_layer, g_layer) }
+    }
+
+    /// 6x6 stiffness in Voigt order. Inconsistent constants (a singular compliance matrix)
+    /// raise pyo3's PanicException, as they would abort an app solve.
+    fn c_matrix(&self) -> Vec<Vec<f64>> {
+        matrix_rows(self.inner.c_matrix())
+    }
+
+    fn __repr__(&self) -> String {
+        let m = self.inner;
+        format!("OrthotropicMaterial(ex={}, ey={}, ez={}, nu_xy={}, nu_yz={}, nu_xz={}, g_xy={}, g_yz={}, g_zx={})",
+            m.ex, m.ey, m.ez, m.nu_xy, m.nu_yz, m.nu_xz, m.g_xy, m.g_yz, m.g_zx)
+    }
+}
+
+/// Either material class, copied out so assembly can run without the GIL.
+#[derive(Clone, Copy)]
+enum AnyMaterial {
+    Isotropic(IsotropicMaterial),
+    Orthotropic(OrthotropicMaterial),
+}
+
+impl AnyMaterial {
+    fn extract(obj: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(m) = obj.extract::<PyIsotropicMaterial>() {
+            return Ok(AnyMaterial::Isotropic(m.inner));
+        }
+        if let Ok(m) = obj.extract::<PyOrthotropicMaterial>() {
+            return Ok(AnyMaterial::Orthotropic(m.inner));
+        }
+        Err(PyValueError::new_err("Expected an IsotropicMaterial or OrthotropicMaterial"))
+    }
+
+    fn as_material(&self) -> &dyn Material {
+        match self {
+            AnyMaterial::Isotropic(m) => m,
+            AnyMaterial::Orthotropic(m) => m,
+        }
+    }
+}
+
+/// Global stiffness in coordinate form, as `assemble_stiffness` returns it.
+#[pyclass(name = "AssemblyResult", module = "shortstack_core", get_all)]
+pub struct PyAssemblyResult {
+    dofs: usize,
+    /// (row, col, value); duplicates are summed, as in scipy's coo_matrix.
+    triplets: Vec<(usize, usize, f64)>,
+    /// (element, jacobian_ratio, rule_points, reduced_accuracy, inverted) per non-standard element.
+    flagged: Vec<QualityTuple>,
+}
+
+/// Per-element quadrature point counts and the elements that were upgraded or flagged.
+#[pyfunction]
+fn quadrature_plan(py: Python<'_>, mesh: &PyTetMesh) -> PyResult<(Vec<usize>, Vec<QualityTuple>)> {
+    let mesh = &mesh.inner;
+    let (rules, flagged) = py.allow_threads(|| assembly::quadrature_plan(mesh));
+    // As ints rather than u8s, which pyo3 would hand over as bytes
+    Ok((rules.into_iter().map(usize::from).collect(), quality_tuples(flagged)))
+}
+
+/// Assembles the global stiffness matrix (3 dofs per node, mm-N-MPa).
+#[pyfunction]
+fn assemble_stiffness(py: Python<'_>, mesh: &PyTetMesh, material: &Bound<'_, PyAny>) -> PyResult<PyAssemblyResult> {
+    let material = AnyMaterial::extract(material)?;
+    let mesh = &mesh.inner;
+    let result = py.allow_threads(|| assembly::assemble_stiffness(mesh, material.as_material()))
+        .map_err(|e| PyValueError::new_err(e.message))?;
+    Ok(PyAssemblyResult { dofs: result.dofs, triplets: result.triplets, flagged: quality_tuples(result.flagged) })
+}
+
+/// Decimates/subdivides a surface triangle soup towards `target_edge_len`.
+/// `vertices` is flat [x0, y0, z0, ...] and `indices` flat triangles; returns both.
+#[pyfunction]
+fn regularize(py: Python<'_>, vertices: Vec<f64>, indices: Vec<usize>, target_edge_len: f64) -> PyResult<(Vec<f64>, Vec<usize>)> {
+    if !vertices.len().is_multiple_of(3) || !indices.len().is_multiple_of(3) {
+        return Err(PyValueError::new_err("vertices and indices must be flat lists of triples"));
+    }
+    if target_edge_len <= 0.0 {
+        return Err(PyValueError::new_err("target_edge_len must be positive"));
+    }
+    Ok(py.allow_threads(|| regularizer::regularize(&vertices, &indices, target_edge_len)))
+}
+
+#[pymodule]
+fn shortstack_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyTetMesh>()?;
+    m.add_class::<PyIsotropicMaterial>()?;
+    m.add_class::<PyOrthotropicMaterial>()?;
+    m.add_class::<PyAssemblyResult>()?;
+    m.add_function(wrap_pyfunction!(quadrature_plan, m)?)?;
+    m.add_function(wrap_pyfunction!(assemble_stiffness, m)?)?;
+    m.add_function(wrap_pyfunction!(regularize, m)?)?;
+    m.add("QUALITY_STANDARD", assembly::QUALITY_STANDARD)?;
+    m.add("QUALITY_REDUCED", assembly::QUALITY_REDUCED)?;
+    Ok(())
+}
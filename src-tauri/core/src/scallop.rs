@@ -0,0 +1,223 @@
+//! Estimates the scallop (cusp) height a ball-nose raster leaves on each visible depth region
+//! of a carve layer, and the stepover that would keep it under a target finish.
+//!
+//! Flat regions use the textbook cusp h = r - sqrt(r² - (s/2)²). On the slices of a
+//! ball-nose gradient the passes lie further apart along the surface (s / cos φ for slope φ,
+//! assuming the stepover runs across the slope), and the concave fillet of radius ρ nests
+//! the tool, so the effective radius becomes 1 / (1/r - 1/ρ).
+use crate::export::{BALL_NOSE_STEPS, ExportRequest, ball_nose_fillet_radius, get_depth_regions};
+use crate::messages::{Message, MessageCode};
+use geo::{Area, BoundingRect};
+use serde::Serialize;
+
+/// Slopes above this (degrees) are better finished with contour/waterline passes.
+const STEEP_SLOPE_DEG: f64 = 60.0;
+
+/// Scallop estimate for one visible depth region.
+#[derive(Debug, Serialize, Clone)]
+pub struct RegionScallop {
+    /// Region depth (mm)
+    pub depth: f64,
+    /// Bounding box, board coordinates (mm)
+    pub min: [f64; 2],
+    /// See `min`
+    pub max: [f64; 2],
+    /// Visible area (mm²)
+    pub area: f64,
+    /// Steepest surface slope represented by the region
+    pub slope_deg: f64,
+    /// Concave gradient radius; None on flat floors
+    pub fillet_radius: Option<f64>,
+    /// Cusp height left at the given stepover (mm); None when the tool does not fit
+    pub scallop_height: Option<f64>,
+    /// Largest stepover meeting the target, if one was given
+    pub suggested_stepover: Option<f64>,
+    /// Whether `scallop_height` is within the target, if one was given (false when the
+    /// tool does not fit)
+    pub meets_target: Option<bool>,
+    /// Slope above 60°, better finished with contour passes
+    pub steep: bool,
+    /// False when the ball is larger than the fillet, which then comes out at the tool radius
+    pub tool_fits: bool,
+}
+
+/// Result of `estimate_scallops` for one carve layer.
+#[derive(Debug, Serialize, Clone)]
+pub struct ScallopReport {
+    /// Ball-nose diameter used (mm)
+    pub tool_diameter: f64,
+    /// Stepover used (mm)
+    pub stepover: f64,
+    /// Target cusp height (mm), if one was given
+    pub target_scallop: Option<f64>,
+    /// Largest `scallop_height` over the regions the tool fits (mm)
+    pub max_scallop: f64,
+    /// Smallest suggestion among non-steep regions (steep ones need contour passes)
+    pub suggested_stepover: Option<f64>,
+    /// One entry per visible depth region
+    pub regions: Vec<RegionScallop>,
+}
+
+/// Slope and fillet radius of the surface one depth slice stands for.
+struct SliceSurface {
+    depth: f64,
+    slope: f64, // Radians from horizontal
+    fillet_radius: Option<f64>,
+}
+
+/// Mirrors `expand_ball_nose_shape`: slice j at depth z_j covers the ring out to slice
+/// j + 1, so its surface is the chord between the two points of the fillet profile.
+fn shape_surfaces(request: &ExportRequest) -> Vec<SliceSurface> {
+    let mut out = Vec::new();
+    for shape in &request.shapes {
+        let r = ball_nose_fillet_radius(shape);
+        if r <= 0.0 {
+            out.push(SliceSurface { depth: shape.depth, slope: 0.0, fillet_radius: None });
+            continue;
+        }
+        let base = shape.depth - r;
+        let profile: Vec<(f64, f64)> = (0..=BALL_NOSE_STEPS)
+            .map(|i| {
+                let theta = i as f64 / BALL_NOSE_STEPS as f64 * std::f64::consts::FRAC_PI_2;
+                (r * (1.0 - theta.cos()), base + theta.sin() * r)
+            })
+            .collect();
+        for j in 0..BALL_NOSE_STEPS {
+            let ((o0, z0), (o1, z1)) = (profile[j], profile[j + 1]);
+            out.push(SliceSurface { depth: z0, slope: (z1 - z0).atan2(o1 - o0), fillet_radius: Some(r) });
+        }
+        // The last slice is the flat floor inside the fillet
+        out.push(SliceSurface { depth: profile[BALL_NOSE_STEPS].1, slope: 0.0, fillet_radius: None });
+    }
+    out
+}
+
+/// Ball radius that matters on a concave surface of radius `fillet`; None when the tool
+/// is wider than the fillet and cannot follow it.
+fn effective_radius(tool_radius: f64, fillet: Option<f64>) -> Option<f64> {
+    match fillet {
+        None => Some(tool_radius),
+        Some(rho) if rho > tool_radius + 1e-9 => Some(1.0 / (1.0 / tool_radius - 1.0 / rho)),
+        Some(_) => None,
+    }
+}
+
+fn cusp_height(radius: f64, spacing: f64) -> f64 {
+    let half = spacing / 2.0;
+    if half >= radius { radius } else { radius - (radius * radius - half * half).sqrt() }
+}
+
+/// Plan-view stepover giving a cusp of `target` on a surface with this slope.
+fn stepover_for(radius: f64, slope: f64, target: f64) -> f64 {
+    let h = target.min(radius);
+    2.0 * (h * (2.0 * radius - h)).sqrt() * slope.cos()
+}
+
+/// Scallop height per visible depth region for a ball-nose raster at `stepover`, plus the
+/// stepover that would meet `target_scallop`.
+pub fn estimate_scallops(request: &ExportRequest, tool_diameter: f64, stepover: f64, target_scallop: Option<f64>) -> Result<ScallopReport, Message> {
+    if request.machining_type != "Carved/Printed" {
+        return Err(Message::new(MessageCode::ScallopNeedsCarvedLayer, "Scallop estimation needs a carved layer"));
+    }
+    if tool_diameter <= 0.0 {
+        return Err(Message::not_positive("tool_diameter", "Tool diameter must be positive"));
+    }
+    if stepover <= 0.0 {
+        return Err(Message::not_positive("stepover", "Stepover must be positive"));
+    }
+    if target_scallop.is_some_and(|t| t <= 0.0) {
+        return Err(Message::not_positive("target_scallop", "Target scallop height must be positive"));
+    }
+    let tool_radius = tool_diameter / 2.0;
+    let (_, regions) = get_depth_regions(request).ok_or_else(Message::outline_missing)?;
+    let surfaces = shape_surfaces(request);
+
+    let mut out = Vec::new();
+    for (depth, region) in regions {
+        if depth <= 1e-6 { continue; }
+        let Some(bounds) = region.bounding_rect() else { continue };
+
+        // Several shapes can share a depth; the steepest surface decides the finish
+        let surface = surfaces.iter()
+            .filter(|s| (s.depth - depth).abs() < 1e-6)
+            .max_by(|a, b| a.slope.partial_cmp(&b.slope).unwrap_or(std::cmp::Ordering::Equal));
+        let (slope, fillet_radius) = surface.map_or((0.0, None), |s| (s.slope, s.fillet_radius));
+
+        let radius = effective_radius(tool_radius, fillet_radius);
+        let scallop_height = radius.map(|r| cusp_height(r, stepover / slope.cos().max(1e-6)));
+        let suggested_stepover = target_scallop.and_then(|t| radius.map(|r| stepover_for(r, slope, t).min(tool_diameter)));
+
+        out.push(RegionScallop {
+            depth,
+            min: [bounds.min().x, bounds.min().y],
+            max: [bounds.max().x, bounds.max().y],
+            area: region.unsigned_area(),
+            slope_deg: slope.to_degrees(),
+            fillet_radius,
+            scallop_height,
+            suggested_stepover,
+            meets_target: target_scallop.map(|t| scallop_height.is_some_and(|h| h <= t + 1e-9)),
+            steep: slope.to_degrees() > STEEP_SLOPE_DEG,
+            tool_fits: radius.is_some(),
+        });
+    }
+
+    let max_scallop = out.iter().filter_map(|r| r.scallop_height).fold(0.0, f64::max);
+    let suggested_stepover = out.iter().filter(|r| !r.steep).filter_map(|r| r.suggested_stepover).reduce(f64::min);
+    Ok(ScallopReport { tool_diameter, stepover, target_scallop, max_scallop, suggested_stepover, regions: out })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn carved_circle(endmill_radius: f64) -> ExportRequest {
+        let corner = |x: f64, y: f64| serde_json::json!({ "x": x, "y": y, "handle_in": null, "handle_out": null });
+        let circle = serde_json::json!({
+            "shape_type": "circle", "x": 50.0, "y": 50.0, "width": null, "height": null, "diameter": 20.0,
+            "angle": null, "corner_radius": null, "thickness": null, "points": null, "depth": 3.0,
+            "endmill_radius": endmill_radius, "feed": null, "speed": null, "power": null,
+        });
+        serde_json::from_value(serde_json::json!({
+            "filepath": "", "file_type": "SVG", "machining_type": "Carved/Printed", "cut_direction": "Top",
+            "outline": [corner(0.0, 0.0), corner(100.0, 0.0), corner(100.0, 100.0), corner(0.0, 100.0)],
+            "shapes": [circle], "layer_thickness": 6.0, "stl_content": null,
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_cusp_height_matches_hand_values() {
+        // r = 3, s = 2: 3 - sqrt(9 - 1)
+        assert_relative_eq!(cusp_height(3.0, 2.0), 0.17157287525381, epsilon = 1e-12);
+        // Passes wider than the ball leave the full radius
+        assert_relative_eq!(cusp_height(1.0, 3.0), 1.0);
+    }
+
+    #[test]
+    fn test_stepover_for_matches_hand_values() {
+        // r = 5, h = 0.1: 2 sqrt(0.1 * 9.9)
+        assert_relative_eq!(stepover_for(5.0, 0.0, 0.1), 1.98997487421324, epsilon = 1e-12);
+        // On a 60° slope the plan-view stepover halves
+        assert_relative_eq!(stepover_for(5.0, 60f64.to_radians(), 0.1), 0.99498743710662, epsilon = 1e-12);
+        // And it inverts cusp_height on the flat
+        assert_relative_eq!(cusp_height(3.0, stepover_for(3.0, 0.0, 0.2)), 0.2, epsilon = 1e-12);
+    }
+
+    #[test]
+    fn test_tool_too_wide_for_fillet_has_no_scallop() {
+        // 6 mm ball in a 2 mm fillet: only the flat floor can be estimated
+        let report = estimate_scallops(&carved_circle(2.0), 6.0, 1.0, Some(0.05)).unwrap();
+        let (fits, no_fit): (Vec<_>, Vec<_>) = report.regions.iter().partition(|r| r.tool_fits);
+        assert!(!fits.is_empty() && !no_fit.is_empty());
+        for r in &no_fit {
+            assert_eq!(r.scallop_height, None);
+            assert_eq!(r.meets_target, Some(false));
+            assert_eq!(r.suggested_stepover, None);
+        }
+        for r in &fits {
+            assert!(r.scallop_height.is_some());
+        }
+        assert_relative_eq!(report.max_scallop, fits.iter().filter_map(|r| r.scallop_height).fold(0.0, f64::max));
+    }
+}
@@ -0,0 +1,108 @@
+//! Precomputed 2D signed distance field over circular obstacles.
+//! Used by the optimizer when a board has too many obstacles for exact per-obstacle checks.
+use crate::geometry::Obstacle;
+use geo::Point;
+use rayon::prelude::*;
+
+/// Upper bound on grid samples (8 bytes each); finer requests are coarsened to fit.
+const MAX_CELLS: f64 = 4_000_000.0;
+
+/// Signed distance to the circle obstacles, sampled on a regular grid.
+pub struct DistanceField {
+    min_x: f64,
+    min_y: f64,
+    cell: f64,
+    nx: usize,
+    ny: usize,
+    values: Vec<f64>, // Row-major, ny rows of nx samples
+}
+
+impl DistanceField {
+    /// Samples distance-to-nearest-circle-boundary on a regular grid covering
+    /// `[min, max]` with spacing `cell`. Negative values are inside a circle.
+    /// The spacing is widened when the grid would exceed `MAX_CELLS` samples.
+    /// Rows are computed in parallel on the rayon pool.
+    pub fn build(obstacles: &[Obstacle], min: [f64; 2], max: [f64; 2], cell: f64) -> Self {
+        let area = (max[0] - min[0]).max(0.0) * (max[1] - min[1]).max(0.0);
+        let cell = cell.max(1e-3).max((area / MAX_CELLS).sqrt());
+        let nx = (((max[0] - min[0]) / cell).ceil() as usize).max(1) + 1;
+        let ny = (((max[1] - min[1]) / cell).ceil() as usize).max(1) + 1;
+
+        let circles: Vec<(f64, f64, f64)> = obstacles.iter()
+            .filter_map(|o| match o {
+                Obstacle::Circle { x, y, r } => Some((*x, *y, *r)),
+                _ => None,
+            })
+            .collect();
+
+        let mut values = vec![f64::MAX; nx * ny];
+        values.par_chunks_mut(nx).enumerate().for_each(|(j, row)| {
+            let py = min[1] + j as f64 * cell;
+            for (i, v) in row.iter_mut().enumerate() {
+                let px = min[0] + i as f64 * cell;
+                for (cx, cy, r) in &circles {
+                    let d = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt() - r;
+                    if d < *v { *v = d; }
+                }
+            }
+        });
+
+        Self { min_x: min[0], min_y: min[1], cell, nx, ny, values }
+    }
+
+    /// Grid spacing actually used, after any coarsening in `build`.
+    pub fn cell(&self) -> f64 {
+        self.cell
+    }
+
+    /// Bilinear lookup. Points outside the grid are clamped to the nearest edge.
+    pub fn sample(&self, x: f64, y: f64) -> f64 {
+        let fx = ((x - self.min_x) / self.cell).clamp(0.0, (self.nx - 1) as f64);
+        let fy = ((y - self.min_y) / self.cell).clamp(0.0, (self.ny - 1) as f64);
+        let i0 = (fx.floor() as usize).min(self.nx.saturating_sub(2));
+        let j0 = (fy.floor() as usize).min(self.ny.saturating_sub(2));
+        let i1 = (i0 + 1).min(self.nx - 1);
+        let j1 = (j0 + 1).min(self.ny - 1);
+        let tx = fx - i0 as f64;
+        let ty = fy - j0 as f64;
+
+        let v00 = self.values[j0 * self.nx + i0];
+        let v10 = self.values[j0 * self.nx + i1];
+        let v01 = self.values[j1 * self.nx + i0];
+        let v11 = self.values[j1 * self.nx + i1];
+
+        let top = v00 + (v10 - v00) * tx;
+        let bottom = v01 + (v11 - v01) * tx;
+        top + (bottom - top) * ty
+    }
+
+    /// Minimum field value along a set of segments, sampled at half-cell spacing.
+    pub fn min_along_path(&self, path: &[(Point<f64>, Point<f64>)]) -> f64 {
+        let step = self.cell * 0.5;
+        let mut min_val = f64::MAX;
+        for (s, e) in path {
+            let len = ((e.x() - s.x()).powi(2) + (e.y() - s.y()).powi(2)).sqrt();
+            let steps = ((len / step).ceil() as usize).max(1);
+            for k in 0..=steps {
+                let t = k as f64 / steps as f64;
+                let v = self.sample(s.x() + (e.x() - s.x()) * t, s.y() + (e.y() - s.y()) * t);
+                min_val = min_val.min(v);
+            }
+        }
+        min_val
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_caps_cell_count() {
+        let obstacles = vec![Obstacle::Circle { x: 500.0, y: 500.0, r: 10.0 }];
+        let field = DistanceField::build(&obstacles, [0.0, 0.0], [1000.0, 1000.0], 1e-6);
+        assert!(field.cell() >= 0.5);
+        assert!((field.nx * field.ny) as f64 <= MAX_CELLS * 1.01);
+        assert!((field.sample(500.0, 500.0) + 10.0).abs() < field.cell());
+    }
+}
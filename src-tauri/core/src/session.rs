@@ -0,0 +1,656 @@
+//! Session recording and replay, for geometry bugs that only show up on a user's project.
+//!
+//! While recording is on, the app appends every invoked command and its arguments to a JSON
+//! lines file (`SessionEntry` per line). The path arguments of each command (`PATH_FIELDS`)
+//! are replaced by `$DIR/<file name>`, unique within the session, so the file can be shared
+//! without leaking the user's directory layout.
+//!
+//! `replay` re-runs the recorded commands in order against this crate, with `$DIR` mapped to
+//! a work directory: exports land there and nowhere else, and files the session read (traced
+//! images, depth maps to verify) must be copied there first. Commands whose logic lives in the app
+//! (sandbox approvals, artifact queries, stored optimizations) are reported as skipped.
+use crate::export::{self, ExportRequest, ExportResult};
+use crate::fem::geo_builder::GeoLayer;
+use crate::fem::gmsh_interop::{self, FeaRequest};
+use crate::fem::tetgen;
+use crate::messages::{Message, MessageCode};
+use crate::{
+    balance, calibration, depth_map_verify, export_verify, glue_area, keepout, mesh_sizing, optimizer, probe_fit,
+    scallop, split_export, thin_webs, tool_reach, trace,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Stands for the directory of every path argument in a recorded session.
+pub const DIR_PLACEHOLDER: &str = "$DIR";
+
+/// Commands that are never written to a session: recording control itself, and export
+/// streams, whose chunks are frontend-generated file contents rather than geometry input.
+const NOT_RECORDED: &[&str] = &[
+    "start_session_recording",
+    "stop_session_recording",
+    "replay_session",
+    "begin_export_stream",
+    "append_export_stream",
+    "finish_export_stream",
+    "cancel_export_stream",
+];
+
+/// One recorded command, as a line of the session file.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SessionEntry {
+    /// Position in the session, starting at 0
+    pub seq: u64,
+    /// Since recording started
+    pub elapsed_ms: u64,
+    /// Tauri command name
+    pub command: String,
+    /// Keyed by the frontend's (camelCase) argument names
+    pub args: Value,
+}
+
+struct ActiveSession {
+    path: PathBuf,
+    file: File,
+    started: Instant,
+    seq: u64,
+    scrubber: PathScrubber,
+}
+
+/// Opt-in command recorder; idle until `start` is called.
+pub struct SessionRecorder {
+    dir: Option<PathBuf>,
+    active: Mutex<Option<ActiveSession>>,
+}
+
+impl SessionRecorder {
+    /// Sessions are written to `dir`; without one, recording cannot be started.
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        Self { dir, active: Mutex::new(None) }
+    }
+
+    /// Starts a new session file (ending any current one) and returns its path.
+    pub fn start(&self) -> Result<PathBuf, Message> {
+        let dir = self.dir.as_ref()
+            .ok_or_else(|| Message::new(MessageCode::AppDataUnavailable, "No directory for session recordings"))?;
+        std::fs::create_dir_all(dir).map_err(|e| Message::write_failed(dir, e))?;
+        let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        let path = dir.join(format!("session_{}.jsonl", stamp));
+        let file = OpenOptions::new().create(true).append(true).open(&path).map_err(|e| Message::write_failed(&path, e))?;
+
+        *self.active.lock().unwrap() = Some(ActiveSession { path: path.clone(), file, started: Instant::now(), seq: 0, scrubber: PathScrubber::default() });
+        Ok(path)
+    }
+
+    /// Ends the session; returns its file, or None when nothing was being recorded.
+    pub fn stop(&self) -> Option<PathBuf> {
+        self.active.lock().unwrap().take().map(|s| s.path)
+    }
+
+    /// Whether a session is currently being written.
+    pub fn is_recording(&self) -> bool {
+        self.active.lock().unwrap().is_some()
+    }
+
+    /// Appends one command with its paths scrubbed. Each line is written straight through,
+    /// so a session survives the crash it is meant to reproduce.
+    pub fn record(&self, command: &str, args: &Value) {
+        if NOT_RECORDED.contains(&command) {
+            return;
+        }
+        let mut active = self.active.lock().unwrap();
+        let Some(session) = active.as_mut() else { return };
+
+        let mut args = args.clone();
+        session.scrubber.scrub(command, &mut args);
+        let entry = SessionEntry {
+            seq: session.seq,
+            elapsed_ms: session.started.elapsed().as_millis() as u64,
+            command: command.to_string(),
+            args,
+        };
+        session.seq += 1;
+        let result = serde_json::to_string(&entry)
+            .map_err(|e| e.to_string())
+            .and_then(|line| writeln!(session.file, "{}", line).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            eprintln!("Failed to record {}: {}", command, e);
+        }
+    }
+}
+
+/// Path-valued arguments of each command, as JSON pointers into its args (`*` matches every
+/// array element). Only these are scrubbed when recording and restored when replaying.
+const PATH_FIELDS: &[(&str, &[&str])] = &[
+    ("export_layer_files", &["/request/filepath"]),
+    ("split_export_request", &["/request/filepath"]),
+    ("mesh_export_request", &["/request/filepath"]),
+    ("suggest_mesh_size", &["/request/filepath"]),
+    ("detect_thin_webs", &["/request/filepath"]),
+    ("glue_area_report", &["/layers/*/filepath"]),
+    ("verify_depth_map", &["/path", "/request/filepath"]),
+    ("achievable_depth_report", &["/request/filepath"]),
+    ("estimate_scallops", &["/request/filepath"]),
+    ("export_calibration_grid", &["/request/filepath"]),
+    ("export_mesh_partitions", &["/filepath"]),
+    ("trace_image", &["/path"]),
+    ("query_artifacts", &["/query/path_contains"]),
+];
+
+fn path_fields(command: &str) -> &'static [&'static str] {
+    PATH_FIELDS.iter().find(|(c, _)| *c == command).map_or(&[], |(_, fields)| fields)
+}
+
+/// Calls `f` on every string `pointer` selects in `value`.
+fn for_each_path(value: &mut Value, pointer: &str, f: &mut dyn FnMut(&mut String)) {
+    let Some(rest) = pointer.strip_prefix('/') else {
+        if let Value::String(s) = value {
+            f(s);
+        }
+        return;
+    };
+    let (key, rest) = rest.find('/').map_or((rest, ""), |i| (&rest[..i], &rest[i..]));
+    match value {
+        Value::Array(items) if key == "*" => items.iter_mut().for_each(|v| for_each_path(v, rest, f)),
+        Value::Object(map) => {
+            if let Some(v) = map.get_mut(key) {
+                for_each_path(v, rest, f);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Gives every distinct path in a session its own `$DIR/<file name>`, renaming a file name
+/// that a different directory already used ("out.svg", "out_2.svg", ...).
+#[derive(Debug, Default)]
+pub struct PathScrubber {
+    names: HashMap<String, String>, // Original path -> scrubbed file name
+    used: HashSet<String>,
+}
+
+impl PathScrubber {
+    /// Replaces the path-typed arguments of `command` in `args`. Empty strings are kept.
+    pub fn scrub(&mut self, command: &str, args: &mut Value) {
+        for pointer in path_fields(command) {
+            for_each_path(args, pointer, &mut |s| {
+                if !s.is_empty() {
+                    *s = format!("{}/{}", DIR_PLACEHOLDER, self.name_for(s));
+                }
+            });
+        }
+    }
+
+    fn name_for(&mut self, path: &str) -> String {
+        if let Some(name) = self.names.get(path) {
+            return name.clone();
+        }
+        let base = match path.rsplit(['/', '\\']).next() {
+            Some(n) if is_plain_file_name(n) => n.to_string(),
+            _ => "file".to_string(),
+        };
+        let (stem, ext) = match base.rfind('.') {
+            Some(i) if i > 0 => (&base[..i], &base[i..]),
+            _ => (base.as_str(), ""),
+        };
+        let mut name = base.clone();
+        let mut n = 2;
+        while self.used.contains(&name) {
+            name = format!("{}_{}{}", stem, n, ext);
+            n += 1;
+        }
+        self.used.insert(name.clone());
+        self.names.insert(path.to_string(), name.clone());
+        name
+    }
+}
+
+/// A single path component that cannot climb out of, or re-root, the directory it is
+/// joined to.
+fn is_plain_file_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':', '\0'])
+}
+
+/// Maps the `$DIR/<file name>` arguments of `command` back to files directly inside `dir`.
+/// Anything else in a path field (absolute or relative paths, `..`, separators in the name,
+/// a symlink leading out of `dir`) is rejected, so a shared session cannot touch other files.
+pub fn restore_paths(command: &str, args: &mut Value, dir: &Path) -> Result<(), String> {
+    let root = dir.canonicalize().map_err(|e| format!("{}: {}", dir.display(), e))?;
+    let mut result = Ok(());
+    for pointer in path_fields(command) {
+        for_each_path(args, pointer, &mut |s| {
+            if s.is_empty() || result.is_err() {
+                return;
+            }
+            result = restore_path(s, dir, &root).map(|p| *s = p.to_string_lossy().into_owned());
+        });
+    }
+    result
+}
+
+fn restore_path(scrubbed: &str, dir: &Path, root: &Path) -> Result<PathBuf, String> {
+    let name = scrubbed
+        .strip_prefix(DIR_PLACEHOLDER)
+        .and_then(|r| r.strip_prefix('/'))
+        .ok_or_else(|| format!("Path `{}` was not recorded as {}/<file name>", scrubbed, DIR_PLACEHOLDER))?;
+    if !is_plain_file_name(name) {
+        return Err(format!("Path `{}` is not a plain file name", scrubbed));
+    }
+    let path = dir.join(name);
+    // An existing entry may be a symlink; it must still resolve inside the work directory
+    let resolved = if std::fs::symlink_metadata(&path).is_ok() {
+        path.canonicalize().map_err(|e| format!("Path `{}`: {}", scrubbed, e))?
+    } else {
+        root.join(name)
+    };
+    if resolved.parent() != Some(root) {
+        return Err(format!("Path `{}` resolves outside the replay directory", scrubbed));
+    }
+    Ok(path)
+}
+
+/// Reads a session file, skipping lines that do not parse.
+pub fn load_session(path: &Path) -> Result<Vec<SessionEntry>, Message> {
+    let file = File::open(path).map_err(|e| Message::read_failed(path, e))?;
+    let mut entries = Vec::new();
+    for (i, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| Message::read_failed(path, e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // A crash can cut the last line short; everything before it still replays
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => eprintln!("Skipping session line {}: {}", i + 1, e),
+        }
+    }
+    Ok(entries)
+}
+
+/// How a replayed command ended.
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayStatus {
+    /// Returned a result
+    Ok,
+    /// Returned an error or panicked
+    Error,
+    /// Not replayable here (unknown command or no Gmsh)
+    Skipped,
+}
+
+/// Result of replaying one `SessionEntry`.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReplayOutcome {
+    /// `SessionEntry::seq` of the replayed command
+    pub seq: u64,
+    /// `SessionEntry::command` of the replayed command
+    pub command: String,
+    /// How the command ended
+    pub status: ReplayStatus,
+    /// The command's result, when it succeeded
+    pub result: Option<Value>,
+    /// The command's error as the frontend would have received it
+    pub error: Option<Value>,
+    /// Wall-clock time the command took
+    pub millis: u64,
+}
+
+/// Summary of a whole `replay`.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReplayReport {
+    /// Directory `$DIR` was mapped to
+    pub work_dir: String,
+    /// One outcome per entry, in order
+    pub outcomes: Vec<ReplayOutcome>,
+    /// Number of `Error` outcomes
+    pub errors: usize,
+    /// Number of `Skipped` outcomes
+    pub skipped: usize,
+}
+
+/// Settings for `replay`.
+#[derive(Debug, Default, Clone)]
+pub struct ReplayOptions {
+    /// Gmsh executable for meshing commands; they are skipped without one.
+    pub gmsh: Option<PathBuf>,
+}
+
+fn arg<T: DeserializeOwned>(args: &Value, key: &str) -> Result<T, Value> {
+    serde_json::from_value(args.get(key).cloned().unwrap_or(Value::Null))
+        .map_err(|e| Value::String(format!("Bad argument `{}`: {}", key, e)))
+}
+
+fn to_outcome<T: Serialize, E: Serialize>(result: Result<T, E>) -> Result<Value, Value> {
+    match result {
+        Ok(v) => serde_json::to_value(v).map_err(|e| Value::String(e.to_string())),
+        Err(e) => Err(serde_json::to_value(e).unwrap_or(Value::Null)),
+    }
+}
+
+fn ok<T: Serialize>(value: T) -> Result<Value, Value> {
+    to_outcome::<T, String>(Ok(value))
+}
+
+/// Commands that write files only ever write directly into the work directory.
+fn write_target(path: &str, work_dir: &Path) -> Result<(), Value> {
+    if Path::new(path).parent() == Some(work_dir) {
+        Ok(())
+    } else {
+        Err(Value::String(format!("Refusing to write `{}` outside the replay directory", path)))
+    }
+}
+
+/// Layer and cut ids end up in the .geo script Gmsh runs, so a shared session may only
+/// carry printable ones.
+fn check_geo_ids(layers: &[GeoLayer]) -> Result<(), Message> {
+    let ids = layers.iter().flat_map(|l| std::iter::once(&l.id).chain(l.cuts.iter().map(|c| &c.id)));
+    for id in ids {
+        if id.chars().any(char::is_control) {
+            return Err(Message::new(MessageCode::ReplayInvalidId, format!("Invalid layer or cut id {:?}", id))
+                .with("id", format!("{:?}", id)));
+        }
+    }
+    Ok(())
+}
+
+/// Runs one recorded command; None when it cannot run outside the app.
+fn run_command(entry: &SessionEntry, args: &Value, work_dir: &Path, options: &ReplayOptions) -> Option<Result<Value, Value>> {
+    let run = || -> Result<Value, Value> {
+        match entry.command.as_str() {
+            "export_layer_files" => {
+                let request: ExportRequest = arg(args, "request")?;
+                write_target(&request.filepath, work_dir)?;
+                let written = export::write_layer_file(&request, None).map_err(|e| serde_json::to_value(e).unwrap_or(Value::Null))?;
+                let issues = written.as_ref()
+                    .map(|g| export_verify::verify_export(&request.filepath, &request.file_type, g))
+                    .unwrap_or_default();
+                ok(ExportResult { verified: written.map(|_| issues.is_empty()), issues })
+            }
+            "split_export_request" => to_outcome(split_export::split_export_request(&arg(args, "result")?, &arg(args, "request")?)),
+            "inject_split_cuts" => {
+                let mut layers: Vec<GeoLayer> = arg(args, "layers")?;
+                let kerf = arg::<Option<f64>>(args, "kerf")?.unwrap_or(split_export::DEFAULT_SPLIT_KERF);
+                split_export::inject_split_cuts(&mut layers, &arg::<Vec<_>>(args, "cuts")?, kerf);
+                ok(layers)
+            }
+            "stackup_layers" => to_outcome(export::stackup_layers(&arg::<Vec<_>>(args, "layers")?)),
+            "export_calibration_grid" => {
+                let request: calibration::CalibrationRequest = arg(args, "request")?;
+                write_target(&request.filepath, work_dir)?;
+                to_outcome(calibration::export_calibration_grid(&request))
+            }
+            "verify_depth_map" => to_outcome(depth_map_verify::verify_depth_map(
+                &arg::<String>(args, "path")?, &arg(args, "request")?, arg(args, "tolerance")?, arg(args, "resolution")?,
+            )),
+            "achievable_depth_report" => to_outcome(tool_reach::achievable_depth_report(&arg(args, "request")?, &arg::<Vec<_>>(args, "tools")?)),
+            "estimate_scallops" => to_outcome(scallop::estimate_scallops(
+                &arg(args, "request")?, arg(args, "toolDiameter")?, arg(args, "stepover")?, arg(args, "targetScallop")?,
+            )),
+            "fit_probe_points" => to_outcome(probe_fit::fit_probe_points(
+                &arg::<Vec<_>>(args, "outline")?, &arg::<Vec<_>>(args, "points")?, arg(args, "allowScale")?,
+            )),
+            "trace_image" => to_outcome(trace::trace_image(&arg::<String>(args, "path")?, &arg(args, "options")?)),
+            "compute_smart_split" => ok(optimizer::run_optimization(arg(args, "input")?, optimizer::random_seed())),
+            "get_debug_eval" => ok(optimizer::debug_split_eval(arg(args, "input")?)),
+            "extract_keepouts" => ok(keepout::extract_keepouts(
+                &arg::<Vec<_>>(args, "shapes")?, arg(args, "layerThickness")?, &arg(args, "margins")?,
+            )),
+            "suggest_mesh_size" => to_outcome(mesh_sizing::suggest_mesh_size(&arg(args, "request")?)),
+            "detect_thin_webs" => to_outcome(thin_webs::find_thin_webs(&arg(args, "request")?, arg(args, "threshold")?)),
+            "glue_area_report" => to_outcome(glue_area::glue_area_report(&arg::<Vec<_>>(args, "layers")?, arg(args, "minArea")?)),
+            "balance_report" => to_outcome(balance::balance_report(&arg(args, "request")?)),
+            "cmd_repair_mesh" => to_outcome(tetgen::repair_mesh(arg(args, "vertices")?, arg(args, "targetLen")?)),
+            "cmd_tetrahedralize" => to_outcome(tetgen::tetrahedralize(arg(args, "vertices")?, arg(args, "options")?, arg(args, "targetLen")?)),
+            "run_gmsh_meshing" | "mesh_export_request" => {
+                let gmsh = options.gmsh.as_deref().expect("checked before running");
+                let req: FeaRequest = if entry.command == "run_gmsh_meshing" {
+                    arg(args, "req")?
+                } else {
+                    let layer = export::export_request_layer(&arg(args, "request")?).map_err(|e| serde_json::to_value(e).unwrap_or(Value::Null))?;
+                    FeaRequest::from_layers(vec![layer], arg(args, "quality")?)
+                };
+                check_geo_ids(&req.layers).map_err(|e| serde_json::to_value(e).unwrap_or(Value::Null))?;
+                let run_dir = work_dir.join(format!("mesh_{}", entry.seq));
+                let out = gmsh_interop::run_gmsh_pipeline(gmsh, &req, &run_dir).map_err(|e| serde_json::to_value(e).unwrap_or(Value::Null))?;
+                ok(serde_json::json!({
+                    "nodes": out.mesh.vertices.len(),
+                    "elements": out.mesh.indices.len(),
+                    "volume": out.volume,
+                    "surface_area": out.surface_area,
+                    "report": gmsh_interop::build_report(&req, out.volume, out.surface_area),
+                    "element_quality": out.element_quality,
+                }))
+            }
+            _ => unreachable!("filtered by is_replayable"),
+        }
+    };
+
+    is_replayable(&entry.command, options).then(run)
+}
+
+fn is_replayable(command: &str, options: &ReplayOptions) -> bool {
+    match command {
+        "run_gmsh_meshing" | "mesh_export_request" => options.gmsh.is_some(),
+        "export_layer_files" | "split_export_request" | "inject_split_cuts" | "stackup_layers" | "export_calibration_grid"
+        | "verify_depth_map" | "achievable_depth_report" | "estimate_scallops" | "fit_probe_points" | "trace_image"
+        | "compute_smart_split" | "get_debug_eval" | "extract_keepouts" | "suggest_mesh_size" | "detect_thin_webs"
+        | "glue_area_report" | "balance_report" | "cmd_repair_mesh" | "cmd_tetrahedralize" => true,
+        _ => false,
+    }
+}
+
+/// Re-executes `entries` in order with `$DIR` mapped to `work_dir`. A command that panics is
+/// reported as an error and the replay continues.
+pub fn replay(entries: &[SessionEntry], work_dir: &Path, options: &ReplayOptions) -> Result<ReplayReport, Message> {
+    std::fs::create_dir_all(work_dir).map_err(|e| Message::write_failed(work_dir, e))?;
+
+    let mut outcomes = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let mut args = entry.args.clone();
+        let start = Instant::now();
+        let result = match restore_paths(&entry.command, &mut args, work_dir) {
+            Err(e) => Some(Err(Value::String(e))),
+            Ok(()) => std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| run_command(entry, &args, work_dir, options)))
+                .unwrap_or_else(|_| Some(Err(Value::String(format!("{} panicked", entry.command))))),
+        };
+        let (status, result, error) = match result {
+            None => (ReplayStatus::Skipped, None, None),
+            Some(Ok(v)) => (ReplayStatus::Ok, Some(v), None),
+            Some(Err(e)) => (ReplayStatus::Error, None, Some(e)),
+        };
+        outcomes.push(ReplayOutcome {
+            seq: entry.seq,
+            command: entry.command.clone(),
+            status,
+            result,
+            error,
+            millis: start.elapsed().as_millis() as u64,
+        });
+    }
+
+    Ok(ReplayReport {
+        work_dir: work_dir.to_string_lossy().into_owned(),
+        errors: outcomes.iter().filter(|o| o.status == ReplayStatus::Error).count(),
+        skipped: outcomes.iter().filter(|o| o.status == ReplayStatus::Skipped).count(),
+        outcomes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shortstack_session_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn layer_args(filepath: &str) -> Value {
+        json!({
+            "request": {
+                "filepath": filepath,
+                "file_type": "SVG",
+                "machining_type": "Cut",
+                "cut_direction": "Top",
+                "outline": [
+                    { "x": 0.0, "y": 0.0, "handle_in": null, "handle_out": null },
+                    { "x": 20.0, "y": 0.0, "handle_in": null, "handle_out": null },
+                    { "x": 20.0, "y": 10.0, "handle_in": null, "handle_out": null },
+                ],
+                "shapes": [],
+                "layer_thickness": 3.0,
+                "stl_content": null,
+            }
+        })
+    }
+
+    #[test]
+    fn test_scrub_only_path_fields() {
+        let mut scrubber = PathScrubber::default();
+        let mut args = json!({ "path": "/home/user/scan.png", "options": { "label": "/not/a/path" } });
+        scrubber.scrub("trace_image", &mut args);
+        assert_eq!(args["path"], "$DIR/scan.png");
+        assert_eq!(args["options"]["label"], "/not/a/path");
+
+        // Commands without path fields are recorded as sent
+        let mut args = json!({ "name": "/top/layer" });
+        scrubber.scrub("fit_probe_points", &mut args);
+        assert_eq!(args["name"], "/top/layer");
+    }
+
+    #[test]
+    fn test_scrub_keeps_names_unique() {
+        let mut scrubber = PathScrubber::default();
+        let mut a = layer_args("/home/user/a/out.svg");
+        let mut b = layer_args("C:\\Users\\user\\b\\out.svg");
+        let mut a_again = layer_args("/home/user/a/out.svg");
+        scrubber.scrub("export_layer_files", &mut a);
+        scrubber.scrub("export_layer_files", &mut b);
+        scrubber.scrub("export_layer_files", &mut a_again);
+        assert_eq!(a["request"]["filepath"], "$DIR/out.svg");
+        assert_eq!(b["request"]["filepath"], "$DIR/out_2.svg");
+        assert_eq!(a_again["request"]["filepath"], "$DIR/out.svg");
+
+        let mut layers = json!({ "layers": [layer_args("/x/top.svg")["request"], layer_args("/y/top.svg")["request"]] });
+        scrubber.scrub("glue_area_report", &mut layers);
+        assert_eq!(layers["layers"][0]["filepath"], "$DIR/top.svg");
+        assert_eq!(layers["layers"][1]["filepath"], "$DIR/top_2.svg");
+    }
+
+    #[test]
+    fn test_restore_roundtrip() {
+        let dir = scratch_dir("restore");
+        let mut scrubber = PathScrubber::default();
+        let mut args = json!({ "path": "/data/depth.png", "request": { "filepath": "" } });
+        scrubber.scrub("verify_depth_map", &mut args);
+        restore_paths("verify_depth_map", &mut args, &dir).unwrap();
+        assert_eq!(args["path"], dir.join("depth.png").to_string_lossy().as_ref());
+        assert_eq!(args["request"]["filepath"], "");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_restore_rejects_traversal() {
+        let dir = scratch_dir("traversal");
+        for bad in ["$DIR/../../.bashrc", "$DIR/..", "$DIR/a/b.svg", "$DIR/a\\b.svg", "$DIR/", "/etc/passwd", "out.svg", "$DIRout.svg"] {
+            let mut args = json!({ "path": bad });
+            assert!(restore_paths("trace_image", &mut args, &dir).is_err(), "{} was accepted", bad);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_restore_rejects_symlink_out_of_work_dir() {
+        let dir = scratch_dir("symlink");
+        let outside = scratch_dir("symlink_outside");
+        std::os::unix::fs::symlink(outside.join("target.svg"), dir.join("out.svg")).unwrap();
+        let mut args = layer_args("$DIR/out.svg");
+        assert!(restore_paths("export_layer_files", &mut args, &dir).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+        std::fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn test_recorder_skips_not_recorded() {
+        let dir = scratch_dir("recorder");
+        let recorder = SessionRecorder::new(Some(dir.clone()));
+        recorder.record("export_layer_files", &layer_args("/home/user/idle.svg")); // Not started yet
+        let path = recorder.start().unwrap();
+        recorder.record("start_session_recording", &json!({}));
+        recorder.record("append_export_stream", &json!({ "id": 1 }));
+        recorder.record("export_layer_files", &layer_args("/home/user/top.svg"));
+        assert_eq!(recorder.stop(), Some(path.clone()));
+        assert!(!recorder.is_recording());
+
+        let entries = load_session(&path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].command, "export_layer_files");
+        assert_eq!(entries[0].args["request"]["filepath"], "$DIR/top.svg");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_stays_in_work_dir() {
+        let dir = scratch_dir("replay");
+        let work_dir = dir.join("work");
+        let entry = |seq, command: &str, args| SessionEntry { seq, elapsed_ms: 0, command: command.into(), args };
+        let entries = vec![
+            entry(0, "export_layer_files", layer_args("$DIR/top.svg")),
+            entry(1, "export_layer_files", layer_args("$DIR/../escaped.svg")),
+            entry(2, "list_approved_directories", json!({})),
+        ];
+        let report = replay(&entries, &work_dir, &ReplayOptions::default()).unwrap();
+
+        let statuses: Vec<_> = report.outcomes.iter().map(|o| o.status).collect();
+        assert_eq!(statuses, [ReplayStatus::Ok, ReplayStatus::Error, ReplayStatus::Skipped]);
+        assert!(work_dir.join("top.svg").exists());
+        assert!(!dir.join("escaped.svg").exists());
+        assert_eq!((report.errors, report.skipped), (1, 1));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_reports_failed_export() {
+        let dir = scratch_dir("failed_export");
+        let mut args = layer_args("$DIR/top.stl");
+        args["request"]["file_type"] = json!("STL");
+        let entries = vec![SessionEntry { seq: 0, elapsed_ms: 0, command: "export_layer_files".into(), args }];
+        let report = replay(&entries, &dir.join("work"), &ReplayOptions::default()).unwrap();
+
+        assert_eq!(report.outcomes[0].status, ReplayStatus::Error);
+        assert_eq!(report.outcomes[0].error.as_ref().unwrap()["code"], "STL_CONTENT_MISSING");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_replay_rejects_hostile_geo_ids() {
+        let dir = scratch_dir("hostile");
+        let layer = json!({
+            "id": "top\nSystem \"touch pwned\";",
+            "z": 0.0,
+            "thickness": 3.0,
+            "outline": [[0.0, 0.0], [10.0, 0.0], [10.0, 10.0]],
+        });
+        let req = json!({ "footprint": null, "stackup": [], "params": [], "quality": 1.0, "layers": [layer] });
+        let entries = vec![SessionEntry { seq: 0, elapsed_ms: 0, command: "run_gmsh_meshing".into(), args: json!({ "req": req }) }];
+        // Gmsh is never reached, so any path will do
+        let options = ReplayOptions { gmsh: Some(dir.join("gmsh")) };
+        let report = replay(&entries, &dir.join("work"), &options).unwrap();
+
+        assert_eq!(report.outcomes[0].status, ReplayStatus::Error);
+        assert_eq!(report.outcomes[0].error.as_ref().unwrap()["code"], "REPLAY_INVALID_ID");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
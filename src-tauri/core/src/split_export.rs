@@ -0,0 +1,306 @@
+//! Turns an optimizer split into two standalone export requests, one per part, or into
+//! the slot between the parts for meshing.
+use crate::fem::geo_builder::{GeoCut, GeoLayer, SPLIT_CUT_PREFIX};
+use crate::geometry::{GeneratedCut, OptimizationResult};
+use crate::messages::{Message, MessageCode};
+use crate::export::{ExportPoint, ExportRequest, ExportShape, discretize_path_closed, shape_to_polygon};
+use csgrs::sketch::Sketch;
+use csgrs::traits::CSG;
+use geo::{Area, BoundingRect, Coord, Intersects, LineString, Point, Polygon};
+use std::path::Path;
+
+/// Outcome of `split_export_request`.
+#[derive(Debug, serde::Serialize)]
+pub struct SplitExport {
+    /// One per region between the cuts. Each cut keeps the number of the part it splits
+    /// for its socket side and appends its tail side, so a single cut gives socket, tail.
+    pub parts: Vec<ExportRequest>,
+    /// Pieces of a part that were disconnected from its largest piece and left out of it
+    pub dropped: Vec<DroppedFragment>,
+}
+
+/// A disconnected piece of a split part that no export request covers.
+#[derive(Debug, serde::Serialize)]
+pub struct DroppedFragment {
+    /// Part it broke off from, numbered from 1 like `parts`
+    pub part: usize,
+    /// Fragment area (mm²)
+    pub area: f64,
+    /// Fragment exterior ring
+    pub outline: Vec<[f64; 2]>,
+}
+
+/// Splits `request` along every cut of `result`, in order.
+/// Each returned request carries its part's outline (dovetails included) and only
+/// the shapes touching that part. Shapes straddling a cut go to both parts;
+/// the exporters clip them to the outline anyway.
+/// Each side of a cut uses its own filleted cut path so the parts mate with the
+/// requested clearance. A cut only splits the parts it crosses; a cut that crosses
+/// none of them is an error.
+/// When a cut leaves a part in several pieces only the largest is exported; the
+/// others are listed in `dropped`.
+pub fn split_export_request(result: &OptimizationResult, request: &ExportRequest) -> Result<SplitExport, Message> {
+    if result.shapes.is_empty() {
+        return Err(Message::new(MessageCode::SplitNoCuts, "Optimization result contains no cuts"));
+    }
+    if request.outline.is_empty() {
+        return Err(Message::outline_missing());
+    }
+
+    let board_poly = Polygon::new(discretize_path_closed(&request.outline), vec![]);
+    let mut parts = vec![board_poly.clone()];
+    let mut dropped = Vec::new();
+
+    for cut in &result.shapes {
+        let socket_sketch = Sketch::from_geo(geo::Geometry::Polygon(half_plane_polygon(cut, &cut.socket_polyline(), &board_poly)).into(), None);
+        let tail_sketch = Sketch::from_geo(geo::Geometry::Polygon(half_plane_polygon(cut, &cut.tail_polyline(), &board_poly)).into(), None);
+
+        let mut crossed = false;
+        for index in 0..parts.len() {
+            let part_sketch = Sketch::from_geo(geo::Geometry::Polygon(parts[index].clone()).into(), None);
+            // A part entirely on one side of this cut stays whole
+            let (Some((socket, rest_socket)), Some((tail, rest_tail))) = (
+                largest_polygon(&part_sketch.intersection(&socket_sketch)),
+                largest_polygon(&part_sketch.difference(&tail_sketch)),
+            ) else { continue };
+
+            crossed = true;
+            parts[index] = socket;
+            parts.push(tail);
+            dropped.extend(rest_socket.into_iter().map(|p| (index + 1, p)));
+            dropped.extend(rest_tail.into_iter().map(|p| (parts.len(), p)));
+        }
+        if !crossed {
+            return Err(Message::new(MessageCode::SplitMissesBoard, format!("Cut {} does not cross the board", cut.id))
+                .with("cut", cut.id.as_str()));
+        }
+    }
+
+    Ok(SplitExport {
+        parts: parts.iter().enumerate().map(|(i, part)| part_request(request, part, i + 1)).collect(),
+        dropped: dropped.into_iter()
+            .map(|(part, poly)| DroppedFragment {
+                part,
+                area: poly.unsigned_area(),
+                outline: poly.exterior().0.iter().map(|c| [c.x, c.y]).collect(),
+            })
+            .collect(),
+    })
+}
+
+/// Gap (mm) left between split parts for meshing when none is given; matches the
+/// frontend's default split kerf.
+pub const DEFAULT_SPLIT_KERF: f64 = 0.5;
+
+/// Material removed along `cut` inside `board`: the joint clearance between the socket
+/// and tail paths, widened by `kerf` so the two parts never share a face.
+/// Everything outside it belongs to exactly one of the parts `split_export_request` makes.
+pub fn split_slot(cut: &GeneratedCut, board: &Polygon<f64>, kerf: f64) -> Vec<Polygon<f64>> {
+    let to_sketch = |p: Polygon<f64>| Sketch::<()>::from_geo(geo::Geometry::Polygon(p).into(), None);
+    let board_sketch = to_sketch(board.clone());
+    // Part 1 keeps the socket side, part 2 everything outside the tail side
+    let socket_side = to_sketch(half_plane_polygon(cut, &cut.socket_polyline(), board)).offset(-kerf / 2.0);
+    let tail_side = to_sketch(half_plane_polygon(cut, &cut.tail_polyline(), board)).offset(kerf / 2.0);
+
+    let mut slot = Vec::new();
+    for geom in board_sketch.intersection(&tail_side.difference(&socket_side)).geometry {
+        match geom {
+            geo::Geometry::Polygon(p) => slot.push(p),
+            geo::Geometry::MultiPolygon(mp) => slot.extend(mp.0),
+            _ => {}
+        }
+    }
+    slot.retain(|p| p.unsigned_area() > 1e-9);
+    slot
+}
+
+/// Adds the slot of every accepted cut to each layer as through cuts named
+/// `temp_split_<cut id>_<n>`, so the mesher builds the parts as separate bodies.
+/// Cuts that miss a layer's outline leave it untouched.
+pub fn inject_split_cuts(layers: &mut [GeoLayer], cuts: &[GeneratedCut], kerf: f64) {
+    for layer in layers {
+        let outline = layer.polygon();
+        for cut in cuts {
+            for (n, poly) in split_slot(cut, &outline, kerf).into_iter().enumerate() {
+                let ring = |ls: &LineString<f64>| ls.0.iter().map(|c| [c.x, c.y]).collect::<Vec<_>>();
+                layer.cuts.push(GeoCut {
+                    id: format!("{}{}_{}", SPLIT_CUT_PREFIX, cut.id, n),
+                    exterior: ring(poly.exterior()),
+                    interiors: poly.interiors().iter().map(ring).collect(),
+                    depth: layer.thickness,
+                    from_bottom: false,
+                });
+            }
+        }
+    }
+}
+
+/// Polygon covering everything on the dovetail-normal side of the cut `path`.
+/// The straight ends are extended well past the board so the region fully bisects it.
+fn half_plane_polygon(cut: &GeneratedCut, path: &[Point<f64>], board: &Polygon<f64>) -> Polygon<f64> {
+    let p1 = path[0];
+    let p2 = path[path.len() - 1];
+
+    let extent = board.bounding_rect()
+        .map(|r| r.width() + r.height())
+        .unwrap_or(1000.0) * 2.0 + 1.0;
+
+    let dx = p2.x() - p1.x();
+    let dy = p2.y() - p1.y();
+    let len = (dx * dx + dy * dy).sqrt().max(1e-9);
+    let (ux, uy) = (dx / len, dy / len);
+    let (vx, vy) = if cut.flipped { (uy, -ux) } else { (-uy, ux) };
+
+    let far_l = Coord { x: p1.x() - ux * extent, y: p1.y() - uy * extent };
+    let far_r = Coord { x: p2.x() + ux * extent, y: p2.y() + uy * extent };
+
+    let mut coords = vec![far_l];
+    coords.extend(path[1..path.len() - 1].iter().map(|p| p.0));
+    coords.extend([
+        far_r,
+        Coord { x: far_r.x + vx * extent, y: far_r.y + vy * extent },
+        Coord { x: far_l.x + vx * extent, y: far_l.y + vy * extent },
+        far_l,
+    ]);
+    Polygon::new(LineString::new(coords), vec![])
+}
+
+/// Largest non-degenerate polygon of `sketch`, and the other non-degenerate ones.
+fn largest_polygon(sketch: &Sketch<()>) -> Option<(Polygon<f64>, Vec<Polygon<f64>>)> {
+    let mut polys = Vec::new();
+    for geom in &sketch.geometry {
+        match geom {
+            geo::Geometry::Polygon(p) => polys.push(p.clone()),
+            geo::Geometry::MultiPolygon(mp) => polys.extend(mp.0.iter().cloned()),
+            _ => {}
+        }
+    }
+    polys.retain(|p| p.unsigned_area() > 1e-9);
+    let largest = polys.iter().enumerate()
+        .max_by(|(_, a), (_, b)| a.unsigned_area().partial_cmp(&b.unsigned_area()).unwrap_or(std::cmp::Ordering::Equal))
+        .map(|(i, _)| i)?;
+    let kept = polys.swap_remove(largest);
+    Some((kept, polys))
+}
+
+fn part_request(request: &ExportRequest, part: &Polygon<f64>, index: usize) -> ExportRequest {
+    let mut coords = &part.exterior().0[..];
+    if coords.len() > 1 && coords.first() == coords.last() {
+        coords = &coords[..coords.len() - 1];
+    }
+    let outline: Vec<ExportPoint> = coords.iter()
+        .map(|c| ExportPoint { x: c.x, y: c.y, handle_in: None, handle_out: None })
+        .collect();
+
+    let shapes: Vec<ExportShape> = request.shapes.iter()
+        .filter(|s| shape_to_polygon(s).is_none_or(|poly| poly.intersects(part)))
+        .cloned()
+        .collect();
+
+    ExportRequest {
+        filepath: part_filepath(&request.filepath, index),
+        file_type: request.file_type.clone(),
+        machining_type: request.machining_type.clone(),
+        cut_direction: request.cut_direction.clone(),
+        outline,
+        shapes,
+        layer_thickness: request.layer_thickness,
+        // Pre-computed meshes describe the whole layer and cannot be reused per part
+        stl_content: None,
+        project_id: request.project_id.clone(),
+        dxf_hatch: request.dxf_hatch,
+    }
+}
+
+/// "board.dxf" -> "board_Part1.dxf", matching the frontend's multi-part naming.
+fn part_filepath(filepath: &str, index: usize) -> String {
+    let path = Path::new(filepath);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}_Part{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}_Part{}", stem, index),
+    };
+    path.with_file_name(name).to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(outline: &[(f64, f64)]) -> ExportRequest {
+        ExportRequest {
+            filepath: "/tmp/board.dxf".into(),
+            file_type: "DXF".into(),
+            machining_type: "Cut".into(),
+            cut_direction: "Top".into(),
+            outline: outline.iter().map(|&(x, y)| ExportPoint { x, y, handle_in: None, handle_out: None }).collect(),
+            shapes: vec![],
+            layer_thickness: 3.0,
+            stl_content: None,
+            project_id: None,
+            dxf_hatch: false,
+        }
+    }
+
+    fn result(cuts: &[([f64; 2], [f64; 2])]) -> OptimizationResult {
+        let shapes = cuts.iter().enumerate().map(|(i, &(start, end))| GeneratedCut {
+            id: format!("c{}", i),
+            start,
+            end,
+            dovetail_width: 10.0,
+            dovetail_height: 5.0,
+            dovetail_t: 0.5,
+            flipped: false,
+            anchors: None,
+            fillet_radius: 0.0,
+            joint_clearance: 0.0,
+            robustness: None,
+        }).collect();
+        OptimizationResult { success: true, cost: 0.0, shapes, robustness: None, run_id: None }
+    }
+
+    fn part_area(part: &ExportRequest) -> f64 {
+        Polygon::new(discretize_path_closed(&part.outline), vec![]).unsigned_area()
+    }
+
+    #[test]
+    fn test_split_reports_dropped_fragments() {
+        // U-shaped board; a cut across both arms leaves the socket side in two pieces
+        let request = request(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (70.0, 100.0), (70.0, 30.0), (30.0, 30.0), (30.0, 100.0), (0.0, 100.0)]);
+        let split = split_export_request(&result(&[([-10.0, 60.0], [110.0, 60.0])]), &request).unwrap();
+        assert_eq!(split.parts.len(), 2);
+        assert_eq!(split.dropped.len(), 1);
+        assert_eq!(split.dropped[0].part, 1);
+        assert!((split.dropped[0].area - 1200.0).abs() < 1.0, "area {}", split.dropped[0].area);
+    }
+
+    #[test]
+    fn test_split_along_every_cut() {
+        let square = request(&[(0.0, 0.0), (120.0, 0.0), (120.0, 120.0), (0.0, 120.0)]);
+
+        // Two parallel cuts give three strips; the dovetails only move area between neighbours
+        let split = split_export_request(&result(&[([40.0, -10.0], [40.0, 130.0]), ([80.0, -10.0], [80.0, 130.0])]), &square).unwrap();
+        assert_eq!(split.parts.len(), 3);
+        assert!(split.dropped.is_empty());
+        let areas: Vec<f64> = split.parts.iter().map(part_area).collect();
+        assert!((areas.iter().sum::<f64>() - 14400.0).abs() < 1.0, "areas {:?}", areas);
+        for area in &areas {
+            assert!((area - 4800.0).abs() < 100.0, "areas {:?}", areas);
+        }
+        let names: Vec<&str> = split.parts.iter().map(|p| p.filepath.as_str()).collect();
+        assert_eq!(names, ["/tmp/board_Part1.dxf", "/tmp/board_Part2.dxf", "/tmp/board_Part3.dxf"]);
+
+        // Crossing cuts give four quarters
+        let split = split_export_request(&result(&[([60.0, -10.0], [60.0, 130.0]), ([-10.0, 30.0], [130.0, 30.0])]), &square).unwrap();
+        assert_eq!(split.parts.len(), 4);
+        assert!((split.parts.iter().map(part_area).sum::<f64>() - 14400.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_cut_off_the_board_is_an_error() {
+        let square = request(&[(0.0, 0.0), (100.0, 0.0), (100.0, 100.0), (0.0, 100.0)]);
+        let err = split_export_request(&result(&[([50.0, -10.0], [50.0, 110.0]), ([200.0, -10.0], [200.0, 110.0])]), &square).unwrap_err();
+        assert_eq!(err.code, MessageCode::SplitMissesBoard);
+        assert_eq!(err.params["cut"], "c1");
+    }
+}
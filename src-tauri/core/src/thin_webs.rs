@@ -0,0 +1,126 @@
+//! Finds material webs narrower than a threshold between cuts and the board outline.
+use crate::export::{ExportRequest, discretize_path_closed, shape_to_polygon};
+use crate::messages::Message;
+use csgrs::sketch::Sketch;
+use csgrs::traits::CSG;
+use geo::{BoundingRect, Centroid, Distance, Euclidean, Polygon, Rect};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Material between two features that is narrower than the minimum web width.
+#[derive(Debug, Serialize, Clone)]
+pub struct ThinWeb {
+    /// Centre of the overlap region
+    pub x: f64,
+    /// See `x`
+    pub y: f64,
+    /// Narrowest material between the two features
+    pub width: f64,
+    /// Index into request.shapes
+    pub shape_a: usize,
+    /// Other shape, or None for the board outline
+    pub shape_b: Option<usize>,
+    /// min_x, min_y, max_x, max_y of the overlap region
+    pub bounds: [f64; 4],
+}
+
+/// Two features closer than the search width; `b == None` means the board outline.
+pub struct WebCandidate {
+    /// Tag of the first cut
+    pub a: usize,
+    /// Other cut's tag, or None for the board outline
+    pub b: Option<usize>,
+    /// Narrowest distance between the two
+    pub width: f64,
+}
+
+/// Gaps between every pair of cuts and between each cut and the outline that are
+/// narrower than `max_width`. Overlapping cuts merge into one opening and are skipped.
+pub fn web_candidates(board: &Polygon<f64>, cuts: &[(usize, Polygon<f64>)], max_width: f64) -> Vec<WebCandidate> {
+    let boxes: Vec<Option<Rect<f64>>> = cuts.iter().map(|(_, p)| p.bounding_rect()).collect();
+    let mut out = Vec::new();
+
+    for (i, (a_idx, a)) in cuts.iter().enumerate() {
+        let d = Euclidean::distance(board.exterior(), a);
+        if d > 1e-6 && d < max_width {
+            out.push(WebCandidate { a: *a_idx, b: None, width: d });
+        }
+
+        for (j, (b_idx, b)) in cuts.iter().enumerate().skip(i + 1) {
+            if let (Some(ra), Some(rb)) = (boxes[i], boxes[j]) && !rects_within(ra, rb, max_width) {
+                continue;
+            }
+            let d = Euclidean::distance(a, b);
+            if d > 1e-6 && d < max_width {
+                out.push(WebCandidate { a: *a_idx, b: Some(*b_idx), width: d });
+            }
+        }
+    }
+    out
+}
+
+/// Grows every cut by half the threshold and shrinks the outline by the same amount.
+/// Wherever two grown cuts overlap, or a grown cut pokes outside the shrunk outline,
+/// the web between them is thinner than `threshold`.
+pub fn find_thin_webs(request: &ExportRequest, threshold: f64) -> Result<Vec<ThinWeb>, Message> {
+    if request.outline.is_empty() {
+        return Err(Message::outline_missing());
+    }
+    if threshold <= 0.0 {
+        return Err(Message::not_positive("threshold", "Threshold must be positive"));
+    }
+
+    let board = Polygon::new(discretize_path_closed(&request.outline), vec![]);
+    let cuts: Vec<(usize, Polygon<f64>)> = request.shapes.iter().enumerate()
+        .filter(|(_, s)| s.depth > 1e-6)
+        .filter_map(|(i, s)| shape_to_polygon(s).map(|p| (i, p)))
+        .collect();
+
+    let half = threshold / 2.0;
+    let to_sketch = |p: &Polygon<f64>| Sketch::<()>::from_geo(geo::Geometry::Polygon(p.clone()).into(), None);
+    let grown: HashMap<usize, Sketch<()>> = cuts.iter()
+        .map(|(i, p)| (*i, to_sketch(p).offset_rounded(half)))
+        .collect();
+    let board_sketch = to_sketch(&board);
+    let shrunk_board = board_sketch.offset(-half);
+
+    let mut webs = Vec::new();
+    for candidate in web_candidates(&board, &cuts, threshold) {
+        let region = match candidate.b {
+            Some(b) => grown[&candidate.a].intersection(&grown[&b]),
+            // Clip to the board so the reported location sits on the web itself
+            None => grown[&candidate.a].difference(&shrunk_board).intersection(&board_sketch),
+        };
+        let Some(web) = region_to_web(&region, &candidate) else { continue };
+        webs.push(web);
+    }
+    Ok(webs)
+}
+
+fn region_to_web(region: &Sketch<()>, candidate: &WebCandidate) -> Option<ThinWeb> {
+    let mut polys = Vec::new();
+    for geom in &region.geometry {
+        match geom {
+            geo::Geometry::Polygon(p) => polys.push(p.clone()),
+            geo::Geometry::MultiPolygon(mp) => polys.extend(mp.0.iter().cloned()),
+            _ => {}
+        }
+    }
+    let mp = geo::MultiPolygon::new(polys);
+    let centroid = mp.centroid()?;
+    let rect = mp.bounding_rect()?;
+
+    Some(ThinWeb {
+        x: centroid.x(),
+        y: centroid.y(),
+        width: candidate.width,
+        shape_a: candidate.a,
+        shape_b: candidate.b,
+        bounds: [rect.min().x, rect.min().y, rect.max().x, rect.max().y],
+    })
+}
+
+fn rects_within(a: Rect<f64>, b: Rect<f64>, gap: f64) -> bool {
+    a.min().x - gap <= b.max().x && b.min().x - gap <= a.max().x
+        && a.min().y - gap <= b.max().y && b.min().y - gap <= a.max().y
+}
@@ -0,0 +1,343 @@
+//! Compares a carve layer's designed depth map with what a tool library can actually cut:
+//! the floor each visible depth region really gets, the smallest internal corner it leaves,
+//! and per shape the patches that stay short of their designed depth.
+use crate::export::{ExportRequest, get_depth_regions, shape_to_polygon};
+use crate::messages::{Message, MessageCode};
+use csgrs::sketch::Sketch;
+use csgrs::traits::CSG;
+use geo::{Area, BoundingRect, MultiPolygon};
+use serde::{Deserialize, Serialize};
+
+/// Unreachable slivers smaller than this (mm²) are offset/discretisation noise.
+const MIN_PATCH_AREA: f64 = 0.01;
+
+/// One cutter from the tool library.
+#[derive(Debug, Deserialize, Clone)]
+pub struct Tool {
+    /// Display name; defaults to kind and diameter
+    #[serde(default)]
+    pub name: Option<String>,
+    /// "flat", "ball", "vbit"
+    pub kind: String,
+    /// Cutting diameter (mm)
+    pub diameter: f64,
+    /// V-bit included angle (degrees); defaults to 90
+    #[serde(default)]
+    pub angle: Option<f64>,
+    /// Usable flute length (mm)
+    #[serde(default)]
+    pub max_depth: Option<f64>,
+}
+
+impl Tool {
+    fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| format!("{} {}mm", self.kind, self.diameter))
+    }
+
+    fn half_angle(&self) -> f64 {
+        (self.angle.unwrap_or(90.0) / 2.0).to_radians()
+    }
+}
+
+/// How one visible depth region comes out with the best tool for it.
+#[derive(Debug, Serialize, Clone)]
+pub struct RegionReach {
+    /// Designed depth (mm)
+    pub depth: f64,
+    /// Label of the tool chosen for this region
+    pub tool: String,
+    /// Deepest the tool gets where it reaches
+    pub achievable_depth: f64,
+    /// Plan-view corner radius left in internal corners
+    pub min_internal_radius: f64,
+    /// Floor-to-wall fillet (ball nose)
+    pub floor_fillet_radius: f64,
+    /// Floor at full achievable depth (mm²); 0 for V-bits
+    pub flat_floor_area: f64,
+    /// Visible area of the region (mm²)
+    pub area: f64,
+    /// Left above the achievable depth (corners, narrow slots)
+    pub unreachable_area: f64,
+}
+
+/// Part of a shape that does not reach its designed depth.
+#[derive(Debug, Serialize, Clone)]
+pub struct ShapeDeviation {
+    /// Bounding box, board coordinates (mm)
+    pub min: [f64; 2],
+    /// See `min`
+    pub max: [f64; 2],
+    /// Patch area (mm²)
+    pub area: f64,
+    /// Depth the shape asks for (mm)
+    pub design_depth: f64,
+    /// Depth the floor actually reaches over this patch
+    pub achieved_depth: f64,
+}
+
+/// How one shape comes out with the tool chosen for its depth region.
+#[derive(Debug, Serialize, Clone)]
+pub struct ShapeReach {
+    /// Position in the request's shapes
+    pub index: usize,
+    /// Copied from the shape, for display
+    pub shape_type: String,
+    /// Shape center x (mm)
+    pub x: f64,
+    /// Shape center y (mm)
+    pub y: f64,
+    /// Depth the shape asks for (mm)
+    pub design_depth: f64,
+    /// Deepest the chosen tool gets where it reaches (mm)
+    pub achieved_depth: f64,
+    /// Label of the chosen tool
+    pub tool: String,
+    /// See `RegionReach::min_internal_radius`
+    pub min_internal_radius: f64,
+    /// See `RegionReach::floor_fillet_radius`
+    pub floor_fillet_radius: f64,
+    /// Area where this shape sets the floor (not covered by deeper shapes)
+    pub visible_area: f64,
+    /// Patches that stay short of `design_depth`
+    pub deviations: Vec<ShapeDeviation>,
+    /// True when there are no deviations
+    pub ok: bool,
+}
+
+/// Result of `achievable_depth_report` for one carve layer.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReachReport {
+    /// One entry per visible depth region
+    pub regions: Vec<RegionReach>,
+    /// One entry per shape that sets part of the floor
+    pub shapes: Vec<ShapeReach>,
+}
+
+fn to_sketch(mp: &MultiPolygon<f64>) -> Sketch<()> {
+    Sketch::from_geo(geo::Geometry::MultiPolygon(mp.clone()).into(), None)
+}
+
+fn to_multipolygon(sketch: &Sketch<()>) -> MultiPolygon<f64> {
+    let mut polys = Vec::new();
+    for geom in &sketch.geometry {
+        match geom {
+            geo::Geometry::Polygon(p) => polys.push(p.clone()),
+            geo::Geometry::MultiPolygon(mp) => polys.extend(mp.0.iter().cloned()),
+            _ => {}
+        }
+    }
+    polys.retain(|p| p.unsigned_area() > 1e-9);
+    MultiPolygon::new(polys)
+}
+
+/// Everything a disk of radius `r` can sweep while staying inside `region`
+/// (erode, then dilate with round corners).
+fn opening(region: &MultiPolygon<f64>, r: f64) -> MultiPolygon<f64> {
+    if r <= 0.0 {
+        return region.clone();
+    }
+    to_multipolygon(&to_sketch(region).offset(-r).offset_rounded(r))
+}
+
+/// Radius of the largest circle that fits in `region`, by bisecting on erosion.
+fn inscribed_radius(region: &MultiPolygon<f64>) -> f64 {
+    let Some(bounds) = region.bounding_rect() else { return 0.0 };
+    let sketch = to_sketch(region);
+    let (mut lo, mut hi) = (0.0, bounds.width().min(bounds.height()) / 2.0);
+    for _ in 0..20 {
+        let mid = 0.5 * (lo + hi);
+        if to_multipolygon(&sketch.offset(-mid)).0.is_empty() { hi = mid; } else { lo = mid; }
+    }
+    lo
+}
+
+/// The region as cut by `tool`: (achievable depth, reachable floor, unreachable part).
+fn cut_with(tool: &Tool, region: &MultiPolygon<f64>, depth: f64) -> (f64, MultiPolygon<f64>, MultiPolygon<f64>) {
+    let reach = tool.max_depth.map_or(depth, |m| depth.min(m));
+    if tool.kind == "vbit" {
+        // A V-bit only reaches full depth along the region's medial axis; no flat floor
+        let achievable = reach.min(inscribed_radius(region) / tool.half_angle().tan());
+        return (achievable, MultiPolygon::new(vec![]), region.clone());
+    }
+    let floor = opening(region, tool.diameter / 2.0);
+    let unreachable = to_multipolygon(&to_sketch(region).difference(&to_sketch(&floor)));
+    let unreachable = MultiPolygon::new(unreachable.0.into_iter().filter(|p| p.unsigned_area() >= MIN_PATCH_AREA).collect());
+    (reach, floor, unreachable)
+}
+
+/// Picks, per visible depth region of the carve, the tool that leaves the least material
+/// (deepest first, then least unreachable area, then the largest tool), and reports each
+/// shape's deviations from its designed depth.
+pub fn achievable_depth_report(request: &ExportRequest, tools: &[Tool]) -> Result<ReachReport, Message> {
+    if tools.is_empty() {
+        return Err(Message::new(MessageCode::ToolLibraryEmpty, "Tool library is empty"));
+    }
+    if let Some(t) = tools.iter().find(|t| t.diameter <= 0.0 || !["flat", "ball", "vbit"].contains(&t.kind.as_str())) {
+        return Err(Message::new(
+            MessageCode::InvalidTool,
+            format!("Invalid tool '{}': kind must be flat, ball or vbit with a positive diameter", t.label()),
+        ).with("tool", t.label()));
+    }
+    let thickness = request.layer_thickness;
+    let (board, regions) = get_depth_regions(request).ok_or_else(Message::outline_missing)?;
+    let board_mp = to_sketch(&MultiPolygon::new(vec![board]));
+
+    struct Cut {
+        depth: f64,
+        region: MultiPolygon<f64>,
+        tool: usize,
+        achievable: f64,
+        unreachable: MultiPolygon<f64>,
+    }
+
+    let mut report_regions = Vec::new();
+    let mut cuts = Vec::new();
+    for (depth, region) in regions {
+        let depth = depth.clamp(0.0, thickness);
+        let area = region.unsigned_area();
+        let best = tools.iter().enumerate()
+            .map(|(i, t)| (i, cut_with(t, &region, depth)))
+            .max_by(|(ia, (da, _, ua)), (ib, (db, _, ub))| {
+                da.partial_cmp(db).unwrap_or(std::cmp::Ordering::Equal)
+                    .then(ub.unsigned_area().partial_cmp(&ua.unsigned_area()).unwrap_or(std::cmp::Ordering::Equal))
+                    .then(tools[*ia].diameter.partial_cmp(&tools[*ib].diameter).unwrap_or(std::cmp::Ordering::Equal))
+            });
+        let Some((i, (achievable, floor, unreachable))) = best else { continue };
+        let tool = &tools[i];
+        let radius = if tool.kind == "vbit" { 0.0 } else { tool.diameter / 2.0 };
+        report_regions.push(RegionReach {
+            depth,
+            tool: tool.label(),
+            achievable_depth: achievable,
+            min_internal_radius: radius,
+            floor_fillet_radius: if tool.kind == "ball" { radius } else { 0.0 },
+            flat_floor_area: floor.unsigned_area(),
+            area,
+            unreachable_area: unreachable.unsigned_area(),
+        });
+        cuts.push(Cut { depth, region, tool: i, achievable, unreachable });
+    }
+
+    let mut shapes = Vec::new();
+    for (index, shape) in request.shapes.iter().enumerate() {
+        let design_depth = shape.depth.clamp(0.0, thickness);
+        if design_depth <= 1e-6 { continue; }
+        let Some(poly) = shape_to_polygon(shape) else { continue };
+        let own = to_sketch(&MultiPolygon::new(vec![poly])).intersection(&board_mp);
+
+        // The shape is visible where its depth region covers it
+        let Some(cut) = cuts.iter().find(|c| (c.depth - design_depth).abs() < 1e-6) else { continue };
+        let visible_area = to_multipolygon(&own.intersection(&to_sketch(&cut.region))).unsigned_area();
+        if visible_area < MIN_PATCH_AREA { continue; }
+
+        let tool = &tools[cut.tool];
+        let radius = if tool.kind == "vbit" { 0.0 } else { tool.diameter / 2.0 };
+        let mut deviations = Vec::new();
+        // Where the tool reaches, it stops at the achievable depth; elsewhere (corners,
+        // slots narrower than the tool) the surrounding floor is not tracked, so report 0
+        let reached: MultiPolygon<f64> = to_multipolygon(&own.intersection(&to_sketch(&cut.region)).difference(&to_sketch(&cut.unreachable)));
+        if cut.achievable < design_depth - 1e-6 {
+            deviations.extend(reached.0.iter().map(|p| (p, cut.achievable)));
+        }
+        let short: MultiPolygon<f64> = to_multipolygon(&own.intersection(&to_sketch(&cut.unreachable)));
+        let short_depth = if tool.kind == "vbit" { cut.achievable } else { 0.0 };
+        deviations.extend(short.0.iter().map(|p| (p, short_depth)));
+
+        let deviations: Vec<ShapeDeviation> = deviations.into_iter()
+            .filter(|(p, _)| p.unsigned_area() >= MIN_PATCH_AREA)
+            .filter_map(|(p, achieved_depth)| p.bounding_rect().map(|b| ShapeDeviation {
+                min: [b.min().x, b.min().y],
+                max: [b.max().x, b.max().y],
+                area: p.unsigned_area(),
+                design_depth,
+                achieved_depth,
+            }))
+            .collect();
+
+        shapes.push(ShapeReach {
+            index,
+            shape_type: shape.shape_type.clone(),
+            x: shape.x,
+            y: shape.y,
+            design_depth,
+            achieved_depth: cut.achievable,
+            tool: tool.label(),
+            min_internal_radius: radius,
+            floor_fillet_radius: if tool.kind == "ball" { radius } else { 0.0 },
+            visible_area,
+            ok: deviations.is_empty(),
+            deviations,
+        });
+    }
+
+    Ok(ReachReport { regions: report_regions, shapes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+    use serde_json::json;
+
+    /// Carve layer, 10 mm thick, with one `w` x `h` rect pocket 3 mm deep at (50, 50).
+    fn pocket(w: f64, h: f64) -> ExportRequest {
+        let corner = |x: f64, y: f64| json!({ "x": x, "y": y, "handle_in": null, "handle_out": null });
+        let rect = json!({
+            "shape_type": "rect", "x": 50.0, "y": 50.0, "width": w, "height": h, "diameter": null,
+            "angle": null, "corner_radius": null, "thickness": null, "points": null, "depth": 3.0,
+            "endmill_radius": null, "feed": null, "speed": null, "power": null,
+        });
+        serde_json::from_value(json!({
+            "filepath": "", "file_type": "SVG", "machining_type": "Carved/Printed", "cut_direction": "Top",
+            "outline": [corner(0.0, 0.0), corner(100.0, 0.0), corner(100.0, 100.0), corner(0.0, 100.0)],
+            "shapes": [rect], "layer_thickness": 10.0, "stl_content": null,
+        })).unwrap()
+    }
+
+    fn tool(kind: &str, diameter: f64, max_depth: Option<f64>) -> Tool {
+        Tool { name: None, kind: kind.into(), diameter, angle: None, max_depth }
+    }
+
+    fn pocket_region(report: &ReachReport) -> &RegionReach {
+        report.regions.iter().find(|r| (r.depth - 3.0).abs() < 1e-9).unwrap()
+    }
+
+    #[test]
+    fn test_flat_tool_leaves_square_corners() {
+        let report = achievable_depth_report(&pocket(20.0, 20.0), &[tool("flat", 6.0, None)]).unwrap();
+        let region = pocket_region(&report);
+        assert_relative_eq!(region.achievable_depth, 3.0);
+        assert_relative_eq!(region.min_internal_radius, 3.0);
+        // Four corners of r² - πr²/4 each, up to the arc discretisation
+        let corners = 4.0 * (9.0 - std::f64::consts::PI * 9.0 / 4.0);
+        assert_relative_eq!(region.unreachable_area, corners, epsilon = 0.1);
+
+        let shape = &report.shapes[0];
+        assert!(!shape.ok);
+        assert_eq!(shape.deviations.len(), 4);
+        assert!(shape.deviations.iter().all(|d| d.achieved_depth == 0.0));
+    }
+
+    #[test]
+    fn test_short_flutes_cap_the_depth() {
+        let report = achievable_depth_report(&pocket(20.0, 20.0), &[tool("ball", 6.0, Some(2.0))]).unwrap();
+        let region = pocket_region(&report);
+        assert_relative_eq!(region.achievable_depth, 2.0);
+        assert_relative_eq!(region.floor_fillet_radius, 3.0);
+        assert!(report.shapes[0].deviations.iter().any(|d| d.achieved_depth == 2.0));
+    }
+
+    #[test]
+    fn test_vbit_depth_limited_by_slot_width() {
+        // 4 mm slot: the inscribed radius is 2, so a 90° V-bit bottoms out 2 mm down
+        let report = achievable_depth_report(&pocket(4.0, 20.0), &[tool("vbit", 6.0, None)]).unwrap();
+        assert_relative_eq!(pocket_region(&report).achievable_depth, 2.0, epsilon = 1e-4);
+    }
+
+    #[test]
+    fn test_smaller_tool_wins_on_corners() {
+        let report = achievable_depth_report(&pocket(20.0, 20.0), &[tool("flat", 6.0, None), tool("flat", 2.0, None)]).unwrap();
+        assert_eq!(pocket_region(&report).tool, "flat 2mm");
+        assert!(achievable_depth_report(&pocket(20.0, 20.0), &[]).is_err());
+    }
+}
@@ -0,0 +1,259 @@
+//! Traces a scanned part (PNG/JPEG) into an outline polygon with holes.
+use geo::{Area, BoundingRect, Contains, Coord, LineString, Point, Polygon, Simplify};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use crate::messages::{Message, MessageCode};
+
+/// Contours enclosing fewer pixels than this are treated as scan noise.
+const MIN_FEATURE_AREA_PX: f64 = 16.0;
+
+/// How to trace a photo or scan of a part into an outline.
+#[derive(Debug, Deserialize, Clone)]
+pub struct TraceOptions {
+    /// mm spanned by the traced outline along `reference_axis`
+    pub reference_length: f64,
+    /// "width" | "height"
+    #[serde(default = "default_axis")]
+    pub reference_axis: String,
+    /// 0-255; Otsu's method when omitted
+    #[serde(default)]
+    pub threshold: Option<u8>,
+    /// Part is lighter than the background
+    #[serde(default)]
+    pub invert: bool,
+    /// Simplification tolerance in pixels
+    #[serde(default = "default_tolerance")]
+    pub tolerance: f64,
+}
+
+fn default_axis() -> String { "width".into() }
+fn default_tolerance() -> f64 { 1.0 }
+
+/// Traced part outline and holes, scaled to mm.
+#[derive(Debug, Serialize, Clone)]
+pub struct TracedOutline {
+    /// mm, origin at the outline's lower-left corner, y up
+    pub outline: Vec<[f64; 2]>,
+    /// Inner contours, same coordinates as `outline`
+    pub holes: Vec<Vec<[f64; 2]>>,
+    /// Scale derived from `TraceOptions::reference_length`
+    pub mm_per_pixel: f64,
+    /// Grey level actually used
+    pub threshold: u8,
+}
+
+/// Otsu's method: the grey level that maximises the between-class variance.
+fn otsu_threshold(histogram: &[u64; 256]) -> u8 {
+    let total: u64 = histogram.iter().sum();
+    let sum_all: f64 = histogram.iter().enumerate().map(|(i, &n)| i as f64 * n as f64).sum();
+    let (mut weight_bg, mut sum_bg) = (0.0, 0.0);
+    let (mut best, mut best_var) = (127u8, -1.0);
+
+    for (level, &count) in histogram.iter().enumerate() {
+        weight_bg += count as f64;
+        if weight_bg == 0.0 { continue; }
+        let weight_fg = total as f64 - weight_bg;
+        if weight_fg == 0.0 { break; }
+        sum_bg += level as f64 * count as f64;
+        let mean_bg = sum_bg / weight_bg;
+        let mean_fg = (sum_all - sum_bg) / weight_fg;
+        let var = weight_bg * weight_fg * (mean_bg - mean_fg).powi(2);
+        if var > best_var {
+            best_var = var;
+            best = level as u8;
+        }
+    }
+    best
+}
+
+/// Marching squares over a binary mask. The mask is padded by one empty pixel so every
+/// contour closes. Segment endpoints sit on cell-edge midpoints, keyed in doubled integer
+/// coordinates so shared endpoints match exactly. Saddle cells always keep the two filled
+/// corners apart, so every endpoint is shared by exactly two segments.
+/// Rings are returned in pixel coordinates (pixel centres at integers).
+fn marching_squares(mask: &[bool], width: usize, height: usize) -> Vec<LineString<f64>> {
+    let at = |x: i64, y: i64| x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && mask[y as usize * width + x as usize];
+
+    let mut links: HashMap<(i64, i64), Vec<(i64, i64)>> = HashMap::new();
+    let mut link = |a: (i64, i64), b: (i64, i64)| {
+        links.entry(a).or_default().push(b);
+        links.entry(b).or_default().push(a);
+    };
+
+    for y in -1..height as i64 {
+        for x in -1..width as i64 {
+            let (tl, tr, br, bl) = (at(x, y), at(x + 1, y), at(x + 1, y + 1), at(x, y + 1));
+            let top = (2 * x + 1, 2 * y);
+            let right = (2 * x + 2, 2 * y + 1);
+            let bottom = (2 * x + 1, 2 * y + 2);
+            let left = (2 * x, 2 * y + 1);
+
+            let mut active = Vec::with_capacity(4);
+            if tl != tr { active.push(top); }
+            if tr != br { active.push(right); }
+            if br != bl { active.push(bottom); }
+            if bl != tl { active.push(left); }
+
+            match active.len() {
+                2 => link(active[0], active[1]),
+                4 if tl => { link(top, left); link(right, bottom); }
+                4 => { link(top, right); link(bottom, left); }
+                _ => {}
+            }
+        }
+    }
+
+    let mut rings = Vec::new();
+    let mut visited: HashMap<(i64, i64), bool> = HashMap::new();
+    let starts: Vec<(i64, i64)> = links.keys().copied().collect();
+    for start in starts {
+        if visited.contains_key(&start) { continue; }
+        let mut coords = Vec::new();
+        let (mut prev, mut cur) = (start, start);
+        loop {
+            visited.insert(cur, true);
+            coords.push(Coord { x: cur.0 as f64 / 2.0, y: cur.1 as f64 / 2.0 });
+            let next = links[&cur].iter().copied().find(|&n| n != prev && !visited.contains_key(&n));
+            match next {
+                Some(n) => { prev = cur; cur = n; }
+                None => break,
+            }
+        }
+        if coords.len() >= 3 {
+            let mut ls = LineString::new(coords);
+            ls.close();
+            rings.push(ls);
+        }
+    }
+    rings
+}
+
+/// Loads the image, thresholds it, traces every contour and keeps the largest as the
+/// outline and the contours directly inside it as holes. The result is simplified,
+/// scaled so the outline spans `reference_length` along the reference axis, flipped to
+/// y-up and moved so its bounding box starts at the origin.
+pub fn trace_image(path: &str, options: &TraceOptions) -> Result<TracedOutline, Message> {
+    if options.reference_length <= 0.0 {
+        return Err(Message::not_positive("reference_length", "Reference length must be positive"));
+    }
+    let img = image::open(path).map_err(|e| Message::read_failed(path, e))?.to_luma8();
+    let (width, height) = (img.width() as usize, img.height() as usize);
+
+    let mut histogram = [0u64; 256];
+    for p in img.pixels() {
+        histogram[p.0[0] as usize] += 1;
+    }
+    let threshold = options.threshold.unwrap_or_else(|| otsu_threshold(&histogram));
+    let mask: Vec<bool> = img.pixels().map(|p| (p.0[0] > threshold) == options.invert).collect();
+
+    let mut polys: Vec<Polygon<f64>> = marching_squares(&mask, width, height).into_iter()
+        .map(|ring| Polygon::new(ring, vec![]))
+        .filter(|p| p.unsigned_area() >= MIN_FEATURE_AREA_PX)
+        .collect();
+    polys.sort_by(|a, b| b.unsigned_area().total_cmp(&a.unsigned_area()));
+    let outline = polys.first().cloned()
+        .ok_or_else(|| Message::new(MessageCode::TraceNoPart, "No part found in the image; try another threshold"))?;
+
+    // Holes are contours inside the outline that are not inside another hole (islands in holes are dropped)
+    let mut holes: Vec<Polygon<f64>> = Vec::new();
+    for p in &polys[1..] {
+        let probe = Point::from(p.exterior().0[0]);
+        if outline.contains(&probe) && !holes.iter().any(|h| h.contains(&probe)) {
+            holes.push(p.clone());
+        }
+    }
+
+    let degenerate = || Message::new(MessageCode::OutlineDegenerate, "Traced outline is degenerate");
+    let rect = outline.bounding_rect().ok_or_else(degenerate)?;
+    let span_px = if options.reference_axis == "height" { rect.height() } else { rect.width() };
+    if span_px <= 0.0 {
+        return Err(degenerate());
+    }
+    let mm_per_pixel = options.reference_length / span_px;
+
+    let to_mm = |ring: &LineString<f64>| -> Vec<[f64; 2]> {
+        let simplified = ring.simplify(&options.tolerance);
+        let ring = if simplified.0.len() >= 4 { &simplified } else { ring };
+        // Drop the closing point; outlines elsewhere are implicitly closed
+        ring.0[..ring.0.len() - 1].iter()
+            .map(|c| [(c.x - rect.min().x) * mm_per_pixel, (rect.max().y - c.y) * mm_per_pixel])
+            .collect()
+    };
+
+    Ok(TracedOutline {
+        outline: to_mm(outline.exterior()),
+        holes: holes.iter().map(|h| to_mm(h.exterior())).collect(),
+        mm_per_pixel,
+        threshold,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn bounds(ring: &[[f64; 2]]) -> [f64; 4] {
+        ring.iter().fold([f64::MAX, f64::MAX, f64::MIN, f64::MIN], |b, p| {
+            [b[0].min(p[0]), b[1].min(p[1]), b[2].max(p[0]), b[3].max(p[1])]
+        })
+    }
+
+    #[test]
+    fn test_otsu_splits_two_levels() {
+        let mut histogram = [0u64; 256];
+        histogram[50] = 1000;
+        histogram[200] = 3000;
+        let t = otsu_threshold(&histogram);
+        assert!((50..200).contains(&t));
+    }
+
+    #[test]
+    fn test_traces_plate_with_hole() {
+        // White 100 x 60 scan; a dark 80 x 40 px part with a 20 x 10 px window and a 2 x 2 speck of dust
+        let img = image::GrayImage::from_fn(100, 60, |x, y| {
+            let part = (10..90).contains(&x) && (10..50).contains(&y);
+            let window = (40..60).contains(&x) && (25..35).contains(&y);
+            let speck = (70..72).contains(&x) && (20..22).contains(&y);
+            image::Luma([if part && !window && !speck { 0 } else { 255 }])
+        });
+        let path = std::env::temp_dir().join(format!("shortstack_trace_{}.png", std::process::id()));
+        img.save(&path).unwrap();
+
+        let options = TraceOptions {
+            reference_length: 160.0,
+            reference_axis: "width".into(),
+            threshold: None,
+            invert: false,
+            tolerance: 1.0,
+        };
+        let traced = trace_image(path.to_str().unwrap(), &options).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        // Contours run through pixel edges: 80 px across, so 2 mm per pixel
+        assert_relative_eq!(traced.mm_per_pixel, 2.0, epsilon = 1e-12);
+        // Simplification may pull an extreme in by up to the tolerance
+        let slack = options.tolerance * traced.mm_per_pixel;
+        let outline = bounds(&traced.outline);
+        for (b, e) in outline.iter().zip([0.0, 0.0, 160.0, 80.0]) {
+            assert_relative_eq!(*b, e, epsilon = slack);
+        }
+
+        // The speck is below the noise floor; the window is the only hole, flipped to y-up
+        assert_eq!(traced.holes.len(), 1);
+        let hole = bounds(&traced.holes[0]);
+        for (b, e) in hole.iter().zip([60.0, 30.0, 100.0, 50.0]) {
+            assert_relative_eq!(*b, e, epsilon = slack);
+        }
+    }
+
+    #[test]
+    fn test_blank_image_has_no_part() {
+        let path = std::env::temp_dir().join(format!("shortstack_trace_blank_{}.png", std::process::id()));
+        image::GrayImage::from_pixel(20, 20, image::Luma([255])).save(&path).unwrap();
+        let options = TraceOptions { reference_length: 10.0, reference_axis: "width".into(), threshold: Some(127), invert: false, tolerance: 1.0 };
+        let err = trace_image(path.to_str().unwrap(), &options).unwrap_err();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(err.code, MessageCode::TraceNoPart);
+    }
+}
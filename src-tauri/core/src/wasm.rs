@@ -0,0 +1,106 @@
+//! Browser bindings for the frontend's live preview (`wasm` feature, built for
+//! wasm32-unknown-unknown without `native`). They run the same code as the export commands,
+//! so a preview matches the exported file exactly without a round-trip through Tauri.
+//!
+//! Nothing here touches the filesystem: requests come in as the JSON the commands take
+//! (`filepath` is ignored) and results go back as JSON strings or file bytes.
+//!
+//! Polygons cross the boundary as rings: a MultiPolygon is `[[[x, y], ...], ...][]`, each
+//! polygon its exterior ring followed by its holes.
+use crate::export::{ExportRequest, export_bytes, get_depth_regions};
+use csgrs::sketch::Sketch;
+use csgrs::traits::CSG;
+use geo::{Area, LineString, MultiPolygon, Polygon};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+type Rings = Vec<Vec<Vec<[f64; 2]>>>;
+
+#[derive(Serialize)]
+struct PreviewRegion {
+    depth: f64,
+    polygons: Rings,
+}
+
+#[derive(Serialize)]
+struct DepthPreview {
+    board: Vec<[f64; 2]>,
+    regions: Vec<PreviewRegion>,
+}
+
+fn parse<T: serde::de::DeserializeOwned>(json: &str) -> Result<T, JsValue> {
+    serde_json::from_str(json).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, JsValue> {
+    serde_json::to_string(value).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+fn ring_coords(ring: &LineString<f64>) -> Vec<[f64; 2]> {
+    ring.coords().map(|c| [c.x, c.y]).collect()
+}
+
+fn to_rings(mp: &MultiPolygon<f64>) -> Rings {
+    mp.0.iter()
+        .map(|p| std::iter::once(p.exterior()).chain(p.interiors()).map(ring_coords).collect())
+        .collect()
+}
+
+fn from_rings(rings: Rings) -> MultiPolygon<f64> {
+    MultiPolygon::new(rings.into_iter().filter_map(|polygon| {
+        let mut rings = polygon.into_iter().map(|r| LineString::from(r.into_iter().map(|[x, y]| (x, y)).collect::<Vec<_>>()));
+        let exterior = rings.next()?;
+        Some(Polygon::new(exterior, rings.collect()))
+    }).collect())
+}
+
+fn to_sketch(mp: MultiPolygon<f64>) -> Sketch<()> {
+    Sketch::from_geo(geo::Geometry::MultiPolygon(mp).into(), None)
+}
+
+fn to_multipolygon(sketch: &Sketch<()>) -> MultiPolygon<f64> {
+    let mut polys = Vec::new();
+    for geom in &sketch.geometry {
+        match geom {
+            geo::Geometry::Polygon(p) => polys.push(p.clone()),
+            geo::Geometry::MultiPolygon(mp) => polys.extend(mp.0.iter().cloned()),
+            _ => {}
+        }
+    }
+    polys.retain(|p| p.unsigned_area() > 1e-9);
+    MultiPolygon::new(polys)
+}
+
+/// Boolean of two ring sets: `op` is "union", "difference" (a − b), "intersection" or "xor".
+#[wasm_bindgen(js_name = polygonBoolean)]
+pub fn polygon_boolean(a: &str, b: &str, op: &str) -> Result<String, JsValue> {
+    let a = to_sketch(from_rings(parse(a)?));
+    let b = to_sketch(from_rings(parse(b)?));
+    let result = match op {
+        "union" => a.union(&b),
+        "difference" => a.difference(&b),
+        "intersection" => a.intersection(&b),
+        "xor" => a.xor(&b),
+        other => return Err(JsValue::from_str(&format!("Unknown boolean op: {}", other))),
+    };
+    to_json(&to_rings(&to_multipolygon(&result)))
+}
+
+/// The carve layer's visible depth regions, shallowest first, with the board outline:
+/// `{ board, regions: [{ depth, polygons }] }`.
+#[wasm_bindgen(js_name = depthRegions)]
+pub fn depth_regions(request: &str) -> Result<String, JsValue> {
+    let request: ExportRequest = parse(request)?;
+    let (board, regions) = get_depth_regions(&request).ok_or_else(|| JsValue::from_str("Board outline is missing"))?;
+    to_json(&DepthPreview {
+        board: ring_coords(board.exterior()),
+        regions: regions.iter().map(|(depth, mp)| PreviewRegion { depth: *depth, polygons: to_rings(mp) }).collect(),
+    })
+}
+
+/// The SVG or DXF bytes `export_layer_files` would write for this request.
+#[wasm_bindgen(js_name = exportFile)]
+pub fn export_file(request: &str) -> Result<Vec<u8>, JsValue> {
+    let request: ExportRequest = parse(request)?;
+    export_bytes(&request).map_err(|e| JsValue::from_str(&e.message))
+}
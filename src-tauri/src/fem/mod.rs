@@ -1,11 +0,0 @@
-pub mod tet10;
-pub mod quadrature;
-pub mod material;
-pub mod mesh;
-pub mod tetgen;
-pub mod mesh_utils;
-pub mod regularizer;
-
-#[cfg(test)]
-mod tests;
-pub mod gmsh_interop;
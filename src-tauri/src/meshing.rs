@@ -0,0 +1,130 @@
+// Tauri side of meshing: the bundled Gmsh sidecar, the run archive in the app data dir, and
+// the TetGen/repair commands. The pipeline itself is shortstack_core::fem.
+use std::fs;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::Emitter;
+use tauri_plugin_shell::ShellExt;
+use shortstack_core::artifacts::{self, ArtifactIndex};
+use shortstack_core::fem::gmsh_interop::{FeaRequest, FeaResult, build_report, collect_mesh, gmsh_failed, gmsh_launch_failed, prepare_geo};
+use shortstack_core::fem::geo_builder;
+use shortstack_core::messages::{Message, MessageCode};
+use shortstack_core::fem::mesh::TetMesh;
+use shortstack_core::fem::tetgen::{self, SurfaceMesh, TetrahedralizedMesh};
+
+/// Event carrying a `Message` (MESHING_* code) as each stage of a meshing run starts.
+const MESHING_PROGRESS_EVENT: &str = "meshing-progress";
+
+fn report_stage(app_handle: &tauri::AppHandle, code: MessageCode, message: &str) {
+    let _ = app_handle.emit(MESHING_PROGRESS_EVENT, Message::new(code, message));
+}
+
+/// Copies the run's .geo/.msh into `artifacts/` (named by geometry hash, quality and start
+/// time, so re-meshing the same model keeps every earlier run) and records them plus the
+/// result in the index.
+#[allow(clippy::too_many_arguments)]
+fn archive_run(app_handle: &tauri::AppHandle, app_dir: &Path, req: &FeaRequest, geo_path: &Path, msh_path: &Path, mesh: &TetMesh, volume: f64, surface_area: f64) {
+    use tauri::Manager;
+
+    let hash = artifacts::project_hash(&(&req.footprint, &req.stackup, &req.params));
+    let archive_dir = app_dir.join("artifacts");
+    if let Err(e) = fs::create_dir_all(&archive_dir) {
+        eprintln!("Failed to create artifact dir: {}", e);
+        return;
+    }
+
+    let index = app_handle.state::<ArtifactIndex>();
+    let params = serde_json::json!({ "quality": req.quality });
+    let stamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+    let name = |kind: &str| archive_dir.join(format!("{}_q{}_{}.{}", hash, req.quality, stamp, kind));
+
+    for (kind, src) in [("geo", geo_path), ("msh", msh_path)] {
+        let dst = name(kind);
+        match fs::copy(src, &dst) {
+            Ok(_) => { index.record(kind, &dst, &hash, params.clone()); }
+            Err(e) => eprintln!("Failed to archive {}: {}", kind, e),
+        }
+    }
+
+    index.record("result", &name("msh"), &hash, serde_json::json!({
+        "quality": req.quality,
+        "nodes": mesh.vertices.len(),
+        "elements": mesh.indices.len(),
+        "volume": volume,
+        "surface_area": surface_area,
+    }));
+}
+
+/// The bundled Gmsh outside the shell plugin (session replays run it directly). Tauri
+/// places sidecars next to the app executable.
+pub fn sidecar_gmsh_path() -> Option<std::path::PathBuf> {
+    let exe = std::env::current_exe().ok()?;
+    let path = exe.parent()?.join(if cfg!(windows) { "gmsh.exe" } else { "gmsh" });
+    path.exists().then_some(path)
+}
+
+#[tauri::command]
+pub async fn run_gmsh_meshing(app_handle: tauri::AppHandle, req: FeaRequest) -> Result<FeaResult, Message> {
+    use tauri::Manager;
+
+    // 1. Setup Paths
+    let app_dir = app_handle.path().app_data_dir()
+        .map_err(|e| Message::new(MessageCode::AppDataUnavailable, format!("No app data directory: {}", e)))?;
+    if !app_dir.exists() {
+        let _ = fs::create_dir_all(&app_dir);
+    }
+
+    // 2. Generate Script
+    report_stage(&app_handle, MessageCode::MeshingWriteGeo, "Writing geometry script...");
+    let cache_dir = app_dir.join("geo_cache");
+    let cache_dir = fs::create_dir_all(&cache_dir).ok().map(|_| cache_dir);
+    let (geo_path, msh_path) = prepare_geo(&req, &app_dir, cache_dir.as_deref())?;
+
+    // 3. Resolve Sidecar
+    // Note: In Tauri v2, sidecars are strictly managed. 
+    // You must define `gmsh` in tauri.conf.json -> bundle -> externalBin
+    let sidecar_command = app_handle.shell().sidecar("gmsh").map_err(gmsh_launch_failed)?;
+    
+    // 4. Execute Sidecar
+    // args: path_to_geo, "-" (non-interactive)
+    report_stage(&app_handle, MessageCode::MeshingRunGmsh, "Meshing with Gmsh...");
+    let output = sidecar_command
+        .args(&[geo_path.to_str().unwrap(), "-"])
+        .output()
+        .await
+        .map_err(gmsh_launch_failed)?;
+
+    if !output.status.success() {
+        return Err(gmsh_failed(&output.stderr));
+    }
+
+    // 5. Parse Output and measure the mesh
+    report_stage(&app_handle, MessageCode::MeshingReadMesh, "Reading mesh...");
+    let run = collect_mesh(&req, &msh_path, String::from_utf8_lossy(&output.stdout).to_string())?;
+
+    // 6. Archive the inputs/outputs so this run can be found again later
+    report_stage(&app_handle, MessageCode::MeshingArchive, "Archiving run...");
+    archive_run(&app_handle, &app_dir, &req, &geo_path, &msh_path, &run.mesh, run.volume, run.surface_area);
+    if let Some(dir) = &cache_dir {
+        geo_builder::prune_cache(dir, geo_builder::GEO_CACHE_MAX_BYTES);
+    }
+
+    Ok(FeaResult {
+        report: build_report(&req, run.volume, run.surface_area),
+        mesh: run.mesh,
+        volume: run.volume,
+        surface_area: run.surface_area,
+        logs: run.logs,
+        element_quality: run.element_quality,
+    })
+}
+
+#[tauri::command]
+pub async fn cmd_repair_mesh(vertices: Vec<f64>, target_len: f64) -> Result<SurfaceMesh, Message> {
+    tetgen::repair_mesh(vertices, target_len)
+}
+
+#[tauri::command]
+pub async fn cmd_tetrahedralize(vertices: Vec<f64>, options: String, target_len: Option<f64>) -> Result<TetrahedralizedMesh, Message> {
+    tetgen::tetrahedralize(vertices, options, target_len)
+}
@@ -0,0 +1,237 @@
+// Restricts filesystem writes coming from the webview to user-approved directories.
+use serde::{Deserialize, Serialize};
+use shortstack_core::messages::{Message, MessageCode};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+/// Approvals made without a project id (e.g. an unsaved project) land here.
+const DEFAULT_PROJECT: &str = "default";
+
+/// Structured error returned to the frontend when a path is rejected.
+#[derive(Debug, Serialize, Clone)]
+pub struct PermissionError {
+    pub code: MessageCode, // InvalidPath, PathNotApproved or WriteFailed
+    pub path: String,
+    pub message: String,
+}
+
+impl PermissionError {
+    pub fn new(code: MessageCode, path: &str, message: impl Into<String>) -> Self {
+        Self { code, path: path.to_string(), message: message.into() }
+    }
+}
+
+impl std::fmt::Display for PermissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?} ({}): {}", self.code, self.path, self.message)
+    }
+}
+
+/// Lets commands that report coded messages return sandbox denials without losing the code.
+impl From<PermissionError> for Message {
+    fn from(e: PermissionError) -> Self {
+        Message::new(e.code, e.to_string()).with("path", e.path).with("error", e.message)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SandboxConfig {
+    projects: HashMap<String, Vec<PathBuf>>,
+}
+
+/// Per-project list of approved directories, persisted as JSON in the app data dir.
+pub struct PathSandbox {
+    config_path: Option<PathBuf>,
+    config: Mutex<SandboxConfig>,
+}
+
+impl PathSandbox {
+    /// Loads approvals from `config_path`. A missing or unreadable file starts empty.
+    pub fn load(config_path: Option<PathBuf>) -> Self {
+        let config = config_path
+            .as_ref()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+        Self { config_path, config: Mutex::new(config) }
+    }
+
+    /// Remembers `directory` as writable for the project and persists the list. Only called
+    /// with folders the user picked in a native dialog, never with a path from the webview.
+    pub fn approve(&self, project_id: Option<&str>, directory: &str) -> Result<PathBuf, PermissionError> {
+        let dir = normalize_path(directory)?;
+        if !dir.is_dir() {
+            return Err(PermissionError::new(MessageCode::InvalidPath, directory, "Not an existing directory"));
+        }
+
+        let mut config = self.config.lock().unwrap();
+        let entry = config.projects.entry(project_key(project_id)).or_default();
+        if !entry.contains(&dir) {
+            entry.push(dir.clone());
+        }
+        self.save(&config).map_err(|e| PermissionError::new(MessageCode::WriteFailed, directory, e))?;
+        Ok(dir)
+    }
+
+    pub fn approved(&self, project_id: Option<&str>) -> Vec<PathBuf> {
+        let config = self.config.lock().unwrap();
+        config.projects.get(&project_key(project_id)).cloned().unwrap_or_default()
+    }
+
+    /// Validates a write target and returns its normalized form.
+    /// The path must be absolute and lie inside one of the project's approved directories.
+    pub fn check_write(&self, project_id: Option<&str>, path: &str) -> Result<PathBuf, PermissionError> {
+        let target = normalize_path(path)?;
+        if target.is_dir() {
+            return Err(PermissionError::new(MessageCode::InvalidPath, path, "Target is a directory"));
+        }
+
+        if self.approved(project_id).iter().any(|dir| target.starts_with(dir)) {
+            Ok(target)
+        } else {
+            Err(PermissionError::new(
+                MessageCode::PathNotApproved,
+                path,
+                "Target is outside the directories approved for this project",
+            ))
+        }
+    }
+
+    fn save(&self, config: &SandboxConfig) -> Result<(), String> {
+        let Some(path) = &self.config_path else { return Ok(()) };
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(config).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| e.to_string())
+    }
+}
+
+fn project_key(project_id: Option<&str>) -> String {
+    match project_id {
+        Some(id) if !id.trim().is_empty() => id.to_string(),
+        _ => DEFAULT_PROJECT.to_string(),
+    }
+}
+
+/// Makes `path` absolute-and-clean: resolves `.`/`..` lexically, then canonicalizes the
+/// deepest existing ancestor so symlinks cannot be used to step outside an approved dir.
+pub fn normalize_path(path: &str) -> Result<PathBuf, PermissionError> {
+    if path.trim().is_empty() || path.contains('\0') {
+        return Err(PermissionError::new(MessageCode::InvalidPath, path, "Empty or malformed path"));
+    }
+    let raw = Path::new(path);
+    if !raw.is_absolute() {
+        return Err(PermissionError::new(MessageCode::InvalidPath, path, "Path must be absolute"));
+    }
+
+    let mut clean = PathBuf::new();
+    for comp in raw.components() {
+        match comp {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !clean.pop() {
+                    return Err(PermissionError::new(MessageCode::InvalidPath, path, "Path escapes the filesystem root"));
+                }
+            }
+            other => clean.push(other),
+        }
+    }
+
+    // Canonicalize the existing prefix and re-attach the parts that don't exist yet.
+    let mut existing = clean.clone();
+    let mut rest = Vec::new();
+    while !existing.exists() {
+        match (existing.file_name(), existing.parent()) {
+            (Some(name), Some(parent)) => {
+                rest.push(name.to_os_string());
+                existing = parent.to_path_buf();
+            }
+            _ => break,
+        }
+    }
+    let mut resolved = existing
+        .canonicalize()
+        .map_err(|e| PermissionError::new(MessageCode::InvalidPath, path, e.to_string()))?;
+    for name in rest.into_iter().rev() {
+        resolved.push(name);
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fresh directory under the system temp dir, canonicalized so comparisons hold on
+    /// platforms where the temp dir itself sits behind a symlink.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("shortstack_sandbox_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    fn path_str(path: &Path) -> String {
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn normalize_resolves_parent_components() {
+        let dir = scratch_dir("parent");
+        std::fs::create_dir_all(dir.join("a/b")).unwrap();
+        let raw = path_str(&dir.join("a/b/../../c.svg"));
+        assert_eq!(normalize_path(&raw).unwrap(), dir.join("c.svg"));
+
+        if cfg!(unix) {
+            assert_eq!(normalize_path("/../../etc").unwrap_err().code, MessageCode::InvalidPath);
+        }
+        assert_eq!(normalize_path("relative/out.svg").unwrap_err().code, MessageCode::InvalidPath);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn normalize_keeps_missing_tail() {
+        let dir = scratch_dir("missing");
+        let raw = path_str(&dir.join("not/yet/created.dxf"));
+        assert_eq!(normalize_path(&raw).unwrap(), dir.join("not/yet/created.dxf"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn normalize_follows_symlinks_out_of_approved_dir() {
+        let approved = scratch_dir("link_approved");
+        let outside = scratch_dir("link_outside");
+        std::os::unix::fs::symlink(&outside, approved.join("escape")).unwrap();
+
+        let resolved = normalize_path(&path_str(&approved.join("escape/out.svg"))).unwrap();
+        assert_eq!(resolved, outside.join("out.svg"));
+
+        let sandbox = PathSandbox::load(None);
+        sandbox.approve(Some("p"), &path_str(&approved)).unwrap();
+        let err = sandbox.check_write(Some("p"), &path_str(&approved.join("escape/out.svg"))).unwrap_err();
+        assert_eq!(err.code, MessageCode::PathNotApproved);
+
+        std::fs::remove_dir_all(&approved).unwrap();
+        std::fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn check_write_requires_approval() {
+        let dir = scratch_dir("approval");
+        let target = path_str(&dir.join("layer.svg"));
+        let sandbox = PathSandbox::load(None);
+
+        assert_eq!(sandbox.check_write(Some("p"), &target).unwrap_err().code, MessageCode::PathNotApproved);
+
+        sandbox.approve(Some("p"), &path_str(&dir)).unwrap();
+        assert_eq!(sandbox.check_write(Some("p"), &target).unwrap(), dir.join("layer.svg"));
+        // Approvals are per project
+        assert_eq!(sandbox.check_write(Some("other"), &target).unwrap_err().code, MessageCode::PathNotApproved);
+        // The approved directory itself is not a file target
+        assert_eq!(sandbox.check_write(Some("p"), &path_str(&dir)).unwrap_err().code, MessageCode::InvalidPath);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
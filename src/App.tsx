@@ -6,9 +6,10 @@ import { writeTextFile, readTextFile } from "@tauri-apps/plugin-fs";
 import { getVersion } from '@tauri-apps/api/app';
 import { check, Update } from "@tauri-apps/plugin-updater";
 import { relaunch } from "@tauri-apps/plugin-process";
+import { invoke } from "@tauri-apps/api/core";
 import "./App.css";
 
-import { Parameter, StackupLayer, ProjectData, Footprint, FootprintShape, LayerAssignment, FootprintBoardOutline, MeshAsset } from "./types";
+import { Parameter, StackupLayer, ProjectData, Footprint, FootprintShape, LayerAssignment, FootprintBoardOutline, MeshAsset, ReportUnits, DEFAULT_REPORT_UNITS } from "./types";
 import { resolveParameters, repairBoardAssignments } from "./utils/footprintUtils";
 
 import ParametersEditor from "./components/ParametersEditor";
@@ -34,6 +35,8 @@ function App() {
   
   const [activeTab, setActiveTab] = useState<Tab>("stackup");
   const [fabPlans, setFabPlans] = useState<any[]>([]);
+  const [reportUnits, setReportUnits] = useState<ReportUnits>(DEFAULT_REPORT_UNITS);
+  const [sessionPath, setSessionPath] = useState<string | null>(null); // Set while commands are being recorded
 
   // --- UPDATER STATE ---
   const [update, setUpdate] = useState<Update | null>(null);
@@ -96,7 +99,7 @@ function App() {
 
     const saveData = async () => {
       try {
-        const projectData: ProjectData = { params, stackup, footprints, meshes: meshAssets, fabPlans };
+        const projectData: ProjectData = { params, stackup, footprints, meshes: meshAssets, fabPlans, reportUnits };
         const content = JSON.stringify(projectData, null, 2);
         await writeTextFile(currentPath, content);
         console.log("Auto-saved to", currentPath);
@@ -107,7 +110,7 @@ function App() {
     
     const timer = setTimeout(saveData, 500);
     return () => clearTimeout(timer);
-  }, [params, stackup, footprints, meshAssets, fabPlans, currentPath]);
+  }, [params, stackup, footprints, meshAssets, fabPlans, reportUnits, currentPath]);
 
   // CREATE PROJECT
   async function createProject() {
@@ -129,6 +132,7 @@ function App() {
         setStackup([]);
         setFootprints([]);
         setMeshAssets([]);
+        setReportUnits(DEFAULT_REPORT_UNITS);
         setCurrentPath(path);
         setActiveTab("stackup");
       }
@@ -215,7 +219,10 @@ function App() {
                     assignedLayers[k] = { 
                         depth: obj.depth || "0", 
                         endmillRadius: obj.endmillRadius || "0",
-                        inputFillet: obj.inputFillet || "0"
+                        inputFillet: obj.inputFillet || "0",
+                        ...(obj.feedOverride ? { feedOverride: obj.feedOverride } : {}),
+                        ...(obj.speedOverride ? { speedOverride: obj.speedOverride } : {}),
+                        ...(obj.powerOverride ? { powerOverride: obj.powerOverride } : {})
                     };
                 }
             });
@@ -351,6 +358,7 @@ function App() {
             waterlineSettings: p.waterlineSettings || {},
             cncSettings: p.cncSettings || {}
         })));
+        setReportUnits({ ...DEFAULT_REPORT_UNITS, ...(rawData.reportUnits || {}) });
         setCurrentPath(path as string);
         setActiveTab("stackup");
       }
@@ -364,6 +372,21 @@ function App() {
       setMeshAssets(prev => [...prev, asset]);
   }
 
+  // Opt-in command recording for bug reports; the backend scrubs absolute paths
+  async function toggleSessionRecording() {
+    try {
+      if (sessionPath) {
+        const path: string | null = await invoke("stop_session_recording");
+        setSessionPath(null);
+        if (path) alert(`Session saved to:\n${path}\n\nAttach this file to your bug report.`);
+      } else {
+        setSessionPath(await invoke<string>("start_session_recording"));
+      }
+    } catch (e) {
+      alert("Session recording failed: " + describeError(e));
+    }
+  }
+
   function closeProject() {
     setCurrentPath(null);
     setParams([]);
@@ -424,6 +447,9 @@ function App() {
         <div className="file-info">
           <span>Editing: <strong>{currentPath}</strong></span>
         </div>
+        <button className="secondary" onClick={toggleSessionRecording} title={sessionPath ?? "Record backend commands to reproduce a problem"}>
+          {sessionPath ? "Stop Recording" : "Record Session"}
+        </button>
         <button className="secondary" onClick={closeProject}>Close Project</button>
       </header>
 
@@ -455,6 +481,7 @@ function App() {
               stackup={stackup}
               meshAssets={meshAssets}
               onRegisterMesh={registerMeshAsset}
+              projectId={currentPath}
             />
           </div>
         )}
@@ -474,6 +501,7 @@ function App() {
               stackup={stackup}
               params={params}
               meshAssets={meshAssets}
+              projectId={currentPath}
             />
           </div>
         )}
@@ -485,6 +513,8 @@ function App() {
               fabPlans={fabPlans}
               stackup={stackup}
               params={params}
+              reportUnits={reportUnits}
+              onReportUnitsChange={setReportUnits}
             />
           </div>
         )}
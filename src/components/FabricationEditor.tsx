@@ -1,7 +1,6 @@
 // src/components/FabricationEditor.tsx
 import { useState, useRef, useMemo, useEffect } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { open } from "@tauri-apps/plugin-dialog";
 import { join } from "@tauri-apps/api/path";
 import { 
     FabricationPlan, 
@@ -18,16 +17,18 @@ import {
 import { IconOutline, IconGrip } from "./Icons";
 import ExpressionEditor from "./ExpressionEditor";
 import { evaluateExpression, resolvePoint, getLineLength, convertExportShapeToFootprintShape } from "../utils/footprintUtils";
-import { collectExportShapesAsync, sliceExportShapes } from "../utils/exportUtils";
+import { collectExportShapesAsync, sliceExportShapes, streamExportFile, onExportProgress } from "../utils/exportUtils";
+import { describeError } from "../utils/messages";
 import Footprint3DView, { Footprint3DViewHandle, callWorker } from "./Footprint3DView";
 import "./FabricationEditor.css";
 
-const MATERIAL_DATA: Record<string, { density: number; methods: string[] }> = {
+// maxPassDepth (mm) caps the CNC step-down for materials that need shallower passes
+const MATERIAL_DATA: Record<string, { density: number; methods: string[]; maxPassDepth?: number }> = {
     "PLA (10% Infill)": { density: 0.124, methods: ["3D printed"] },
     "PLA (100% Infill)": { density: 1.24, methods: ["3D printed"] },
-    "Balsa Wood": { density: 0.14, methods: ["CNC", "Laser cut", "Waterline laser cut"] },
-    "Aluminum": { density: 2.66, methods: ["CNC", "Laser cut", "Waterline laser cut"] },
-    "XPS Foam": { density: 0.045, methods: ["CNC"] },
+    "Balsa Wood": { density: 0.14, methods: ["CNC", "Laser cut", "Waterline laser cut"], maxPassDepth: 3 },
+    "Aluminum": { density: 2.66, methods: ["CNC", "Laser cut", "Waterline laser cut"], maxPassDepth: 0.5 },
+    "XPS Foam": { density: 0.045, methods: ["CNC"], maxPassDepth: 10 },
     "Delrin": { density: 1.41, methods: ["Laser cut", "Waterline laser cut"] },
     "Plexiglass": { density: 1.2, methods: ["Laser cut", "Waterline laser cut"] },
     "Carbon Fiber": { density: 1.7, methods: ["Laser cut", "Waterline laser cut"] },
@@ -40,9 +41,10 @@ interface Props {
   stackup: StackupLayer[];
   params: Parameter[];
   meshAssets: MeshAsset[];
+  projectId: string | null; // Scope for export directory approvals
 }
 
-export default function FabricationEditor({ fabPlans, setFabPlans, footprints, stackup, params, meshAssets }: Props) {
+export default function FabricationEditor({ fabPlans, setFabPlans, footprints, stackup, params, meshAssets, projectId }: Props) {
   const [activePlanId, setActivePlanId] = useState<string | null>(null);
   const [isExporting, setIsExporting] = useState(false);
   const [exportProgress, setExportProgress] = useState("");
@@ -50,6 +52,8 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
   const [layerVisibility, setLayerVisibility] = useState<Record<string, boolean>>({});
   const [layerVolumes, setLayerVolumes] = useState<Record<string, number>>({});
   const [activeToolpaths, setActiveToolpaths] = useState<Record<string, number[][]>>({});
+  // G-code programs per CNC layer (one per tool), written next to the depth map on export
+  const [activeGcode, setActiveGcode] = useState<Record<string, { name: string; gcode: string }[]>>({});
   
   // NEW: Visual Stack State
   const [visualStack, setVisualStack] = useState<{ layer: StackupLayer, footprint: Footprint }[] | undefined>(undefined);
@@ -115,6 +119,9 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
     let numFiles = 1;
     if (method === "Waterline laser cut") {
         numFiles = numSheets;
+    } else if (method === "CNC") {
+        // Depth map + one G-code program per tool
+        numFiles = activePlan.cncSettings?.[layer.id]?.finishing === "Separate tool" ? 3 : 2;
     } else if (method === "3D printed") {
         const splitSettings = (activePlan as any).layerSplitSettings?.[layer.id];
         if (splitSettings?.enabled) {
@@ -128,7 +135,7 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
     if (method === "Waterline laser cut") {
         exportText = `Exports ${numSheets} DXF cuts`;
     } else if (method === "CNC") {
-        exportText = "Exports SVG depth map";
+        exportText = numFiles > 2 ? "Exports SVG depth map and roughing/finishing G-code" : "Exports SVG depth map and G-code";
     } else if (method === "3D printed") {
         exportText = numFiles > 1 ? `Exports ${numFiles} STL files` : "Exports STL mesh";
     }
@@ -250,16 +257,24 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
   const handleBulkExport = async () => {
     if (!activePlan || !targetFootprint) return;
 
-    const folderPath = await open({
-        directory: true,
-        multiple: false,
-        title: "Select Export Folder"
-    });
+    // The backend shows the folder picker and approves only what the user picks there
+    let folderPath: string | null;
+    try {
+        folderPath = await invoke("pick_export_directory", { projectId, title: "Select Export Folder" });
+    } catch (e) {
+        alert("Export failed: " + describeError(e));
+        return;
+    }
 
     if (!folderPath) return;
 
     setIsExporting(true);
     const planName = activePlan.name.replace(/[^a-zA-Z0-9]/g, '_');
+    const unverified: string[] = []; // Files whose re-read geometry differs from what was sent
+    // Large files are written progressively; show how far along the current one is
+    const unlisten = await onExportProgress(p => {
+        if (!p.done) setExportProgress(`Writing ${p.path.split(/[\\/]/).pop()}: ${(p.bytes_written / 1e6).toFixed(1)} MB`);
+    });
 
     try {
         // 1. Prepare 3D View if any layer needs STL
@@ -321,7 +336,7 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                     const fullPath = await join(folderPath as string, fileName);
 
                     // Export DXF for this sheet
-                    await invoke("export_layer_files", {
+                    const result: any = await invoke("export_layer_files", {
                         request: {
                             filepath: fullPath,
                             file_type: "DXF",
@@ -330,9 +345,13 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                             outline,
                             shapes: slicedShapes,
                             layer_thickness: sheetThickness,
-                            stl_content: null
+                            stl_content: null,
+                            project_id: projectId
                         }
                     });
+                    if (result?.verified === false) {
+                        unverified.push(`${fileName}: ${result.issues.join("; ")}`);
+                    }
                 }
 
             } else {
@@ -346,7 +365,6 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                 const fullPath = await join(folderPath as string, fileName);
 
                 const layerThickness = evaluateExpression(layer.thicknessExpression, params);
-                let stl_content: number[] | null = null;
                 let shapes: any[] = [];
 
                 if (method === "3D printed") {
@@ -358,31 +376,18 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                         continue;
                     }
 
-                    if (stlParts.length > 1) {
-                        // Multi-file export
-                        for (let i = 0; i < stlParts.length; i++) {
-                            const partFileName = `${planName}_${layer.name.replace(/[^a-zA-Z0-9]/g, '_')}_Part${i+1}.${extension}`;
-                            const partPath = await join(folderPath as string, partFileName);
-                            
-                            await invoke("export_layer_files", {
-                                request: {
-                                    filepath: partPath,
-                                    file_type: rustFormat,
-                                    machining_type: "Carved/Printed",
-                                    cut_direction: layer.carveSide,
-                                    outline,
-                                    shapes: [],
-                                    layer_thickness: layerThickness,
-                                    stl_content: Array.from(stlParts[i])
-                                }
-                            });
-                        }
-                        // Skip the single export below
-                        continue; 
-                    } else {
-                        // Single file
-                        stl_content = Array.from(stlParts[0]);
+                    // Meshes are streamed to disk in chunks, one file per part when split
+                    for (let i = 0; i < stlParts.length; i++) {
+                        const partPath = stlParts.length > 1
+                            ? await join(folderPath as string, `${planName}_${layer.name.replace(/[^a-zA-Z0-9]/g, '_')}_Part${i+1}.${extension}`)
+                            : fullPath;
+                        await streamExportFile(partPath, stlParts[i], projectId, {
+                            file_type: rustFormat,
+                            cut_direction: layer.carveSide,
+                            layer_thickness: layerThickness
+                        });
                     }
+                    continue;
                 } else {
                     const effectiveType = method === "Laser cut" ? "Cut" as const : "Carved/Printed" as const;
                     shapes = await collectExportShapesAsync(
@@ -396,7 +401,7 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                     );
                 }
 
-                await invoke("export_layer_files", {
+                const result: any = await invoke("export_layer_files", {
                     request: {
                         filepath: fullPath,
                         file_type: rustFormat,
@@ -405,17 +410,40 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                         outline,
                         shapes,
                         layer_thickness: layerThickness,
-                        stl_content
+                        stl_content: null,
+                        project_id: projectId
                     }
                 });
+                if (result?.verified === false) {
+                    unverified.push(`${fileName}: ${result.issues.join("; ")}`);
+                }
+
+                // The toolpath worker's programs for this layer ("_rough"/"_finish" per tool), streamed alongside the depth map
+                if (method === "CNC") {
+                    for (const program of activeGcode[layer.id] || []) {
+                        const suffix = program.name ? `_${program.name}` : "";
+                        const gcodePath = await join(folderPath as string, `${planName}_${layer.name.replace(/[^a-zA-Z0-9]/g, '_')}${suffix}.nc`);
+                        await streamExportFile(gcodePath, program.gcode, projectId, {
+                            file_type: "GCODE",
+                            program: program.name || "full",
+                            cut_direction: layer.carveSide,
+                            layer_thickness: layerThickness
+                        });
+                    }
+                }
             }
         }
         setExportProgress("");
-        alert("Bulk export successful!");
+        if (unverified.length > 0) {
+            alert("Bulk export finished, but some files did not match the source geometry:\n" + unverified.join("\n"));
+        } else {
+            alert("Bulk export successful!");
+        }
     } catch (e) {
         console.error("Bulk export failed", e);
-        alert("Export failed: " + e);
+        alert("Export failed: " + describeError(e));
     } finally {
+        unlisten();
         setIsExporting(false);
     }
   };
@@ -527,6 +555,7 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
   useEffect(() => {
     if (!activePlan || !targetFootprint) { 
         setActiveToolpaths({}); 
+        setActiveGcode({});
         return; 
     }
 
@@ -538,6 +567,8 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
             
             if (method === "CNC") {
                 const settings = activePlan.cncSettings[layer.id] || DEFAULT_CNC;
+                const material = activePlan.layerMaterials?.[layer.id] ||
+                    Object.keys(MATERIAL_DATA).find(m => MATERIAL_DATA[m].methods.includes(method)) || "";
                 callWorker("computeToolpath", {
                     shapes: targetFootprint.shapes,
                     layerId: layer.id,
@@ -547,9 +578,11 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                     settings,
                     layerThickness: thickness,
                     bottomZ: currentZAccum,
-                    carveSide: layer.carveSide
+                    carveSide: layer.carveSide,
+                    materialPassDepth: MATERIAL_DATA[material]?.maxPassDepth
                 }).then(result => {
                     setActiveToolpaths(prev => ({ ...prev, [layer.id]: result.toolpaths }));
+                    setActiveGcode(prev => ({ ...prev, [layer.id]: result.programs }));
                 });
             } else {
                 setActiveToolpaths(prev => {
@@ -558,13 +591,19 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                     delete n[layer.id];
                     return n;
                 });
+                setActiveGcode(prev => {
+                    if (!prev[layer.id]) return prev;
+                    const n = { ...prev };
+                    delete n[layer.id];
+                    return n;
+                });
             }
             currentZAccum += thickness;
         });
     }, 500); // 500ms debounce for CNC parameter changes
 
     return () => clearTimeout(timer);
-  }, [activePlan?.cncSettings, activePlan?.layerMethods, targetFootprint, params, stackup]);
+  }, [activePlan?.cncSettings, activePlan?.layerMethods, activePlan?.layerMaterials, targetFootprint, params, stackup]);
   const mappedVisibleLayers = useMemo(() => {
     if (!visualStack) return layerVisibility;
     const res = { ...layerVisibility };
@@ -702,7 +741,7 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                                         />
                                     </div>
                                     <div className="cnc-prop">
-                                        <label>Step-down</label>
+                                        <label title="Capped by the layer material's maximum pass depth">Step-down</label>
                                         <ExpressionEditor 
                                             value={activePlan.cncSettings?.[layer.id]?.stepDownExpression || DEFAULT_CNC.stepDownExpression} 
                                             onChange={(val) => updateCNCSetting(layer.id, "stepDownExpression", val)} 
@@ -733,6 +772,68 @@ export default function FabricationEditor({ fabPlans, setFabPlans, footprints, s
                                             params={params} 
                                         />
                                     </div>
+                                    <div className="cnc-prop">
+                                        <label>Arcs</label>
+                                        <select 
+                                            value={activePlan.cncSettings?.[layer.id]?.arcMode || "IJK"}
+                                            onChange={(e) => updateCNCSetting(layer.id, "arcMode", e.target.value)}
+                                        >
+                                            <option value="IJK">G2/G3 (IJ)</option>
+                                            <option value="R">G2/G3 (R)</option>
+                                            <option value="None">G1 only</option>
+                                        </select>
+                                    </div>
+                                    <div className="cnc-prop">
+                                        <label>Entry</label>
+                                        <select 
+                                            value={activePlan.cncSettings?.[layer.id]?.entry || "Plunge"}
+                                            onChange={(e) => updateCNCSetting(layer.id, "entry", e.target.value)}
+                                        >
+                                            <option value="Plunge">Plunge</option>
+                                            <option value="Ramp">Ramp along path</option>
+                                        </select>
+                                    </div>
+                                    {activePlan.cncSettings?.[layer.id]?.entry === "Ramp" && (
+                                        <div className="cnc-prop">
+                                            <label>Ramp Angle</label>
+                                            <ExpressionEditor 
+                                                value={activePlan.cncSettings?.[layer.id]?.rampAngleExpression || "3"} 
+                                                onChange={(val) => updateCNCSetting(layer.id, "rampAngleExpression", val)} 
+                                                params={params} 
+                                            />
+                                        </div>
+                                    )}
+                                    <div className="cnc-prop">
+                                        <label>Finishing</label>
+                                        <select 
+                                            value={activePlan.cncSettings?.[layer.id]?.finishing || "None"}
+                                            onChange={(e) => updateCNCSetting(layer.id, "finishing", e.target.value)}
+                                        >
+                                            <option value="None">None</option>
+                                            <option value="Same tool">Same tool</option>
+                                            <option value="Separate tool">Separate tool</option>
+                                        </select>
+                                    </div>
+                                    {(activePlan.cncSettings?.[layer.id]?.finishing || "None") !== "None" && (
+                                        <div className="cnc-prop">
+                                            <label title="Left on walls and floors by roughing">Allowance</label>
+                                            <ExpressionEditor 
+                                                value={activePlan.cncSettings?.[layer.id]?.stockAllowanceExpression || "0.2"} 
+                                                onChange={(val) => updateCNCSetting(layer.id, "stockAllowanceExpression", val)} 
+                                                params={params} 
+                                            />
+                                        </div>
+                                    )}
+                                    {activePlan.cncSettings?.[layer.id]?.finishing === "Separate tool" && (
+                                        <div className="cnc-prop">
+                                            <label>Finish Tool Dia</label>
+                                            <ExpressionEditor 
+                                                value={activePlan.cncSettings?.[layer.id]?.finishToolDiameterExpression || activePlan.cncSettings?.[layer.id]?.toolDiameterExpression || DEFAULT_CNC.toolDiameterExpression} 
+                                                onChange={(val) => updateCNCSetting(layer.id, "finishToolDiameterExpression", val)} 
+                                                params={params} 
+                                            />
+                                        </div>
+                                    )}
                                 </div>
                             )}
 
@@ -1,7 +1,6 @@
 // src/components/FootprintEditor.tsx
 import React, { useState, useRef, useEffect, useLayoutEffect, useCallback, useMemo } from "react";
 import { invoke } from "@tauri-apps/api/core";
-import { save } from "@tauri-apps/plugin-dialog";
 import { Footprint, FootprintShape, Parameter, StackupLayer, FootprintReference, FootprintLine, FootprintWireGuide, FootprintMesh, FootprintBoardOutline, Point, MeshAsset, FootprintPolygon, FootprintUnion, FootprintText, FootprintSplitLine } from "../types";
 import Footprint3DView, { Footprint3DViewHandle } from "./Footprint3DView";
 import { modifyExpression, isFootprintOptionValid, evaluateExpression, resolvePoint, bezier1D, getShapeAABB, isShapeInSelection, rotatePoint, getAvailableWireGuides, findWireGuideByPath, getFootprintAABB, getTransformAlongLine, getClosestDistanceAlongLine, getLineLength, repairBoardAssignments, collectGlobalObstacles, getTessellatedBoardOutline } from "../utils/footprintUtils";
@@ -11,7 +10,8 @@ import FootprintPropertiesPanel from "./FootprintPropertiesPanel";
 import { IconCircle, IconRect, IconLine, IconGuide, IconOutline, IconMesh, IconPolygon, IconText, IconSplit  } from "./Icons";
 import ShapeListPanel from "./ShapeListPanel";
 import { useUndoHistory } from "../hooks/useUndoHistory"; 
-import { collectExportShapesAsync } from "../utils/exportUtils";
+import { collectExportShapesAsync, streamExportFile } from "../utils/exportUtils";
+import { describeError } from "../utils/messages";
 import './FootprintEditor.css';
 
 // --- GLOBAL CLIPBOARD (Persists across footprint switches) ---
@@ -31,6 +31,7 @@ interface Props {
   stackup: StackupLayer[];
   meshAssets: MeshAsset[];
   onRegisterMesh: (asset: MeshAsset) => void;
+  projectId: string | null; // Scope for export directory approvals
 }
 
 // ------------------------------------------------------------------
@@ -61,7 +62,7 @@ const LayerVisibilityPanel = ({
   stackup: StackupLayer[];
   visibility: Record<string, boolean>;
   onToggle: (id: string) => void;
-  onExport: (id: string, type: "SVG_DEPTH" | "SVG_CUT" | "DXF_CUT" | "SVG" | "DXF" | "STL") => void;
+  onExport: (id: string, type: "SVG_DEPTH" | "SVG_CUT" | "DXF_DEPTH" | "DXF_HATCH" | "DXF_CUT" | "SVG" | "DXF" | "STL") => void;
   isBoard: boolean;
 }) => {
   const [collapsed, setCollapsed] = useState(false);
@@ -121,6 +122,8 @@ const LayerVisibilityPanel = ({
                                     <button className="vis-toggle-btn" style={{minWidth: '35px'}} onClick={() => onExport(layer.id, "STL")}>STL</button>
                                     <button className="vis-toggle-btn" style={{minWidth: '35px'}} onClick={() => onExport(layer.id, "SVG_DEPTH")}>SVG</button>
                                     <button className="vis-toggle-btn" style={{minWidth: '35px'}} onClick={() => onExport(layer.id, "SVG_CUT")}>SVG Cut</button>
+                                    <button className="vis-toggle-btn" style={{minWidth: '35px'}} onClick={() => onExport(layer.id, "DXF_DEPTH")}>DXF</button>
+                                    <button className="vis-toggle-btn" style={{minWidth: '35px'}} onClick={() => onExport(layer.id, "DXF_HATCH")} title="Depth regions as filled HATCH entities">DXF Hatch</button>
                                     <button className="vis-toggle-btn" style={{minWidth: '35px'}} onClick={() => onExport(layer.id, "DXF_CUT")}>DXF Cut</button>
                                 </>
                             )}
@@ -235,7 +238,7 @@ const MouseIcon = ({ left, right, middle, scroll }: { left?: boolean, right?: bo
     </svg>
 );
 
-export default function FootprintEditor({ footprint: initialFootprint, allFootprints, onUpdate, onClose, onEditChild, params, stackup, meshAssets, onRegisterMesh }: Props) {
+export default function FootprintEditor({ footprint: initialFootprint, allFootprints, onUpdate, onClose, onEditChild, params, stackup, meshAssets, onRegisterMesh, projectId }: Props) {
   // --- HISTORY HOOK ---
   // Updated to include selection in the present state
   const { 
@@ -2597,7 +2600,7 @@ const handleUngroup = (unionId: string) => {
         }
     };
 
-const handleExport = async (layerId: string, format: "SVG_DEPTH" | "SVG_CUT" | "DXF_CUT" | "SVG" | "DXF" | "STL") => {
+const handleExport = async (layerId: string, format: "SVG_DEPTH" | "SVG_CUT" | "DXF_DEPTH" | "DXF_HATCH" | "DXF_CUT" | "SVG" | "DXF" | "STL") => {
     const layer = stackup.find(l => l.id === layerId);
     if (!layer) return;
 
@@ -2636,14 +2639,20 @@ const handleExport = async (layerId: string, format: "SVG_DEPTH" | "SVG_CUT" | "
     }
 
     // 1. Open Save Dialog
-    const suffix = (isCutStyle && layer.type !== "Cut") ? "_cut" : (format === "SVG_DEPTH" ? "_depth" : "");
-    const path = await save({
-        defaultPath: `${footprint.name.replace(/[^a-zA-Z0-9]/g, '_')}_${layer.name.replace(/[^a-zA-Z0-9]/g, '_')}${suffix}.${extension}`,
-        filters: [{
-            name: `${rustFormat} File`,
+    const suffix = (isCutStyle && layer.type !== "Cut") ? "_cut" : (format === "SVG_DEPTH" || format === "DXF_DEPTH" || format === "DXF_HATCH" ? "_depth" : "");
+    // The backend shows the save dialog and approves only the folder the user picks there
+    let path: string | null;
+    try {
+        path = await invoke("pick_export_file", {
+            projectId,
+            defaultName: `${footprint.name.replace(/[^a-zA-Z0-9]/g, '_')}_${layer.name.replace(/[^a-zA-Z0-9]/g, '_')}${suffix}.${extension}`,
+            filterName: `${rustFormat} File`,
             extensions: [extension]
-        }]
-    });
+        });
+    } catch (e) {
+        alert("Export failed: " + describeError(e));
+        return;
+    }
 
     if (!path) return;
 
@@ -2685,21 +2694,26 @@ const handleExport = async (layerId: string, format: "SVG_DEPTH" | "SVG_CUT" | "
         footprint3DRef.current // Pass view ref to access worker
     );
 
-    // 3. Prepare STL Data if needed
-    let stlContent: number[] | null = null;
+    // 3. STL meshes come from the 3D view and are streamed to disk in chunks
     if (rustFormat === "STL") {
         const raw = footprint3DRef.current?.getLayerSTL(layerId);
-        if (raw) {
-            stlContent = Array.from(raw);
-        } else {
+        if (!raw) {
              alert("Warning: Could not retrieve 3D mesh for STL export. Ensure the layer is visible in the 3D preview.");
              return;
         }
+        try {
+            const size = await streamExportFile(path, raw, projectId, { file_type: "STL", cut_direction: layer.carveSide, layer_thickness: layerThickness });
+            alert(`Exported ${path} (${(size / 1e6).toFixed(1)} MB)`);
+        } catch (e) {
+            console.error("Export failed", e);
+            alert("Export failed: " + describeError(e));
+        }
+        return;
     }
 
     // 4. Send to Rust
     try {
-        await invoke("export_layer_files", {
+        const result: any = await invoke("export_layer_files", {
             request: {
                 filepath: path,
                 file_type: rustFormat,
@@ -2708,13 +2722,19 @@ const handleExport = async (layerId: string, format: "SVG_DEPTH" | "SVG_CUT" | "
                 outline,
                 shapes,
                 layer_thickness: layerThickness,
-                stl_content: stlContent
+                stl_content: null,
+                project_id: projectId,
+                dxf_hatch: format === "DXF_HATCH"
             }
         });
-        alert(`Export initiated for ${path}`);
+        if (result?.verified === false) {
+            alert(`Exported ${path}, but the file does not match the source geometry:\n${result.issues.join("\n")}`);
+        } else {
+            alert(`Export initiated for ${path}`);
+        }
     } catch (e) {
         console.error("Export failed", e);
-        alert("Export failed: " + e);
+        alert("Export failed: " + describeError(e));
     }
   };
 
@@ -13,9 +13,10 @@ interface Props {
   stackup: StackupLayer[];
   meshAssets: MeshAsset[];
   onRegisterMesh: (asset: MeshAsset) => void;
+  projectId: string | null; // Scope for export directory approvals
 }
 
-export default function FootprintLibrary({ footprints, setFootprints, params, stackup, meshAssets, onRegisterMesh }: Props) {
+export default function FootprintLibrary({ footprints, setFootprints, params, stackup, meshAssets, onRegisterMesh, projectId }: Props) {
   const [editStack, setEditStack] = useState<string[]>([]);
   const [dragOverIndex, setDragOverIndex] = useState<number | null>(null);
   const dragItemIndex = useRef<number | null>(null);
@@ -96,6 +97,7 @@ export default function FootprintLibrary({ footprints, setFootprints, params, st
         stackup={stackup}
         meshAssets={meshAssets}
         onRegisterMesh={onRegisterMesh}
+        projectId={projectId}
       />
     );
   }
@@ -136,6 +136,31 @@ const FootprintPropertiesPanel = ({
   }, [scrollToPointIndex]);
 
   // NEW: Helper to render the Lock Toggle
+  // Optional per-layer feed/speed/power overrides; left blank the layer's machine settings apply
+  const renderMachineOverrides = (target: { id: string; assignedLayers: Record<string, LayerAssignment | string> }, layerId: string, assignment: LayerAssignment) => {
+      const fields: [keyof LayerAssignment, string][] = [
+          ["feedOverride", "Feed (mm/min)"],
+          ["speedOverride", "Spindle RPM"],
+          ["powerOverride", "Laser Power (%)"],
+      ];
+      return (
+          <div className="layer-depth-wrapper">
+              <div style={{ display: 'flex', flexDirection: 'column', gap: '10px' }}>
+                  {fields.map(([field, label]) => (
+                      <div key={field} style={{ flex: 1 }}>
+                          <div style={{ fontSize: '0.7em', color: '#888', marginBottom: '2px' }}>{label}</div>
+                          <ExpressionEditor value={assignment[field] || ""} onChange={(val) => {
+                                  const newAssignments = { ...target.assignedLayers };
+                                  newAssignments[layerId] = { ...assignment, [field]: val };
+                                  updateShape(target.id, "assignedLayers", newAssignments);
+                              }} params={params} placeholder="Layer default" />
+                      </div>
+                  ))}
+              </div>
+          </div>
+      );
+  };
+
   const renderLockToggle = (targetId: string, currentLocked: boolean | undefined) => (
       <div style={{ marginBottom: '10px', padding: '8px', background: '#2a2a2a', borderRadius: '4px', border: '1px solid #444', display: 'flex', alignItems: 'center', justifyContent: 'space-between' }}>
           <label className="checkbox-label" style={{color: currentLocked ? '#ff4d4d' : '#ccc', fontWeight: currentLocked ? 'bold' : 'normal'}}>
@@ -646,6 +671,7 @@ const FootprintPropertiesPanel = ({
                                         </div>
                                     </div>
                                 )}
+                                {isChecked && renderMachineOverrides(shape, layer.id, assignment)}
                             </div>
                         );
                     })}
@@ -1160,6 +1186,7 @@ const FootprintPropertiesPanel = ({
                                         </div>
                                     </div>
                                 )}
+                                {isChecked && renderMachineOverrides(shape, layer.id, assignment)}
                             </div>
                         );
                     })}
@@ -1250,6 +1277,7 @@ const FootprintPropertiesPanel = ({
                         </div>
                     </div>
                 )}
+                {isChecked && renderMachineOverrides(shape, layer.id, assignment)}
               </div>
             );
           })}
@@ -12,9 +12,11 @@ import { invoke } from "@tauri-apps/api/core";
 import * as THREE from "three";
 import TetrahedralRenderer from "./TetrahedralRenderer";
 import SurfaceRenderer from "./SurfaceRenderer";
-import { FabricationPlan, Footprint, StackupLayer, Parameter } from "../types";
+import { FabricationPlan, Footprint, FootprintBoardOutline, StackupLayer, Parameter, ReportUnits } from "../types";
 import { callWorker } from "./Footprint3DView"; // Reuse the Manifold worker connection
-import { evaluateExpression } from "../utils/footprintUtils";
+import { evaluateExpression, resolvePoint } from "../utils/footprintUtils";
+import { collectExportShapesAsync } from "../utils/exportUtils";
+import { describeError, formatMessage, isBackendMessage, onMeshingProgress } from "../utils/messages";
 
 // --- Types ---
 interface ComparisonMetrics {
@@ -23,6 +25,19 @@ interface ComparisonMetrics {
     computedAt: number;
 }
 
+interface ReportQuantity {
+    code: string; // REPORT_* message code; `name` is the English label
+    name: string;
+    value: number;
+    unit: string;
+}
+
+const UNIT_PRESETS: { label: string, units: ReportUnits }[] = [
+    { label: "mm · N · MPa", units: { length: "mm", force: "n", stress: "mpa" } },
+    { label: "mm · kgf · kgf/mm²", units: { length: "mm", force: "kgf", stress: "kgf/mm2" } },
+    { label: "in · lbf · psi", units: { length: "in", force: "lbf", stress: "psi" } },
+];
+
 interface Bounds {
     min: THREE.Vector3;
     max: THREE.Vector3;
@@ -30,7 +45,14 @@ interface Bounds {
     size: THREE.Vector3;
 }
 
+// Gmsh's own stderr for GMSH_FAILED, otherwise the error text
+function gmshOutput(e: unknown): string {
+    if (isBackendMessage(e)) return e.code === "GMSH_FAILED" ? String(e.params?.stderr) : e.message;
+    return String(e);
+}
+
 // --- Helper UI Components ---
+
 function LoadingOverlay({ message }: { message: string }) {
     return (
         <div style={{
@@ -57,13 +79,18 @@ interface Props {
     fabPlans: FabricationPlan[];
     stackup: StackupLayer[];
     params: Parameter[];
+    reportUnits: ReportUnits;
+    onReportUnitsChange: (units: ReportUnits) => void;
 }
 
-export default function SimulationEditor({ footprints, fabPlans, stackup, params }: Props) {
+export default function SimulationEditor({ footprints, fabPlans, stackup, params, reportUnits, onReportUnitsChange }: Props) {
   // State
   const [activePlanId, setActivePlanId] = useState<string>(fabPlans.length > 0 ? fabPlans[0].id : "");
   const [isProcessing, setIsProcessing] = useState(false);
   const [processMessage, setProcessMessage] = useState("");
+  const [healGeometry, setHealGeometry] = useState(false); // OCC "safe retry" healing preset
+  const [loadAxis, setLoadAxis] = useState<"none" | "x" | "y" | "z">("none"); // Drives the anisotropic size field
+  const [report, setReport] = useState<ReportQuantity[]>([]); // Last mesh run, in the project's units
   
   // Data State
   const [manifoldMetrics, setManifoldMetrics] = useState<ComparisonMetrics | null>(null);
@@ -82,6 +109,40 @@ export default function SimulationEditor({ footprints, fabPlans, stackup, params
   const activePlan = fabPlans.find(p => p.id === activePlanId);
   const targetFootprint = footprints.find(f => f.id === activePlan?.footprintId);
 
+  // Export request per stackup layer, resolved by the backend into the stacked layers Gmsh meshes
+  const resolveStackupLayers = async (plan: FabricationPlan, fp: Footprint) => {
+    const requests = [];
+    for (const layer of stackup) {
+        const thickness = evaluateExpression(layer.thicknessExpression, params);
+        const method = plan.layerMethods[layer.id];
+        const machiningType = method === "Laser cut" || method === "Waterline laser cut" ? "Cut" as const : "Carved/Printed" as const;
+
+        const outlineShape = fp.shapes.find(s => s.id === fp.boardOutlineAssignments?.[layer.id]) as FootprintBoardOutline | undefined;
+        const originX = outlineShape ? evaluateExpression(outlineShape.x, params) : 0;
+        const originY = outlineShape ? evaluateExpression(outlineShape.y, params) : 0;
+        const outline = (outlineShape?.points || []).map(p => {
+            const resolved = resolvePoint(p, fp, footprints, params);
+            return { x: resolved.x + originX, y: resolved.y + originY, handle_in: resolved.handleIn, handle_out: resolved.handleOut };
+        });
+
+        const shapes = await collectExportShapesAsync(fp, fp.shapes, footprints, params, { ...layer, type: machiningType }, thickness, null);
+        requests.push({
+            id: layer.id,
+            request: {
+                filepath: "",
+                file_type: "STEP",
+                machining_type: machiningType,
+                cut_direction: layer.carveSide,
+                outline,
+                shapes,
+                layer_thickness: thickness,
+                stl_content: null
+            }
+        });
+    }
+    return invoke("stackup_layers", { layers: requests });
+  };
+
   // --- 1. Compute Geometry & Compare (Stage 1) ---
   const handleVerifyGeometry = async () => {
     if (!activePlan || !targetFootprint) return;
@@ -137,10 +198,13 @@ export default function SimulationEditor({ footprints, fabPlans, stackup, params
             footprint: targetFootprint,
             stackup: stackup, // Ensure serialization works on Rust side
             params: params,
-            quality: 0.0 // 0.0 means "Don't mesh yet, just build geo and inspect"
+            quality: 0.0, // 0.0 means "Don't mesh yet, just build geo and inspect"
+            layers: await resolveStackupLayers(activePlan, targetFootprint),
+            healing: { preset: healGeometry ? "safe_retry" : "none" }
         };
 
-        const gmshResult: any = await invoke("run_gmsh_meshing", { req: feaRequest });
+        const unlisten = await onMeshingProgress(m => setProcessMessage(formatMessage(m)));
+        const gmshResult: any = await invoke("run_gmsh_meshing", { req: feaRequest }).finally(unlisten);
         
         setGmshMetrics({
             volume: gmshResult.volume,
@@ -153,7 +217,8 @@ export default function SimulationEditor({ footprints, fabPlans, stackup, params
 
     } catch (e) {
         console.error(e);
-        alert("Geometry Verification Failed: " + e);
+        const hint = !healGeometry && gmshOutput(e).includes("more volumes") ? "\n\nTry enabling 'Heal geometry' and verifying again." : "";
+        alert("Geometry Verification Failed: " + describeError(e) + hint);
     } finally {
         setIsProcessing(false);
     }
@@ -170,11 +235,16 @@ export default function SimulationEditor({ footprints, fabPlans, stackup, params
             footprint: targetFootprint,
             stackup: stackup,
             params: params,
-            quality: 1.0 // High quality Request
+            quality: 1.0, // High quality Request
+            layers: await resolveStackupLayers(activePlan, targetFootprint),
+            healing: { preset: healGeometry ? "safe_retry" : "none" },
+            load_direction: loadAxis === "none" ? null : { x: [1, 0, 0], y: [0, 1, 0], z: [0, 0, 1] }[loadAxis],
+            units: reportUnits
         };
 
         // Call the sidecar via Rust
-        const result: any = await invoke("run_gmsh_meshing", { req: feaRequest });
+        const unlisten = await onMeshingProgress(m => setProcessMessage(formatMessage(m)));
+        const result: any = await invoke("run_gmsh_meshing", { req: feaRequest }).finally(unlisten);
         
         // Result.mesh contains { vertices: [[x,y,z]...], indices: [[n1...n10]...] }
         // We need to flatten vertices for ThreeJS
@@ -186,12 +256,14 @@ export default function SimulationEditor({ footprints, fabPlans, stackup, params
             vertices: flatVerts,
             indices: tetIndices
         });
+        setReport(result.report || []);
         
         setViewMode('mesh');
 
     } catch (e) {
         console.error(e);
-        alert("Meshing Failed: " + e);
+        const hint = !healGeometry && gmshOutput(e).includes("more volumes") ? "\n\nTry enabling 'Heal geometry' and meshing again." : "";
+        alert("Meshing Failed: " + describeError(e) + hint);
     } finally {
         setIsProcessing(false);
     }
@@ -229,6 +301,10 @@ export default function SimulationEditor({ footprints, fabPlans, stackup, params
             <button className="secondary" onClick={handleVerifyGeometry} style={{ width: "100%", padding: "10px" }}>
                 Compute & Verify Geometry
             </button>
+            <label style={{ display: "flex", alignItems: "center", gap: "6px", marginTop: "8px", fontSize: "0.85em", color: "#aaa" }}>
+                <input type="checkbox" checked={healGeometry} onChange={(e) => setHealGeometry(e.target.checked)} />
+                Heal geometry (safe retry)
+            </label>
 
             {manifoldMetrics && gmshMetrics && (
                 <div style={{ marginTop: "15px", fontSize: "0.85em", background: "#1a1a1a", padding: "10px", borderRadius: "6px" }}>
@@ -264,6 +340,25 @@ export default function SimulationEditor({ footprints, fabPlans, stackup, params
         {/* Step 2: Meshing */}
         <div style={{ padding: "20px", flex: 1, display: "flex", flexDirection: "column" }}>
             <h4 style={{ margin: "0 0 10px 0", color: "#ccc" }}>2. Discretization</h4>
+            <label style={{ fontSize: "0.85em", color: "#888" }}>Dominant Load Direction</label>
+            <select
+                value={loadAxis}
+                onChange={(e) => setLoadAxis(e.target.value as "none" | "x" | "y" | "z")}
+                style={{ width: "100%", margin: "5px 0 10px 0", padding: "8px", background: "#333", border: "1px solid #555", color: "white" }}
+            >
+                <option value="none">Unknown (isotropic mesh)</option>
+                <option value="z">Z (plate bending)</option>
+                <option value="x">X</option>
+                <option value="y">Y</option>
+            </select>
+            <label style={{ fontSize: "0.85em", color: "#888" }}>Report Units</label>
+            <select
+                value={UNIT_PRESETS.findIndex(p => p.units.length === reportUnits.length && p.units.force === reportUnits.force && p.units.stress === reportUnits.stress)}
+                onChange={(e) => onReportUnitsChange(UNIT_PRESETS[parseInt(e.target.value)].units)}
+                style={{ width: "100%", margin: "5px 0 10px 0", padding: "8px", background: "#333", border: "1px solid #555", color: "white" }}
+            >
+                {UNIT_PRESETS.map((p, i) => <option key={p.label} value={i}>{p.label}</option>)}
+            </select>
             <button 
                 className="primary" 
                 onClick={handleGenerateMesh} 
@@ -306,6 +401,19 @@ export default function SimulationEditor({ footprints, fabPlans, stackup, params
                         Elements: <b>{tetMesh.indices.length}</b><br/>
                         Nodes: <b>{tetMesh.vertices.length / 3}</b>
                     </div>
+
+                    {report.length > 0 && (
+                        <table style={{ width: "100%", marginTop: "10px", fontSize: "0.8em", color: "#888" }}>
+                            <tbody>
+                                {report.map(q => (
+                                    <tr key={q.code}>
+                                        <td>{formatMessage({ code: q.code, params: {}, message: q.name })}</td>
+                                        <td style={{ textAlign: "right", fontFamily: "monospace" }}>{q.value.toPrecision(5)} {q.unit}</td>
+                                    </tr>
+                                ))}
+                            </tbody>
+                        </table>
+                    )}
                 </div>
             )}
         </div>
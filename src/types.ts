@@ -28,6 +28,10 @@ export interface LayerAssignment {
     depth: string;
     endmillRadius: string;
     inputFillet?: string; // Expression for top fillet/chamfer radius
+    // Optional machine-setting overrides for this shape on this layer; empty uses the layer's settings
+    feedOverride?: string;  // mm/min
+    speedOverride?: string; // Spindle RPM
+    powerOverride?: string; // Laser power, percent
 }
 
 export interface BaseShape {
@@ -206,7 +210,17 @@ export interface ProjectData {
   footprints: Footprint[];
   meshes: MeshAsset[];
   fabPlans?: FabricationPlan[];
+  reportUnits?: ReportUnits;
 }
+
+// Units used for simulation inputs and exported reports. The solver itself always runs in mm-N-MPa.
+export interface ReportUnits {
+  length: "mm" | "in";
+  force: "n" | "kgf" | "lbf";
+  stress: "mpa" | "psi" | "kgf/mm2";
+}
+
+export const DEFAULT_REPORT_UNITS: ReportUnits = { length: "mm", force: "n", stress: "mpa" };
 // --- FABRICATION TYPES ---
 export type CutFabricationMethod = "Laser cut";
 export type CarvedFabricationMethod = "CNC" | "Waterline laser cut" | "3D printed";
@@ -230,8 +244,17 @@ export interface CNCSettings {
   stepOverExpression: string;
   feedrateExpression: string;
   spindleRpmExpression: string;
+  arcMode?: GCodeArcMode; // Post-processor arc output; defaults to "IJK"
+  entry?: "Plunge" | "Ramp"; // How each pass enters the cut; defaults to "Plunge"
+  rampAngleExpression?: string; // Degrees from horizontal for ramped entry; defaults to "3"
+  finishing?: "None" | "Same tool" | "Separate tool"; // Full-depth finishing pass after roughing; defaults to "None"
+  stockAllowanceExpression?: string; // Stock roughing leaves on walls and floors for finishing; defaults to "0.2"
+  finishToolDiameterExpression?: string; // Finishing tool when "Separate tool"; defaults to the roughing tool
 }
 
+// How G-code arcs are written: centre offsets, radius words, or "None" for controllers without G2/G3 (G1 segments only)
+export type GCodeArcMode = "IJK" | "R" | "None";
+
 export interface FabricationPlan {
   id: string;
   name: string;
@@ -1,8 +1,55 @@
 // src/utils/exportUtils.ts
-import { Footprint, FootprintShape, Parameter, StackupLayer, FootprintReference, FootprintUnion, FootprintCircle, FootprintRect, FootprintLine, FootprintPolygon, FootprintSplitLine } from "../types";
+import { Footprint, FootprintShape, LayerAssignment, Parameter, StackupLayer, FootprintReference, FootprintUnion, FootprintCircle, FootprintRect, FootprintLine, FootprintPolygon, FootprintSplitLine } from "../types";
 import { evaluateExpression, resolvePoint, getTransformAlongLine, offsetPolygonContour, convertRectToPolyPoints } from "./footprintUtils";
 import { Footprint3DViewHandle } from "../components/Footprint3DView";
 import * as THREE from "three";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+
+// Bytes per append_export_stream call; keeps each IPC message small for multi-hundred-MB files
+const STREAM_CHUNK_BYTES = 1 << 20;
+
+// Bytes-written progress the backend reports while writing any export
+export interface ExportProgress {
+    path: string;
+    bytes_written: number;
+    done: boolean;
+}
+
+export function onExportProgress(callback: (progress: ExportProgress) => void): Promise<UnlistenFn> {
+    return listen<ExportProgress>("export-progress", e => callback(e.payload));
+}
+
+// Writes frontend-generated content (STL meshes, G-code) to disk chunk by chunk instead of in one
+// request. `params` describes the file in the artifact index. Returns the file size in bytes.
+export async function streamExportFile(path: string, content: Uint8Array | string, projectId: string | null, params: Record<string, unknown>): Promise<number> {
+    const bytes = typeof content === "string" ? new TextEncoder().encode(content) : content;
+    const id: number = await invoke("begin_export_stream", { filepath: path, projectId });
+    try {
+        for (let offset = 0; offset < bytes.length; offset += STREAM_CHUNK_BYTES) {
+            await invoke("append_export_stream", { id, chunk: Array.from(bytes.subarray(offset, offset + STREAM_CHUNK_BYTES)) });
+        }
+        return await invoke("finish_export_stream", { id, params: { ...params, project_id: projectId } });
+    } catch (e) {
+        await invoke("cancel_export_stream", { id });
+        throw e;
+    }
+}
+
+// Per-shape feed/speed/power overrides under the exporter's field names; blank or non-positive ones are left out
+function evaluateOverrides(assign: LayerAssignment | string | undefined, params: Parameter[]): { feed?: number; speed?: number; power?: number } {
+    const out: { feed?: number; speed?: number; power?: number } = {};
+    if (!assign || typeof assign !== 'object') return out;
+    const pairs: ["feed" | "speed" | "power", string | undefined][] = [
+        ["feed", assign.feedOverride], ["speed", assign.speedOverride], ["power", assign.powerOverride]
+    ];
+    pairs.forEach(([key, expr]) => {
+        if (!expr || expr.trim() === "") return;
+        const val = evaluateExpression(expr, params);
+        if (val > 0) out[key] = val;
+    });
+    return out;
+}
 
 export async function collectExportShapesAsync(
     contextFootprint: Footprint, 
@@ -83,6 +130,8 @@ export async function collectExportShapesAsync(
                  );
                  // Note: this path bypasses hierarchical structure, flattening the union into polygons
                  const sliceResult = slicePolygonContours(contourPoints, effectiveDepth, overrideRadius, 0, 0, 0);
+                 const overrides = evaluateOverrides(assigned, params);
+                 sliceResult.forEach(child => Object.assign(child, overrides));
                  result = result.concat(sliceResult);
              } else {
                  const uAngle = evaluateExpression(u.angle, params);
@@ -93,10 +142,12 @@ export async function collectExportShapesAsync(
                      { x: gx, y: gy, angle: globalAngle }, shouldForceChildren, currentLocal
                  );
                  if (overrideDepth >= 0) {
+                     const overrides = evaluateOverrides(assigned, params);
                      childrenExport.forEach(child => {
                          child.depth = overrideDepth;
                          if (overrideRadius > 0) child.endmill_radius = overrideRadius;
                          if (overrideInputFillet > 0) child.input_fillet = overrideInputFillet;
+                         Object.assign(child, overrides);
                      });
                  }
                  result = result.concat(childrenExport);
@@ -132,6 +183,7 @@ export async function collectExportShapesAsync(
              if (!forceInclude && depth <= 0.0001) continue;
 
              const exportObj: any = { x: gx, y: gy, depth: depth };
+             if (explicitAssignment) Object.assign(exportObj, evaluateOverrides(shape.assignedLayers![layer.id], params));
              if (layer.type === "Carved/Printed") {
                  if (endmillRadius > 0) exportObj.endmill_radius = endmillRadius;
                  if (inputFillet > 0) exportObj.input_fillet = inputFillet;
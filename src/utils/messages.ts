@@ -0,0 +1,96 @@
+// src/utils/messages.ts
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+
+// Coded message from the backend (shortstack_core::messages::Message). `message` is the
+// backend's English text, used when a code has no entry below.
+export interface BackendMessage {
+    code: string;
+    params?: Record<string, unknown>;
+    message: string;
+}
+
+// English texts by code; {name} is replaced by params.name. A translation is another table
+// with the same keys.
+const EN: Record<string, string> = {
+    MESHING_WRITE_GEO: "Writing geometry script...",
+    MESHING_RUN_GMSH: "Meshing with Gmsh (this may take a moment)...",
+    MESHING_READ_MESH: "Reading mesh...",
+    MESHING_ARCHIVE: "Archiving run...",
+    GEO_WRITE_FAILED: "Could not write the geometry script: {error}",
+    GMSH_LAUNCH_FAILED: "Could not start Gmsh: {error}",
+    GMSH_FAILED: "Gmsh failed: {stderr}",
+    MSH_READ_FAILED: "Could not read the mesh file: {error}",
+    MSH_TRUNCATED: "The mesh file is truncated",
+    MESH_TOO_LARGE: "Mesh too large: needs ~{needed_mb} MB but the budget is {budget_mb} MB. Increase mesh size to ≥ {suggested_size} mm (currently {mesh_size} mm) or raise the memory budget.",
+    MESH_IMPORTED: "Mesh imported successfully. {elements} elements.",
+    MESH_QUALITY_FAILED: "Mesh quality check failed. {count} elements have negative/zero Jacobian.",
+    MESH_EMPTY: "Mesh has no elements",
+    APP_DATA_UNAVAILABLE: "The app data folder is unavailable",
+    ELEMENT_INVERTED: "Element {element} is inverted (Jacobian ratio {jacobian_ratio})",
+    ELEMENT_SINGULAR: "Element {element} has a singular Jacobian",
+    INVALID_MESH_OPTIONS: "Invalid TetGen options: {options}",
+    TETGEN_FAILED: "TetGen failed to generate a mesh",
+    TETGEN_TOO_MANY_ELEMENTS: "TetGen generated {tetrahedra} tetrahedra. Try increasing the max edge length.",
+    MESHING_PANICKED: "Meshing crashed",
+    READ_FAILED: "Could not read {path}: {error}",
+    UNSUPPORTED_FORMAT: "Unsupported format: {format}",
+    BOOLEAN_UNION_FAILED: "Could not union the shapes: {error}",
+    EXPORT_STREAM_UNKNOWN: "The export was interrupted; please export again",
+    STL_CONTENT_MISSING: "The STL export has no mesh to write",
+    OUTLINE_MISSING: "The board outline is missing",
+    OUTLINE_DEGENERATE: "The board outline is degenerate",
+    OUTLINE_TOO_FEW_POINTS: "The board outline needs at least {min_points} points",
+    VALUE_NOT_POSITIVE: "{name} must be positive",
+    INVALID_EXPORT_PATH: "Invalid export path: {path}",
+    WRITE_FAILED: "Could not write {path}: {error}",
+    INVALID_PATH: "Invalid path {path}: {error}",
+    PATH_NOT_APPROVED: "{path} is outside the folders approved for this project. Pick the folder again in the export dialog.",
+    SPLIT_KERF_NOT_POSITIVE: "Split kerf must be positive",
+    OPTIMIZATION_PANICKED: "The optimizer crashed",
+    EVAL_PANICKED: "The split evaluation crashed",
+    SPLIT_NO_CUTS: "The optimization result contains no cuts",
+    SPLIT_MISSES_BOARD: "Cut {cut} does not cross the board",
+    OPTIMIZATION_RUN_INVALID: "Invalid optimization run id '{id}'",
+    OPTIMIZATION_RUN_CORRUPT: "Stored input for run {id} does not match its hash",
+    TRACE_NO_PART: "No part found in the image; try another threshold",
+    DEPTH_MAP_ASPECT_MISMATCH: "Image aspect ratio {image_aspect} does not match the board's {board_aspect}",
+    TOOL_LIBRARY_EMPTY: "The tool library is empty",
+    INVALID_TOOL: "Invalid tool '{tool}': kind must be flat, ball or vbit with a positive diameter",
+    SCALLOP_NEEDS_CARVED_LAYER: "Scallop estimation needs a carved layer",
+    BALANCE_NO_LAYERS: "No layers to weigh",
+    BALANCE_NO_MASS: "Configuration '{configuration}' has no mass",
+    PROBE_TOO_FEW_POINTS: "At least {needed} probe points are needed",
+    PROBE_UNDERCONSTRAINED: "Probe points do not constrain the fit; spread them around more than one edge",
+    REPLAY_PANICKED: "Session replay crashed",
+    REPLAY_INVALID_ID: "The session contains an invalid layer or cut id: {id}",
+    REPORT_VOLUME: "Volume",
+    REPORT_SURFACE_AREA: "Surface area",
+    REPORT_YOUNGS_MODULUS: "Young's modulus",
+    REPORT_POISSONS_RATIO: "Poisson's ratio",
+    REPORT_TOTAL_LOAD: "Total applied load",
+};
+
+// Numbers are shown with at most two decimals
+function formatParam(value: unknown): string {
+    if (typeof value === "number") return Number.isInteger(value) ? String(value) : value.toFixed(2);
+    return String(value);
+}
+
+export function isBackendMessage(value: unknown): value is BackendMessage {
+    return typeof value === "object" && value !== null && "code" in value && "message" in value;
+}
+
+export function formatMessage(msg: BackendMessage, table: Record<string, string> = EN): string {
+    const template = table[msg.code];
+    if (!template) return msg.message;
+    return template.replace(/\{(\w+)\}/g, (whole, key) => msg.params && key in msg.params ? formatParam(msg.params[key]) : whole);
+}
+
+// Text for anything a command rejected with: coded messages, plain strings or JS errors
+export function describeError(e: unknown): string {
+    return isBackendMessage(e) ? formatMessage(e) : String(e);
+}
+
+export function onMeshingProgress(callback: (msg: BackendMessage) => void): Promise<UnlistenFn> {
+    return listen<BackendMessage>("meshing-progress", e => callback(e.payload));
+}
@@ -823,8 +823,21 @@ interface RustGeometryInput {
     bed_width: number;
     bed_height: number;
     initial_line?: [[number, number], [number, number]] | null; // Optional seed
+    references?: RustReferenceGeometry[] | null; // Named geometry to anchor the result to
+    tolerance?: { obstacle_position: number; outline_dimension: number; samples?: number | null } | null;
+    sdf_resolution?: number | null; // mm grid spacing; enables sampled collision checks
+    fillet_radius?: number | null; // Dovetail corner radius (endmill radius)
+    joint_clearance?: number | null; // Extra radius on mating convex corners
 }
 
+type RustReferenceGeometry =
+    | { type: 'edge'; name: string; start: [number, number]; end: [number, number] }
+    | { type: 'hole'; name: string; x: number; y: number };
+
+type RustAnchoredPoint =
+    | { type: 'edge'; reference: string; fraction: number; offset: number }
+    | { type: 'hole'; reference: string; dx: number; dy: number };
+
 interface RustGeneratedCut {
     id: string;
     start: [number, number];
@@ -833,12 +846,18 @@ interface RustGeneratedCut {
     dovetail_height: number;
     dovetail_t: number;
     flipped: boolean;
+    anchors?: { start: RustAnchoredPoint; end: RustAnchoredPoint } | null;
+    fillet_radius: number;
+    joint_clearance: number;
+    robustness?: number | null; // Fraction of tolerance samples where this cut stays valid
 }
 
 interface RustOptimizationResult {
     success: boolean;
     cost: number;
     shapes: RustGeneratedCut[];
+    robustness?: number | null; // Lowest robustness among `shapes`
+    run_id?: string | null; // Stored run; replay_optimization re-runs it on the exact same input
     debug_points_a: number[][];
     debug_points_b: number[][];
 }
@@ -905,7 +924,7 @@ export async function autoComputeSplitWithRefinement(
         
         try {
             const res = await invoke<RustOptimizationResult>("compute_smart_split", { input });
-            console.log(`Refinement result for cut ${cut.id}: Success=${res.success} Cost=${res.cost.toFixed(4)}`);
+            console.log(`Refinement result for cut ${cut.id}: Success=${res.success} Cost=${res.cost.toFixed(4)} Run=${res.run_id ?? "unsaved"}`);
             lastDebug = { a: res.debug_points_a, b: res.debug_points_b };
             if (res.success && res.shapes.length > 0) {
                 const s = res.shapes[0]; // The GeneratedCut from Rust
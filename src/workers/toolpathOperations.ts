@@ -1,4 +1,4 @@
-import { FootprintRect } from "../types";
+import { FootprintRect, GCodeArcMode } from "../types";
 import { evaluateExpression, getPolyOutlinePoints } from "../utils/footprintUtils";
 import { createLineShape, flattenShapes, shapeToManifold } from "./meshUtils";
 
@@ -10,6 +10,7 @@ class GCodeGenerator {
     private currentY: number = 0;
     private currentZ: number = 0;
     private currentFeed: number = 0;
+    private currentRpm: number = 0;
     private safeZ: number = 5;
     private isRetracted: boolean = true;
     private precision: number = 4;
@@ -18,15 +19,18 @@ class GCodeGenerator {
         safeZ: number, 
         spindleRpm: number, 
         feedRate: number, 
-        private plungeRate: number
+        private plungeRate: number,
+        private arcMode: GCodeArcMode = "IJK"
     ) {
         this.safeZ = safeZ;
         this.lines.push("%");
+        this.lines.push(`; Arcs: ${arcMode === "None" ? "off (G1 only)" : arcMode}`);
         this.lines.push("G90 G17 G21"); // Absolute, XY Plane, mm
         this.lines.push(`G0 Z${this.fmt(safeZ)}`);
         this.lines.push(`S${Math.round(spindleRpm)} M3`);
         this.lines.push(`F${this.fmt(feedRate)}`);
         this.currentFeed = feedRate;
+        this.currentRpm = Math.round(spindleRpm);
         this.currentZ = safeZ;
     }
 
@@ -38,6 +42,21 @@ class GCodeGenerator {
         this.lines.push(`; ${text}`);
     }
 
+    // Switches the cutting feed / spindle speed; no output when unchanged
+    public setFeed(feedRate: number) {
+        if (Math.abs(feedRate - this.currentFeed) < 1e-6) return;
+        this.lines.push(`F${this.fmt(feedRate)}`);
+        this.currentFeed = feedRate;
+    }
+
+    public setSpindle(spindleRpm: number) {
+        const rpm = Math.round(spindleRpm);
+        if (rpm === this.currentRpm) return;
+        this.moveToSafeZ(); // Change speed clear of the material
+        this.lines.push(`S${rpm}`);
+        this.currentRpm = rpm;
+    }
+
     public moveToSafeZ() {
         if (!this.isRetracted || Math.abs(this.currentZ - this.safeZ) > 0.001) {
             this.lines.push(`G0 Z${this.fmt(this.safeZ)}`);
@@ -60,7 +79,56 @@ class GCodeGenerator {
         this.isRetracted = false;
     }
 
-    // Fits G2/G3 arcs to a sequence of points
+    // Enters a closed contour by descending along it from fromZ (no steeper than maxAngleDeg) instead of
+    // plunging straight down, then finishes the lap at z and recuts the ramped stretch
+    public rampAlongPath(points: number[], fromZ: number, z: number, maxAngleDeg: number) {
+        const segCount = points.length / 2 - 1;
+        let perimeter = 0;
+        for (let j = 0; j < segCount; j++) {
+            perimeter += Math.sqrt((points[2*j+2] - points[2*j])**2 + (points[2*j+3] - points[2*j+1])**2);
+        }
+        if (segCount < 1 || perimeter < 1e-6 || fromZ - z < 1e-6 || maxAngleDeg <= 0) {
+            this.rapidTo(points[0], points[1]);
+            this.plunge(z);
+            this.tracePath(points);
+            return;
+        }
+
+        this.rapidTo(points[0], points[1]);
+        this.plunge(fromZ);
+
+        // Short contours are lapped as a helix until the ramp is long enough
+        const rampLength = (fromZ - z) / Math.tan(maxAngleDeg * Math.PI / 180);
+        let travelled = 0;
+        let j = 0;
+        while (true) {
+            const ax = points[2*j], ay = points[2*j+1];
+            const bx = points[2*j+2], by = points[2*j+3];
+            const len = Math.sqrt((bx - ax)**2 + (by - ay)**2);
+
+            if (travelled + len >= rampLength) {
+                const t = len > 0 ? (rampLength - travelled) / len : 1;
+                const px = ax + (bx - ax) * t;
+                const py = ay + (by - ay) * t;
+                this.lines.push(`G1 X${this.fmt(px)} Y${this.fmt(py)} Z${this.fmt(z)}`);
+                this.currentX = px;
+                this.currentY = py;
+                this.currentZ = z;
+                this.tracePath([px, py, ...points.slice(2*j+2), ...points.slice(2, 2*j+2), px, py]);
+                return;
+            }
+
+            travelled += len;
+            const rampZ = fromZ - (fromZ - z) * travelled / rampLength;
+            this.lines.push(`G1 X${this.fmt(bx)} Y${this.fmt(by)} Z${this.fmt(rampZ)}`);
+            this.currentX = bx;
+            this.currentY = by;
+            this.currentZ = rampZ;
+            j = (j + 1) % segCount;
+        }
+    }
+
+    // Fits G2/G3 arcs to a sequence of points (plain G1 segments when arcs are disabled)
     public tracePath(points: number[]) {
         if (points.length < 2) return;
 
@@ -76,15 +144,20 @@ class GCodeGenerator {
         let i = 0;
         // Process segments
         while (i < points.length - 2) { 
-            const arc = this.fitArc(points, i);
+            const arc = this.arcMode === "None" ? null : this.fitArc(points, i);
             
             if (arc) {
                 const cmd = arc.direction === 'CW' ? 'G2' : 'G3';
-                // I, J are relative to start point
-                const I = arc.center.x - points[i];
-                const J = arc.center.y - points[i+1];
-                
-                this.lines.push(`${cmd} X${this.fmt(arc.end.x)} Y${this.fmt(arc.end.y)} I${this.fmt(I)} J${this.fmt(J)}`);
+                const target = `${cmd} X${this.fmt(arc.end.x)} Y${this.fmt(arc.end.y)}`;
+                if (this.arcMode === "R") {
+                    // Sweep is kept under 180 degrees, so R is always positive
+                    this.lines.push(`${target} R${this.fmt(arc.radius)}`);
+                } else {
+                    // I, J are relative to start point
+                    const I = arc.center.x - points[i];
+                    const J = arc.center.y - points[i+1];
+                    this.lines.push(`${target} I${this.fmt(I)} J${this.fmt(J)}`);
+                }
                 
                 this.currentX = arc.end.x;
                 this.currentY = arc.end.y;
@@ -100,7 +173,7 @@ class GCodeGenerator {
         }
     }
 
-    private fitArc(points: number[], startIndex: number): { center: {x:number, y:number}, end: {x:number, y:number}, nextIndex: number, direction: 'CW' | 'CCW' } | null {
+    private fitArc(points: number[], startIndex: number): { center: {x:number, y:number}, radius: number, end: {x:number, y:number}, nextIndex: number, direction: 'CW' | 'CCW' } | null {
         if (startIndex + 5 >= points.length) return null;
 
         const p1 = { x: points[startIndex], y: points[startIndex+1] };
@@ -114,26 +187,44 @@ class GCodeGenerator {
         if (circle.r > 10000 || circle.r < 0.1) return null;
 
         const tol = 0.02; // Tolerance for fitting
-        if (!this.isPointOnCircle(p2, circle, tol)) return null;
 
-        let lastValidIndex = startIndex + 4;
-        
-        // Greedily consume points that fit the arc
-        for (let k = startIndex + 6; k < points.length; k += 2) {
-            const pNext = { x: points[k], y: points[k+1] };
-            if (this.isPointOnCircle(pNext, circle, tol)) {
-                lastValidIndex = k;
-            } else {
-                break;
-            }
-        }
-        
         // Vector Cross Product to determine direction
         const cross = (p2.x - p1.x) * (p3.y - p1.y) - (p2.y - p1.y) * (p3.x - p1.x);
         const direction = cross < 0 ? 'CW' : 'CCW';
+        const sign = cross < 0 ? -1 : 1;
+
+        // R words are ambiguous from 180 degrees on; IJK stops short of a full turn
+        const maxSweep = this.arcMode === "R" ? Math.PI - 1e-3 : 2 * Math.PI - 1e-3;
+
+        let lastValidIndex = startIndex;
+        let sweep = 0;
+        let prevAngle = Math.atan2(p1.y - circle.y, p1.x - circle.x);
+
+        // Greedily consume points that fit the arc. Vertices alone are not enough (a rectangle's
+        // corners are concyclic), so every chord must also stay within tolerance of the circle.
+        for (let k = startIndex + 2; k < points.length; k += 2) {
+            const pPrev = { x: points[k-2], y: points[k-1] };
+            const pNext = { x: points[k], y: points[k+1] };
+            if (!this.isPointOnCircle(pNext, circle, tol)) break;
+
+            const chord = Math.sqrt((pNext.x - pPrev.x)**2 + (pNext.y - pPrev.y)**2);
+            if (chord > 2 * circle.r || circle.r - Math.sqrt(circle.r**2 - chord**2 / 4) > tol) break;
+
+            const angle = Math.atan2(pNext.y - circle.y, pNext.x - circle.x);
+            let delta = angle - prevAngle;
+            if (delta > Math.PI) delta -= 2 * Math.PI;
+            if (delta <= -Math.PI) delta += 2 * Math.PI;
+            if (delta * sign <= 0 || Math.abs(sweep + delta) > maxSweep) break;
+
+            sweep += delta;
+            prevAngle = angle;
+            lastValidIndex = k;
+        }
+        if (lastValidIndex < startIndex + 4) return null;
 
         return {
             center: { x: circle.x, y: circle.y },
+            radius: circle.r,
             end: { x: points[lastValidIndex], y: points[lastValidIndex+1] },
             nextIndex: lastValidIndex,
             direction
@@ -178,7 +269,7 @@ function parseGCodeToMeshLines(gcode: string): number[][] {
     let lastMode = 'G0';
     
     const lines = gcode.split('\n');
-    const reCmd = /([GXYZIJ])([-\d\.]+)/g;
+    const reCmd = /([GXYZIJR])([-\d\.]+)/g;
     
     const finishPoly = () => {
         if (currentPoly.length > 0) {
@@ -240,11 +331,23 @@ function parseGCodeToMeshLines(gcode: string): number[][] {
         }
         else if (moveType === 'G2' || moveType === 'G3') {
              // Arc Interpolation
-             const I = params.I || 0;
-             const J = params.J || 0;
-             const cx = x + I;
-             const cy = y + J;
-             const r = Math.sqrt(I*I + J*J);
+             let cx: number, cy: number, r: number;
+             if (params.R !== undefined && params.I === undefined && params.J === undefined) {
+                 // Radius form: centre sits on the chord's bisector, left of travel for G3 (right for G2); negative R takes the long way
+                 const dx = targetX - x, dy = targetY - y;
+                 const d = Math.sqrt(dx*dx + dy*dy) || 1e-9;
+                 r = Math.abs(params.R);
+                 const h = Math.sqrt(Math.max(0, r*r - d*d/4));
+                 const side = (moveType === 'G3' ? 1 : -1) * (params.R < 0 ? -1 : 1);
+                 cx = (x + targetX) / 2 - side * h * dy / d;
+                 cy = (y + targetY) / 2 + side * h * dx / d;
+             } else {
+                 const I = params.I || 0;
+                 const J = params.J || 0;
+                 cx = x + I;
+                 cy = y + J;
+                 r = Math.sqrt(I*I + J*J);
+             }
              
              const startAngle = Math.atan2(y - cy, x - cx);
              const endAngle = Math.atan2(targetY - cy, targetX - cx);
@@ -281,11 +384,22 @@ function parseGCodeToMeshLines(gcode: string): number[][] {
     return segments;
 }
 
+// Depths (from the top) of successive passes down to totalDepth, each at most passDepth deeper than the last
+function passLevels(totalDepth: number, passDepth: number): number[] {
+    const levels: number[] = [];
+    let current = 0;
+    while (current < totalDepth - 0.001) {
+        current = Math.min(current + passDepth, totalDepth);
+        levels.push(current);
+    }
+    return levels;
+}
+
 // --- MAIN WORKER ---
 
 export function computeToolpath(id: string, payload: any, manifoldModule: any) {
     if (!manifoldModule) throw new Error("Manifold not initialized");
-    const { shapes, params, contextFp, allFootprints, settings, layerThickness, layerId, bottomZ, resolution = 32 } = payload;
+    const { shapes, params, contextFp, allFootprints, settings, layerThickness, layerId, bottomZ, resolution = 32, materialPassDepth } = payload;
     const { CrossSection } = manifoldModule;
     const garbage: any[] = [];
     const collect = <T>(obj: T): T => { if(obj && (obj as any).delete) garbage.push(obj); return obj; };
@@ -293,7 +407,9 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
     // 1. Settings & Generator Initialization
     const toolDiameter = evaluateExpression(settings.toolDiameterExpression, params);
     const toolRadius = toolDiameter / 2;
-    const stepDown = Math.max(0.1, evaluateExpression(settings.stepDownExpression, params));
+    // The machine's step-down is capped by the material's maximum pass depth, when it has one
+    const stepDown = Math.max(0.1, Math.min(evaluateExpression(settings.stepDownExpression, params), materialPassDepth ?? Infinity));
+    const rampAngle = settings.entry === "Ramp" ? evaluateExpression(settings.rampAngleExpression || "3", params) : 0;
     const stepOverRaw = evaluateExpression(settings.stepOverExpression, params);
     const stockDepthRaw = evaluateExpression(settings.stockDepthExpression, params);
     
@@ -308,12 +424,67 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
     const spindleRpm = evaluateExpression(settings.spindleRpmExpression, params) || 12000;
     const plungeRate = feedRate * 0.4;
     const safeHeight = 5.0;
+
+    // Roughing leaves `allowance` on walls and floors for a full-depth finishing pass
+    const finishing = settings.finishing || "None";
+    const allowance = finishing === "None" ? 0 : Math.max(0, evaluateExpression(settings.stockAllowanceExpression || "0.2", params));
+    const finishToolDiameter = finishing === "Separate tool"
+        ? evaluateExpression(settings.finishToolDiameterExpression || settings.toolDiameterExpression, params)
+        : toolDiameter;
+    const finishToolRadius = finishToolDiameter / 2;
+    const finishStepOver = Math.min(stepOverRaw, finishToolDiameter * 0.95);
     
     // Visualization Z Levels (Absolute)
     const visStockTop = localStockTopZ + bottomZ;
     const visSafeZ = visStockTop + safeHeight;
 
-    const gcode = new GCodeGenerator(visSafeZ, spindleRpm, feedRate, plungeRate);
+    const gcode = new GCodeGenerator(visSafeZ, spindleRpm, feedRate, plungeRate, settings.arcMode || "IJK");
+    // A separate finishing tool gets its own program; otherwise finishing is a labeled section of this one
+    const finishGcode = finishing === "Separate tool"
+        ? new GCodeGenerator(visSafeZ, spindleRpm, feedRate, plungeRate, settings.arcMode || "IJK")
+        : gcode;
+    if (finishing !== "None") {
+        gcode.addComment(`Section: Roughing (tool ${toolDiameter} mm, leaves ${allowance} mm)`);
+    }
+
+    // Cuts one closed contour at cutZ, entering from the previous pass floor at fromZ
+    const cutContour = (flatPoly: number[], fromZ: number, cutZ: number, gen: GCodeGenerator = gcode) => {
+        if (rampAngle > 0) {
+            gen.rampAlongPath(flatPoly, fromZ, cutZ, rampAngle);
+        } else {
+            gen.rapidTo(flatPoly[0], flatPoly[1]);
+            gen.plunge(cutZ);
+            gen.tracePath(flatPoly);
+        }
+    };
+
+    // Cuts the contours from fromZ down to cutZ in passes of at most stepDown, like roughing
+    const stepDownContours = (contours: number[][], fromZ: number, cutZ: number, gen: GCodeGenerator = gcode) => {
+        let prevZ = fromZ;
+        passLevels(fromZ - cutZ, stepDown).forEach(depth => {
+            const passZ = fromZ - depth;
+            contours.forEach(flatPoly => cutContour(flatPoly, prevZ, passZ, gen));
+            prevZ = passZ;
+        });
+    };
+
+    // Closed contours of `cs` inset by `first`, then every `step` further in until nothing is left (just the first without a step)
+    const insetContours = (cs: any, first: number, step?: number): number[][] => {
+        const contours: number[][] = [];
+        let current = collect(cs.offset(-first, "Round"));
+        while (!current.isEmpty()) {
+            current.toPolygons().forEach((poly: number[][]) => {
+                if (poly.length > 2) {
+                    const p = [...poly];
+                    if (Math.abs(p[0][0] - p[p.length-1][0]) > 1e-6 || Math.abs(p[0][1] - p[p.length-1][1]) > 1e-6) p.push(p[0]);
+                    contours.push(p.flat());
+                }
+            });
+            if (step === undefined) break;
+            current = collect(current.offset(-step, "Round"));
+        }
+        return contours;
+    };
 
     // 2. Flatten and Resolve Shapes
     const flatShapes = flattenShapes(contextFp, contextFp, shapes, allFootprints, params);
@@ -377,7 +548,6 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
             surfCS = collect(surfCS.translate([(minX + maxX)/2, (minY + maxY)/2]));
         }
 
-        let currentSurfZ = 0; 
         const totalSurfDepth = localStockTopZ - localLayerTopZ;
         
         // Generate surfacing contours
@@ -396,23 +566,19 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
             surfOffset = collect(surfOffset.offset(-stepOver, "Round"));
         }
         
-        while (currentSurfZ < totalSurfDepth - 0.001) {
-            currentSurfZ += stepDown;
-            if (currentSurfZ > totalSurfDepth) currentSurfZ = totalSurfDepth;
-            
+        let prevSurfZ = visStockTop;
+        passLevels(totalSurfDepth, stepDown).forEach(currentSurfZ => {
             const localZ = localStockTopZ - currentSurfZ;
             const cutZ = localZ + bottomZ;
             
-            surfContours.forEach(flatPoly => {
-                gcode.rapidTo(flatPoly[0], flatPoly[1]);
-                gcode.plunge(cutZ);
-                gcode.tracePath(flatPoly);
-            });
-        }
+            surfContours.forEach(flatPoly => cutContour(flatPoly, prevSurfZ, cutZ));
+            prevSurfZ = cutZ;
+        });
     }
     
     // 3. Pocketing Regions
-    interface Region { cs: any; depth: number; }
+    // feed/rpm carry the shape's overrides; pockets cut by several shapes keep each part's own
+    interface Region { cs: any; depth: number; feed: number; rpm: number; }
     let regions: Region[] = [];
 
     gcode.addComment("Operation: Pocketing Features");
@@ -426,6 +592,13 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
         const targetDepth = Math.min(layerThickness, evaluateExpression(depthExpr, params));
         
         if (targetDepth <= 0.001) return;
+
+        const overrideOf = (expr: string | undefined, fallback: number) => {
+            const val = expr && expr.trim() !== "" ? evaluateExpression(expr, params) : 0;
+            return val > 0 ? val : fallback;
+        };
+        const regionFeed = typeof assignment === "object" ? overrideOf(assignment.feedOverride, feedRate) : feedRate;
+        const regionRpm = typeof assignment === "object" ? overrideOf(assignment.speedOverride, spindleRpm) : spindleRpm;
         
         let shapeCS: any = null;
         const itemTransform = { x: item.relativeTransform.x, y: item.relativeTransform.y, angle: item.relativeTransform.rotation };
@@ -458,17 +631,18 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
             regions.forEach(r => {
                 const diff = collect(r.cs.subtract(shapeCS));
                 if (!diff.isEmpty()) {
-                    nextRegions.push({ cs: diff, depth: r.depth });
+                    nextRegions.push({ cs: diff, depth: r.depth, feed: r.feed, rpm: r.rpm });
                 }
             });
-            nextRegions.push({ cs: collect(shapeCS), depth: targetDepth });
+            nextRegions.push({ cs: collect(shapeCS), depth: targetDepth, feed: regionFeed, rpm: regionRpm });
             regions = nextRegions;
         }
     });
 
     regions.forEach(reg => {
-        const pocketBounds = collect(reg.cs.offset(-toolRadius, "Round"));
-        if (pocketBounds.isEmpty()) return;
+        const pocketBounds = collect(reg.cs.offset(-(toolRadius + allowance), "Round"));
+        const roughDepth = reg.depth - allowance;
+        if (pocketBounds.isEmpty() || roughDepth <= 0.001) return;
 
         let currentOffset = pocketBounds;
         const pocketPaths: number[][][] = []; 
@@ -488,24 +662,32 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
             currentOffset = collect(nextOffset);
         }
 
-        let currentZ = 0;
-        while (currentZ < reg.depth - 0.001) {
-            currentZ += stepDown;
-            if (currentZ > reg.depth) currentZ = reg.depth;
+        if (reg.feed !== feedRate || reg.rpm !== spindleRpm) {
+            gcode.addComment(`Override: F${reg.feed} S${Math.round(reg.rpm)}`);
+        }
+        gcode.setSpindle(reg.rpm);
+        gcode.setFeed(reg.feed);
+
+        let prevZ = localLayerTopZ + bottomZ;
+        passLevels(roughDepth, stepDown).forEach(currentZ => {
             const localZ = localLayerTopZ - currentZ;
             const cutZ = localZ + bottomZ;
             
             pocketPaths.forEach(contours => {
-                contours.forEach(flatPoly => {
-                    gcode.rapidTo(flatPoly[0], flatPoly[1]);
-                    gcode.plunge(cutZ);
-                    gcode.tracePath(flatPoly);
-                });
+                contours.forEach(flatPoly => cutContour(flatPoly, prevZ, cutZ));
             });
-        }
+            prevZ = cutZ;
+        });
     });
 
+    // Back to the layer's settings for anything after the pockets
+    gcode.setSpindle(spindleRpm);
+    gcode.setFeed(feedRate);
+
     // 4. Board Cutout / Moat
+    let boardOutlineCS: any = null;
+    const moatDepth = layerThickness + 0.5;
+    let moatFloorZ = localLayerTopZ + bottomZ; // Where roughing left the moat
     if (contextFp.isBoard) {
         gcode.addComment("Operation: Board Cutout");
         const assignments = contextFp.boardOutlineAssignments || {};
@@ -523,10 +705,12 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
             
             if (absPts.length > 2) {
                 outlineCS = collect(new CrossSection([absPts], "EvenOdd"));
-                const moatMax = Math.max(toolRadius, chuckRadius + 2.0);
+                boardOutlineCS = outlineCS;
+                const moatMin = toolRadius + allowance;
+                const moatMax = Math.max(moatMin, chuckRadius + 2.0);
                 const moatContours: number[][] = [];
                 
-                let currentOffset = toolRadius;
+                let currentOffset = moatMin;
                 while(currentOffset <= moatMax + 0.001) {
                     const offCS = collect(outlineCS.offset(currentOffset, "Round"));
                     const offPolys = offCS.toPolygons();
@@ -542,28 +726,54 @@ export function computeToolpath(id: string, payload: any, manifoldModule: any) {
                     if (currentOffset > moatMax) currentOffset = moatMax;
                 }
 
-                let currentZ = 0;
-                const targetD = layerThickness + 0.5; 
-                while (currentZ < targetD - 0.001) {
-                    currentZ += stepDown;
-                    if (currentZ > targetD) currentZ = targetD;
+                // With finishing, roughing stops short of the bottom so the part stays held by a skin
+                const targetD = finishing === "None" ? moatDepth : layerThickness - allowance;
+                let prevZ = localLayerTopZ + bottomZ;
+                passLevels(targetD, stepDown).forEach(currentZ => {
                     const localZ = localLayerTopZ - currentZ;
                     const cutZ = localZ + bottomZ;
 
-                    moatContours.forEach(flatPoly => {
-                         gcode.rapidTo(flatPoly[0], flatPoly[1]);
-                         gcode.plunge(cutZ);
-                         gcode.tracePath(flatPoly);
-                    });
-                }
+                    moatContours.forEach(flatPoly => cutContour(flatPoly, prevZ, cutZ));
+                    prevZ = cutZ;
+                });
+                moatFloorZ = prevZ;
             }
         }
     }
 
-    const outputGCode = gcode.getGCode();
+    // 5. Finishing: pocket floors and walls, then the board contour, down to full depth
+    if (finishing !== "None") {
+        finishGcode.addComment(`Section: Finishing (tool ${finishToolDiameter} mm, full depth)`);
+        const topZ = localLayerTopZ + bottomZ;
+
+        regions.forEach(reg => {
+            const contours = insetContours(reg.cs, finishToolRadius, finishStepOver);
+            if (contours.length === 0) return;
+            finishGcode.setSpindle(reg.rpm);
+            finishGcode.setFeed(reg.feed);
+            const cutZ = localLayerTopZ - reg.depth + bottomZ;
+            // Start from the roughed floor, or from the top where the roughing tool did not fit
+            const roughed = reg.depth - allowance > 0.001 && !collect(reg.cs.offset(-(toolRadius + allowance), "Round")).isEmpty();
+            const fromZ = roughed ? cutZ + allowance : topZ;
+            stepDownContours(contours, fromZ, cutZ, finishGcode);
+        });
+        finishGcode.setSpindle(spindleRpm);
+        finishGcode.setFeed(feedRate);
+
+        if (boardOutlineCS) {
+            const finalCS = collect(boardOutlineCS.offset(finishToolRadius, "Round"));
+            const cutZ = localLayerTopZ - moatDepth + bottomZ;
+            stepDownContours(insetContours(finalCS, 0), moatFloorZ, cutZ, finishGcode);
+        }
+    }
+
+    // One program per tool; the file name suffix tells them apart on export
+    const programs = finishGcode === gcode
+        ? [{ name: "", gcode: gcode.getGCode() }]
+        : [{ name: "rough", gcode: gcode.getGCode() }, { name: "finish", gcode: finishGcode.getGCode() }];
     // Parse the generated G-code back into visual mesh lines to debug that the GCode looks correct
-    const toolpaths = parseGCodeToMeshLines(outputGCode);
+    const toolpaths = programs.flatMap(p => parseGCodeToMeshLines(p.gcode));
 
-    self.postMessage({ id, type: "success", payload: { toolpaths, gcode: outputGCode } });
+    self.postMessage({ id, type: "success", payload: { toolpaths, gcode: programs.map(p => p.gcode).join("\n"), programs } });
     garbage.forEach(g => { try { g.delete(); } catch(e) {} });
 }
\ No newline at end of file